@@ -17,7 +17,7 @@ pub enum ChangeType {
     RemovedTrailingWhitespace,
 
     /// Empty line(s) at the end of file were removed.
-    RemovedEmptyLines,
+    RemovedTrailingEmptyLines,
 
     /// An empty file was replaced by a file consisting of single empty line.
     ReplacedEmptyFileWithOneLine,
@@ -28,20 +28,137 @@ pub enum ChangeType {
     /// A file consisting of only whitespace was replaced by a file consisting of single empty line.
     ReplacedWhiteSpaceOnlyFileWithOneLine,
 
-    /// A tab character was replaces by space character(s).
-    ReplacedTabWithSpaces,
+    /// A tab character was replaced by the given number of space characters.
+    ReplacedTabWithSpaces(usize),
 
     /// A tab character was removed.
     RemovedTab,
 
+    /// Tabs in the indentation of a line were replaced by spaces.
+    ExpandedIndentationTabs,
+
     /// A non-standard whitespace character (`\f` or `\v`) was replaced by a space character.
     ReplacedNonstandardWhitespaceBySpace(u8),
 
     /// A non-standard whitespace character (`\f` or `\v`) was removed.
     RemovedNonstandardWhitespace(u8),
+
+    /// A Unicode whitespace character (e.g. U+00A0) was replaced by a space character.
+    ReplacedUnicodeWhitespaceBySpace(char),
+
+    /// A Unicode whitespace character (e.g. U+00A0) was removed.
+    RemovedUnicodeWhitespace(char),
+
+    /// Unicode line separator (U+2028) or paragraph separator (U+2029)
+    /// was replaced by a new line marker.
+    ReplacedUnicodeLineSeparator(char, NewLineMarker),
+
+    /// New line markers at the end of the file were replaced by exactly
+    /// the given number of new line markers.
+    EnsuredTrailingNewLineMarkers(usize),
+
+    /// The byte order mark was removed from the beginning of the file.
+    RemovedByteOrderMark,
+
+    /// The byte order mark was added to the beginning of the file (because it was missing).
+    AddedByteOrderMark,
 }
 
 impl ChangeType {
+    /// Stable machine-readable name of the change.
+    /// Unlike `to_string`, the name does not depend on the mode of operation.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeType::NewLineMarkerAddedToEndOfFile => "NewLineMarkerAddedToEndOfFile",
+            ChangeType::NewLineMarkerRemovedFromEndOfFile => "NewLineMarkerRemovedFromEndOfFile",
+            ChangeType::ReplacedNewLineMarker(_, _) => "ReplacedNewLineMarker",
+            ChangeType::RemovedTrailingWhitespace => "RemovedTrailingWhitespace",
+            ChangeType::RemovedTrailingEmptyLines => "RemovedTrailingEmptyLines",
+            ChangeType::ReplacedEmptyFileWithOneLine => "ReplacedEmptyFileWithOneLine",
+            ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile => {
+                "ReplacedWhiteSpaceOnlyFileWithEmptyFile"
+            }
+            ChangeType::ReplacedWhiteSpaceOnlyFileWithOneLine => {
+                "ReplacedWhiteSpaceOnlyFileWithOneLine"
+            }
+            ChangeType::ReplacedTabWithSpaces(_) => "ReplacedTabWithSpaces",
+            ChangeType::RemovedTab => "RemovedTab",
+            ChangeType::ExpandedIndentationTabs => "ExpandedIndentationTabs",
+            ChangeType::ReplacedNonstandardWhitespaceBySpace(_) => {
+                "ReplacedNonstandardWhitespaceBySpace"
+            }
+            ChangeType::RemovedNonstandardWhitespace(_) => "RemovedNonstandardWhitespace",
+            ChangeType::ReplacedUnicodeWhitespaceBySpace(_) => "ReplacedUnicodeWhitespaceBySpace",
+            ChangeType::RemovedUnicodeWhitespace(_) => "RemovedUnicodeWhitespace",
+            ChangeType::ReplacedUnicodeLineSeparator(_, _) => "ReplacedUnicodeLineSeparator",
+            ChangeType::EnsuredTrailingNewLineMarkers(_) => "EnsuredTrailingNewLineMarkers",
+            ChangeType::RemovedByteOrderMark => "RemovedByteOrderMark",
+            ChangeType::AddedByteOrderMark => "AddedByteOrderMark",
+        }
+    }
+
+    /// Human-readable category of the change for the summary of a run,
+    /// e.g. `3 tab replacement(s)`. Related types of changes can share a category.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChangeType::NewLineMarkerAddedToEndOfFile => "added final new line marker(s)",
+            ChangeType::NewLineMarkerRemovedFromEndOfFile => "removed final new line marker(s)",
+            ChangeType::ReplacedNewLineMarker(_, _) => "new line marker replacement(s)",
+            ChangeType::RemovedTrailingWhitespace => "trailing whitespace",
+            ChangeType::RemovedTrailingEmptyLines => "trailing empty line removal(s)",
+            ChangeType::ReplacedEmptyFileWithOneLine => "empty file replacement(s)",
+            ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile
+            | ChangeType::ReplacedWhiteSpaceOnlyFileWithOneLine => {
+                "whitespace-only file replacement(s)"
+            }
+            ChangeType::ReplacedTabWithSpaces(_) => "tab replacement(s)",
+            ChangeType::RemovedTab => "tab removal(s)",
+            ChangeType::ExpandedIndentationTabs => "expanded indentation(s)",
+            ChangeType::ReplacedNonstandardWhitespaceBySpace(_) => {
+                "non-standard whitespace replacement(s)"
+            }
+            ChangeType::RemovedNonstandardWhitespace(_) => "non-standard whitespace removal(s)",
+            ChangeType::ReplacedUnicodeWhitespaceBySpace(_) => "Unicode whitespace replacement(s)",
+            ChangeType::RemovedUnicodeWhitespace(_) => "Unicode whitespace removal(s)",
+            ChangeType::ReplacedUnicodeLineSeparator(_, _) => {
+                "Unicode line separator replacement(s)"
+            }
+            ChangeType::EnsuredTrailingNewLineMarkers(_) => "adjusted trailing new line marker(s)",
+            ChangeType::RemovedByteOrderMark => "removed byte order mark(s)",
+            ChangeType::AddedByteOrderMark => "added byte order mark(s)",
+        }
+    }
+
+    /// Command line option responsible for the change.
+    pub fn option(&self) -> &'static str {
+        match self {
+            ChangeType::NewLineMarkerAddedToEndOfFile => "--add-new-line-marker-at-end-of-file",
+            ChangeType::NewLineMarkerRemovedFromEndOfFile => {
+                "--remove-new-line-marker-from-end-of-file"
+            }
+            ChangeType::ReplacedNewLineMarker(_, _) => "--normalize-new-line-markers",
+            ChangeType::RemovedTrailingWhitespace => "--remove-trailing-whitespace",
+            ChangeType::RemovedTrailingEmptyLines => "--remove-trailing-empty-lines",
+            ChangeType::ReplacedEmptyFileWithOneLine => "--normalize-empty-files",
+            ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile
+            | ChangeType::ReplacedWhiteSpaceOnlyFileWithOneLine => {
+                "--normalize-whitespace-only-files"
+            }
+            ChangeType::ReplacedTabWithSpaces(_) | ChangeType::RemovedTab => {
+                "--replace-tabs-with-spaces"
+            }
+            ChangeType::ExpandedIndentationTabs => "--retab",
+            ChangeType::ReplacedNonstandardWhitespaceBySpace(_)
+            | ChangeType::RemovedNonstandardWhitespace(_) => "--normalize-non-standard-whitespace",
+            ChangeType::ReplacedUnicodeWhitespaceBySpace(_)
+            | ChangeType::RemovedUnicodeWhitespace(_) => "--normalize-unicode-whitespace",
+            ChangeType::ReplacedUnicodeLineSeparator(_, _) => "--normalize-unicode-line-separators",
+            ChangeType::EnsuredTrailingNewLineMarkers(_) => "--ensure-trailing-newlines",
+            ChangeType::RemovedByteOrderMark => "--strip-bom",
+            ChangeType::AddedByteOrderMark => "--add-bom",
+        }
+    }
+
     /// Human-readable representation of the change.
     pub fn to_string(&self, check_only: bool) -> String {
         let check_only_word = if check_only { " would be " } else { " " };
@@ -67,7 +184,7 @@ impl ChangeType {
             ChangeType::RemovedTrailingWhitespace => {
                 format!("Trailing whitespace{}removed.", check_only_word)
             }
-            ChangeType::RemovedEmptyLines => {
+            ChangeType::RemovedTrailingEmptyLines => {
                 format!(
                     "Empty line(s) at the end of the file{}removed.",
                     check_only_word
@@ -85,12 +202,21 @@ impl ChangeType {
             ChangeType::ReplacedWhiteSpaceOnlyFileWithOneLine => {
                 format!("File{}replaced with a single empty line.", check_only_word)
             }
-            ChangeType::ReplacedTabWithSpaces => {
-                format!("Tab{}replaced with spaces.", check_only_word)
+            ChangeType::ReplacedTabWithSpaces(number_of_spaces) => {
+                format!(
+                    "Tab{}replaced with {} space(s).",
+                    check_only_word, number_of_spaces
+                )
             }
             ChangeType::RemovedTab => {
                 format!("Tab{}removed.", check_only_word)
             }
+            ChangeType::ExpandedIndentationTabs => {
+                format!(
+                    "Tabs in the indentation{}replaced with spaces.",
+                    check_only_word
+                )
+            }
             ChangeType::ReplacedNonstandardWhitespaceBySpace(char) => {
                 format!(
                     "Non-standard whitespace character '{}'{}replaced by a space.",
@@ -105,15 +231,50 @@ impl ChangeType {
                     check_only_word
                 )
             }
+            ChangeType::ReplacedUnicodeWhitespaceBySpace(char) => {
+                format!(
+                    "Unicode whitespace character U+{:04X}{}replaced by a space.",
+                    *char as u32, check_only_word
+                )
+            }
+            ChangeType::RemovedUnicodeWhitespace(char) => {
+                format!(
+                    "Unicode whitespace character U+{:04X}{}removed.",
+                    *char as u32, check_only_word
+                )
+            }
+            ChangeType::ReplacedUnicodeLineSeparator(separator, new) => {
+                format!(
+                    "Unicode line separator U+{:04X}{}replaced by '{}'.",
+                    *separator as u32, check_only_word, new
+                )
+            }
+            ChangeType::EnsuredTrailingNewLineMarkers(number_of_new_line_markers) => {
+                format!(
+                    "End of the file{}changed to exactly {} new line marker(s).",
+                    check_only_word, number_of_new_line_markers
+                )
+            }
+            ChangeType::RemovedByteOrderMark => {
+                format!("Byte order mark{}removed.", check_only_word)
+            }
+            ChangeType::AddedByteOrderMark => {
+                format!("Byte order mark{}added.", check_only_word)
+            }
         }
     }
 }
 
 /// A formatting change that was made or would be made to a file.
-/// The location of the change is identified by its line number.
+/// The location of the change is identified by its line number and,
+/// for changes within a line, by its column.
 #[derive(PartialEq, Debug)]
 pub struct Change {
     line_number: usize,
+
+    /// 1-based byte column within the line in the original file.
+    column: Option<usize>,
+
     change_type: ChangeType,
 }
 
@@ -122,17 +283,53 @@ impl Change {
     pub fn new(line_number: usize, change_type: ChangeType) -> Change {
         Change {
             line_number,
+            column: None,
             change_type,
         }
     }
 
+    /// Sets the column of the change.
+    pub fn with_column(mut self, column: usize) -> Change {
+        self.column = Some(column);
+        self
+    }
+
+    /// Moves the change the given number of lines down.
+    pub fn with_line_offset(mut self, number_of_lines: usize) -> Change {
+        self.line_number += number_of_lines;
+        self
+    }
+
+    /// Getter.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Getter.
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    /// Getter.
+    pub fn change_type(&self) -> &ChangeType {
+        &self.change_type
+    }
+
     /// Human-readable representation of the change
     pub fn to_string(&self, check_only: bool) -> String {
-        format!(
-            "line {}: {}",
-            self.line_number,
-            self.change_type.to_string(check_only)
-        )
+        match self.column {
+            Some(column) => format!(
+                "line {}, col {}: {}",
+                self.line_number,
+                column,
+                self.change_type.to_string(check_only)
+            ),
+            None => format!(
+                "line {}: {}",
+                self.line_number,
+                self.change_type.to_string(check_only)
+            ),
+        }
     }
 }
 
@@ -156,6 +353,15 @@ mod tests {
             "line 2: New line marker '\\r\\n' replaced by '\\n'."
         );
 
+        assert_eq!(
+            Change::new(
+                3,
+                ChangeType::ReplacedUnicodeLineSeparator('\u{2029}', NewLineMarker::Windows)
+            )
+            .to_string(true),
+            "line 3: Unicode line separator U+2029 would be replaced by '\\r\\n'."
+        );
+
         assert_eq!(
             Change::new(3, ChangeType::ReplacedNonstandardWhitespaceBySpace(0x0B)).to_string(false),
             "line 3: Non-standard whitespace character '\\v' replaced by a space."
@@ -165,5 +371,74 @@ mod tests {
             Change::new(4, ChangeType::RemovedNonstandardWhitespace(0x0C)).to_string(false),
             "line 4: Non-standard whitespace character '\\f' removed."
         );
+
+        assert_eq!(
+            Change::new(2, ChangeType::ReplacedUnicodeWhitespaceBySpace('\u{A0}'))
+                .with_column(3)
+                .to_string(false),
+            "line 2, col 3: Unicode whitespace character U+00A0 replaced by a space."
+        );
+
+        assert_eq!(
+            Change::new(5, ChangeType::RemovedTrailingWhitespace)
+                .with_column(12)
+                .to_string(true),
+            "line 5, col 12: Trailing whitespace would be removed."
+        );
+
+        assert_eq!(
+            Change::new(6, ChangeType::ReplacedTabWithSpaces(4))
+                .with_column(1)
+                .to_string(false),
+            "line 6, col 1: Tab replaced with 4 space(s)."
+        );
+    }
+
+    #[test]
+    fn test_change_type_name() {
+        assert_eq!(
+            ChangeType::RemovedTrailingWhitespace.name(),
+            "RemovedTrailingWhitespace"
+        );
+        assert_eq!(
+            ChangeType::ReplacedNewLineMarker(NewLineMarker::Windows, NewLineMarker::Linux).name(),
+            "ReplacedNewLineMarker"
+        );
+        assert_eq!(
+            ChangeType::RemovedNonstandardWhitespace(0x0C).name(),
+            "RemovedNonstandardWhitespace"
+        );
+    }
+
+    #[test]
+    fn test_change_type_label() {
+        assert_eq!(
+            ChangeType::RemovedTrailingWhitespace.label(),
+            "trailing whitespace"
+        );
+        assert_eq!(
+            ChangeType::ReplacedTabWithSpaces(4).label(),
+            "tab replacement(s)"
+        );
+        assert_eq!(
+            ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile.label(),
+            ChangeType::ReplacedWhiteSpaceOnlyFileWithOneLine.label()
+        );
+    }
+
+    #[test]
+    fn test_change_type_option() {
+        assert_eq!(
+            ChangeType::RemovedTrailingWhitespace.option(),
+            "--remove-trailing-whitespace"
+        );
+        assert_eq!(
+            ChangeType::ReplacedTabWithSpaces(4).option(),
+            ChangeType::RemovedTab.option()
+        );
+        assert_eq!(
+            ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile.option(),
+            "--normalize-whitespace-only-files"
+        );
     }
 }
@@ -1,11 +1,88 @@
 // Library imports
 use clap::error::ErrorKind;
 use clap::CommandFactory;
+use serde::Deserialize;
+use serde::Serialize;
 use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 
 /// A regular expression that does not match any string.
 pub const UNMATCHABLE_REGEX: &str = "$.";
 
+/// Regular expression that is matched against the content of files.
+/// It is serialized as the string of the regular expression.
+#[derive(Clone, Debug)]
+pub struct ContentPattern(regex::bytes::Regex);
+
+impl ContentPattern {
+    /// Determines if the regular expression matches somewhere in the data.
+    pub fn is_match(&self, data: &[u8]) -> bool {
+        self.0.is_match(data)
+    }
+}
+
+impl PartialEq for ContentPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl std::str::FromStr for ContentPattern {
+    type Err = regex::Error;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        regex::bytes::Regex::new(pattern).map(ContentPattern)
+    }
+}
+
+impl Serialize for ContentPattern {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ContentPattern {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        pattern.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Inclusive range of 1-based line numbers, given as `START:END`.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    /// Determines if the line is in the range.
+    pub fn contains(&self, line_number: usize) -> bool {
+        self.start <= line_number && line_number <= self.end
+    }
+}
+
+impl std::str::FromStr for LineRange {
+    type Err = String;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let (start, end) = range
+            .split_once(':')
+            .ok_or("expected START:END, e.g. 10:20")?;
+        let parse = |number: &str| {
+            number
+                .parse::<usize>()
+                .map_err(|_| format!("invalid line number '{}'", number))
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start == 0 || start > end {
+            return Err("line numbers start at 1 and START must not exceed END".to_string());
+        }
+        Ok(LineRange { start, end })
+    }
+}
+
 /// Color mode.
 #[derive(clap::ValueEnum, Clone, PartialEq, Debug, Default)]
 pub enum ColoredOutputMode {
@@ -20,8 +97,26 @@ pub enum ColoredOutputMode {
     On,
 }
 
-/// New line marker that should be used in the output files.
+/// Format of the report printed to the standard output.
 #[derive(clap::ValueEnum, Clone, PartialEq, Debug, Default)]
+pub enum OutputFormat {
+    #[default]
+    #[clap(help = "Human-readable (possibly colored) text.")]
+    Human,
+
+    #[clap(help = "Machine-readable JSON.")]
+    Json,
+
+    #[clap(help = "Machine-readable TOML.")]
+    Toml,
+
+    #[clap(help = "CSV with one row per change, e.g. for spreadsheets.")]
+    Csv,
+}
+
+/// New line marker that should be used in the output files.
+#[derive(clap::ValueEnum, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum OutputNewLineMarkerMode {
     #[default]
     #[clap(
@@ -38,10 +133,22 @@ pub enum OutputNewLineMarkerMode {
 
     #[clap(help = "Windows/DOS new line marker '\\r\\n'.")]
     Windows,
+
+    #[clap(
+        help = "Keep the new line marker of each line. New line markers that are added, \
+        e.g. at the end of the file, are the same as the last new line marker in the file. \
+        If no new line marker is present in the file, Linux '\\n' is used."
+    )]
+    KeepPerLine,
+
+    /// Arbitrary sequence of bytes given by `--custom-new-line-marker`.
+    #[clap(skip)]
+    Custom(Vec<u8>),
 }
 
 /// Mode for dealing with `\v` and `\f` characters.
-#[derive(clap::ValueEnum, Clone, PartialEq, Debug, Default)]
+#[derive(clap::ValueEnum, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum NonStandardWhitespaceReplacementMode {
     #[default]
     #[clap(help = "Leave '\\v' and '\\f' as is.")]
@@ -54,9 +161,85 @@ pub enum NonStandardWhitespaceReplacementMode {
     Remove,
 }
 
+/// Mode for dealing with Unicode whitespace characters such as no-break space U+00A0.
+#[derive(clap::ValueEnum, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnicodeWhitespaceReplacementMode {
+    #[default]
+    #[clap(help = "Leave Unicode whitespace characters as is.")]
+    Ignore,
+
+    #[clap(help = "Replace any Unicode whitespace character with a single space.")]
+    ReplaceWithSpace,
+
+    #[clap(help = "Remove all Unicode whitespace characters.")]
+    Remove,
+}
+
+/// Whitespace character that can be removed from the end of a line.
+#[derive(clap::ValueEnum, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrailingWhitespaceChar {
+    #[clap(help = "Space.")]
+    Space,
+
+    #[clap(help = "Horizontal tab.")]
+    Tab,
+
+    #[clap(help = "Vertical tab '\\v'.")]
+    VerticalTab,
+
+    #[clap(help = "Form feed '\\f'.")]
+    FormFeed,
+}
+
+/// Lines from which `--remove-trailing-whitespace` removes the trailing whitespace.
+#[derive(clap::ValueEnum, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrailingWhitespaceLines {
+    #[default]
+    #[clap(help = "All lines.")]
+    All,
+
+    #[clap(help = "Only blank lines, i.e., lines consisting of whitespace only.")]
+    BlankOnly,
+
+    #[clap(help = "Only lines with content other than whitespace.")]
+    ContentOnly,
+}
+
+/// Formatting rule that can be selected by `--only`.
+#[derive(clap::ValueEnum, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    #[clap(help = "Remove trailing whitespace, as --remove-trailing-whitespace.")]
+    TrailingWhitespace,
+
+    #[clap(help = "Remove trailing empty lines, as --remove-trailing-empty-lines.")]
+    TrailingEmptyLines,
+
+    #[clap(
+        help = "Add a new line marker to the end of the file, as --add-new-line-marker-at-end-of-file."
+    )]
+    NewLineAtEndOfFile,
+
+    #[clap(help = "Normalize new line markers, as --normalize-new-line-markers.")]
+    NewLineMarkers,
+
+    #[clap(help = "Replace tabs with spaces. By default, each tab is replaced by 4 spaces.")]
+    Tabs,
+
+    #[clap(help = "Replace '\\v' and '\\f' with spaces, unless another mode is given.")]
+    NonStandardWhitespace,
+
+    #[clap(help = "Replace Unicode whitespace with spaces, unless another mode is given.")]
+    UnicodeWhitespace,
+}
+
 /// Mode for dealing with trivial files.
 /// Trivial files are either empty files, or files consisting of only whitespace.
-#[derive(clap::ValueEnum, Clone, PartialEq, Debug, Default)]
+#[derive(clap::ValueEnum, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum TrivialFileReplacementMode {
     #[default]
     #[clap(help = "Leave the file as is.")]
@@ -74,7 +257,9 @@ pub enum TrivialFileReplacementMode {
 #[command(
     version,
     about = "Whitespace formatter and linter for text files and source code files.",
-    long_about = "Whitespace formatter and linter for text files and source code files."
+    long_about = "Whitespace formatter and linter for text files and source code files.",
+    // Modes in which no files are written.
+    group(clap::ArgGroup::new("no_writes").args(["check_only", "list_only"]).multiple(true))
 )]
 pub struct CommandLineArguments {
     #[arg(
@@ -85,6 +270,36 @@ pub struct CommandLineArguments {
     )]
     pub check_only: bool,
 
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["check_only", "list_only"],
+        help = "Format files, but exit with a non-zero exit code if any file has been changed. \
+        Useful in CI to detect commits that were not formatted."
+    )]
+    pub fail_on_change: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["fail_on_change", "list_only", "verify_writes", "text"],
+        help = "Format all files in memory without writing them, and fail \
+        if formatting any of them once more would change it. Useful for checking \
+        that a combination of options is stable. Implies `--check-only`."
+    )]
+    pub verify: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "no_writes",
+        help = "Stop checking files as soon as N files that need formatting are found. \
+        The files are checked one by one, so the files after the limit are not read. \
+        Requires --check-only or --list-only."
+    )]
+    pub max_violations: Option<u32>,
+
     #[arg(
         long,
         default_value_t = false,
@@ -92,6 +307,25 @@ pub struct CommandLineArguments {
     )]
     pub follow_symlinks: bool,
 
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Search directories only up to depth N. The paths given on the command line \
+        have depth 0, the entries of the given directories have depth 1, and so on. \
+        With 0, only the files given on the command line are processed."
+    )]
+    pub max_depth: Option<usize>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Do not skip files and directories ignored by `.gitignore` files. \
+        By default, files and directories found inside the given directories are skipped \
+        if they are ignored by a `.gitignore` file in their directory or any parent directory \
+        up to the root of the git repository. Files given explicitly are never skipped."
+    )]
+    pub no_respect_gitignore: bool,
+
     #[arg(long,
     default_value_t = String::from(UNMATCHABLE_REGEX),
     help =
@@ -101,6 +335,7 @@ pub struct CommandLineArguments {
     long_help =
         "Regular expression that specifies which files to exclude. \
         The regular expression is evaluated on the path of each file. \
+        Directories whose path followed by '/' matches it are not searched at all. \
         The default value is a regular expression that does not match anything.\
         \n\n\
         Example #1: --exclude=\"(.jpeg|.png)$\" excludes files with '.jpeg' or '.png' extension.\n\
@@ -108,6 +343,76 @@ pub struct CommandLineArguments {
     ")]
     pub exclude: String,
 
+    #[arg(
+        long,
+        value_name = "REGEX",
+        help = "Process only files whose content matches the regular expression. \
+        Other files are reported as skipped. Every file has to be read to decide this."
+    )]
+    pub content_matches: Option<ContentPattern>,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Glob pattern that specifies which files to process. \
+        The option can be repeated. If the option is used, only files \
+        whose path matches at least one of the patterns are processed."
+    )]
+    pub include: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Glob pattern that specifies which files to exclude. \
+        The option can be repeated. It is applied together with `--exclude`."
+    )]
+    pub exclude_glob: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Name of directories that are not searched at all, e.g. `target`. \
+        The option can be repeated. Only the name of each directory found is compared, \
+        not its whole path. Directories given on the command line are always searched."
+    )]
+    pub exclude_dir: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "EXTENSIONS",
+        value_delimiter = ',',
+        help = "Comma-separated list of file extensions, e.g. `rs,txt,md`. \
+        If the option is used, only files with one of the extensions are processed. \
+        Extensions are compared case-insensitively."
+    )]
+    pub extensions: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "extensions",
+        help = "With --extensions, process also files without an extension, \
+        e.g. `Makefile` or `.gitignore`."
+    )]
+    pub include_files_without_extension: bool,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        conflicts_with = "newer_than",
+        help = "Process only files modified within the given time before now, \
+        e.g. `30m`, `2h` or `7d`. The units are s, m, h, d and w."
+    )]
+    pub since: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Process only files modified after the given file or directory."
+    )]
+    pub newer_than: Option<PathBuf>,
+
     #[arg(
         long,
         value_enum,
@@ -116,11 +421,207 @@ pub struct CommandLineArguments {
     )]
     pub color: ColoredOutputMode,
 
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print the report with plain ASCII text instead of emoji and arrows, \
+        e.g. for CI logs that cannot display them."
+    )]
+    pub ascii: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Human,
+        help = "Format of the report printed to the standard output."
+    )]
+    pub format: OutputFormat,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["text", "dump_options_json", "dry_plan"],
+        help = "Also write the report as JSON to the file, whatever the format of the report \
+        printed to the standard output. Useful for CI artifacts. Use --quiet to write \
+        the report only to the file."
+    )]
+    pub output_report: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print a unified diff of the changes for each file that is changed \
+        or would be changed. Trailing whitespace, tabs and new line markers \
+        are shown as visible characters."
+    )]
+    pub show_diff: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "format",
+        help = "For each file, explain its line endings: the number of new line markers \
+        of each type, the dominant one, and whether they are normalized. \
+        Windows line endings on other systems come with a hint about git's core.autocrlf."
+    )]
+    pub explain_line_endings: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        require_equals = true,
+        help = "Print the SHA-256 hash of the formatted content of each processed file \
+        in the format of `sha256sum`. In check-only mode, the hashes are computed from \
+        the content the files would have. If PATH is given, the hashes are written \
+        to that file instead of the standard output."
+    )]
+    pub print_hashes: Option<Option<PathBuf>>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print the formatting options resolved from the command line as JSON and exit. \
+        No files are processed."
+    )]
+    pub dump_options_json: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "dump_options_json",
+        help = "Print the files that would be processed, each with the formatting options \
+        that apply to it, as JSON and exit. The content of the files is not read."
+    )]
+    pub dry_plan: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["dump_options_json", "dry_plan"],
+        help = "Print the number of new line markers of each type in each file \
+        and in total, and exit. The files are not formatted."
+    )]
+    pub new_line_marker_stats: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "After formatting a file, format the result once more and fail \
+        if the second pass would change anything. Useful for debugging."
+    )]
+    pub assert_idempotent: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["check_only", "pipe_through"],
+        help = "After writing a file, read it back from the disk and fail \
+        if formatting it again would change it."
+    )]
+    pub verify_writes: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["format", "show_diff"],
+        help = "Instead of the usual report, print only the distinct types of changes \
+        made (or that would be made) across all files, one per line."
+    )]
+    pub list_change_types: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "format",
+        help = "After the report, print a summary of the changed files similar to \
+        `git diff --stat`, i.e., the number of lines inserted and deleted in each file."
+    )]
+    pub stat: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print statistics about the run after the report, \
+        e.g. the total number of lines processed."
+    )]
+    pub stats: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print the time spent processing each file after the report, \
+        the slowest files first, followed by the total."
+    )]
+    pub timings: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Report the changes of each file sorted by line number. \
+        Changes on the same line are kept in the order in which they were made."
+    )]
+    pub sort_changes_by_line: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print additional details about the run, \
+        e.g. the symbolic links skipped when searching for files."
+    )]
+    pub verbose: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["format", "show_diff", "list_change_types", "stat", "stats", "print_hashes", "explain_line_endings"],
+        help = "Print only a single line summarizing the run, \
+        e.g. `whitespace-format: clean`. Useful for commit hooks."
+    )]
+    pub hook_output: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["format", "show_diff", "list_change_types", "stat", "stats", "print_hashes", "hook_output", "explain_line_endings"],
+        help = "Print only the paths of the files that would be changed, one per line, \
+        and nothing else. Implies `--check-only`."
+    )]
+    pub list_only: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["format", "show_diff", "list_change_types", "stat", "stats", "print_hashes", "hook_output", "explain_line_endings", "verbose"],
+        help = "Do not print anything except errors and, with `--list-only`, the paths \
+        of the files. Only the exit code tells the result."
+    )]
+    pub quiet: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "list_only",
+        help = "With `--list-only`, separate the paths by NUL characters instead of new lines."
+    )]
+    pub null: bool,
+
     #[arg(long, value_enum,
     default_value_t = OutputNewLineMarkerMode::Auto,
     help = "New line marker to use.")]
     pub new_line_marker: OutputNewLineMarkerMode,
 
+    #[arg(
+        long,
+        value_name = "BYTES",
+        value_parser = parse_custom_new_line_marker,
+        conflicts_with = "new_line_marker",
+        help = "Use an arbitrary sequence of bytes as the new line marker in the output, \
+        e.g. '\\x1e'. The escape sequences '\\xHH', '\\n', '\\r', '\\t', '\\0' and '\\\\' are recognized. \
+        Only '\\n', '\\r' and '\\r\\n' are recognized as new line markers in the input."
+    )]
+    pub custom_new_line_marker: Option<OutputNewLineMarkerMode>,
+
     #[arg(
         long,
         default_value_t = false,
@@ -149,9 +650,58 @@ pub struct CommandLineArguments {
     #[arg(
         long,
         default_value_t = false,
-        help = "Remove whitespace at the end of each line."
+        help = "Treat Unicode line separator U+2028 and paragraph separator U+2029 \
+        as new line markers and replace them with the output new line marker."
+    )]
+    pub normalize_unicode_line_separators: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "RULES",
+        value_delimiter = ',',
+        help = "Comma-separated list of formatting rules. If the option is used, \
+        exactly these rules are applied and all other formatting options are disabled. \
+        Parameters of the rules, e.g. --new-line-marker or --replace-tabs-with-spaces, are kept."
+    )]
+    pub only: Vec<Rule>,
+
+    #[arg(
+        long,
+        value_name = "LINES",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "all",
+        help = "Remove whitespace at the end of each line. Optionally, only from blank lines \
+        or only from lines with other content."
+    )]
+    pub remove_trailing_whitespace: Option<TrailingWhitespaceLines>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "When removing trailing whitespace, keep Markdown hard line breaks, i.e., \
+        two spaces at the end of a line with content. Three or more spaces are trimmed \
+        to two, and a single space is removed. Applies only to Markdown files, \
+        i.e., files with the extension .md or .markdown."
     )]
-    pub remove_trailing_whitespace: bool,
+    pub markdown_hard_breaks: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [
+            TrailingWhitespaceChar::Space,
+            TrailingWhitespaceChar::Tab,
+            TrailingWhitespaceChar::VerticalTab,
+            TrailingWhitespaceChar::FormFeed,
+        ],
+        help = "Comma-separated list of characters that are removed from the end of each line \
+        by --remove-trailing-whitespace. Other whitespace characters at the end of a line are kept."
+    )]
+    pub trailing_whitespace_chars: Vec<TrailingWhitespaceChar>,
 
     #[arg(
         long,
@@ -163,9 +713,34 @@ pub struct CommandLineArguments {
     )]
     pub remove_trailing_empty_lines: bool,
 
-    #[arg(long,
-    value_enum,
-    default_value_t = TrivialFileReplacementMode::Ignore,
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with = "remove_trailing_empty_lines",
+        help = "Remove empty lines at the end of each file, but keep up to N of them. \
+        Missing empty lines are not added. With 0, it is the same as --remove-trailing-empty-lines."
+    )]
+    pub max_trailing_blank_lines: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Make each file end with exactly N new line markers. \
+        The new line marker specified by `--new-line-marker` is used."
+    )]
+    pub ensure_trailing_newlines: Option<usize>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Consider lines consisting of whitespace only (spaces, tabs, '\\v', '\\f') to be empty. \
+        Such lines at the end of the file are removed by `--remove-trailing-empty-lines`."
+    )]
+    pub blank_lines_include_whitespace: bool,
+
+    #[arg(long,
+    value_enum,
+    default_value_t = TrivialFileReplacementMode::Ignore,
     help = "Replace files of zero length.",
     )]
     pub normalize_empty_files: TrivialFileReplacementMode,
@@ -179,12 +754,38 @@ pub struct CommandLineArguments {
     behavior that is not idempotent.")]
     pub normalize_whitespace_only_files: TrivialFileReplacementMode,
 
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Files larger than the limit are never treated as whitespace-only files, \
+        i.e., `--normalize-whitespace-only-files` does not apply to them. \
+        Instead, they are processed line by line like any other file."
+    )]
+    pub max_whitespace_only_size: Option<usize>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["normalize_whitespace_only_files", "max_whitespace_only_size"],
+        help = "Never treat files as whitespace-only files. Instead, process them line by line \
+        like any other file, so that e.g. `--remove-trailing-whitespace` and \
+        `--remove-trailing-empty-lines` turn a file of blank lines into an empty file."
+    )]
+    pub apply_line_rules_to_whitespace_only_files: bool,
+
     #[arg(long,
     value_enum,
     default_value_t = NonStandardWhitespaceReplacementMode::Ignore,
     help = "Replace or remove non-standard whitespace characters '\\v' and '\\f' in each file.")]
     pub normalize_non_standard_whitespace: NonStandardWhitespaceReplacementMode,
 
+    #[arg(long,
+    value_enum,
+    default_value_t = UnicodeWhitespaceReplacementMode::Ignore,
+    help = "Replace or remove Unicode whitespace characters (e.g. no-break space U+00A0, \
+    zero-width space U+200B or ideographic space U+3000) in each file.")]
+    pub normalize_unicode_whitespace: UnicodeWhitespaceReplacementMode,
+
     #[arg(long,
     default_value_t = -1,
     help = "Replace tabs with spaces. \
@@ -193,17 +794,382 @@ pub struct CommandLineArguments {
     If the parameter is negative, tabs are not replaced.")]
     pub replace_tabs_with_spaces: isize,
 
+    #[arg(
+        long,
+        value_name = "tabs-to-spaces:N",
+        value_parser = parse_retab,
+        help = "Replace tabs in the indentation of each line with N spaces per tab. \
+        Lines whose indentation would not convert back to the same tabs \
+        (e.g. a tab after spaces) are reported and left unchanged. \
+        Tabs after the indentation are left as is."
+    )]
+    pub retab: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "WIDTH",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with = "retab",
+        help = "Replace each tab with the number of spaces needed to reach the next tab stop. \
+        Tab stops are every WIDTH columns. A tab in column 3 with WIDTH 4 becomes one space."
+    )]
+    pub expand_tabs_to_stops: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "K",
+        default_value_t = 0,
+        requires = "expand_tabs_to_stops",
+        help = "With --expand-tabs-to-stops=WIDTH, put the tab stops at column K \
+        and every WIDTH columns after it, e.g. for lines with a fixed-width prefix. \
+        A tab before column K is expanded up to column K. Columns are counted from 0."
+    )]
+    pub tab_stop_origin: usize,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "add_bom",
+        help = "Remove the UTF-8 byte order mark from the beginning of each file."
+    )]
+    pub strip_bom: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Add the UTF-8 byte order mark to the beginning of each file if it is missing."
+    )]
+    pub add_bom: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Warn about lines that end with non-ASCII whitespace characters \
+        (e.g. no-break space U+00A0 or ideographic space U+3000). \
+        Such characters are reported, but they are not removed."
+    )]
+    pub warn_exotic_trailing_whitespace: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Warn about files that look minified, i.e., files with a few very long lines. \
+        Changes at the end of such files (e.g. adding a new line marker) may be unwanted. \
+        The files are formatted as usual."
+    )]
+    pub warn_if_minified: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Warn about files that start with a double-encoded byte order mark, \
+        i.e., the characters 'ï»¿' left behind by a tool that decoded a UTF-8 file as Latin-1. \
+        The files are formatted as usual."
+    )]
+    pub warn_double_encoded_bom: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Leave files whose first line contains the comment \
+        `whitespace-format: ignore-file` untouched. Such files are reported as skipped."
+    )]
+    pub honor_ignore_file_directive: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Format binary files too. By default, files that contain a NUL byte \
+        near the beginning are considered binary; they are left untouched and reported as skipped."
+    )]
+    pub process_binary: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "pipe_through",
+        help = "Format gzip-compressed files too. The decompressed content is formatted, \
+        and the file is compressed again when it is written. Such files are recognized \
+        by their content, not by the extension."
+    )]
+    pub handle_gzip: bool,
+
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ".bak",
+        value_parser = clap::builder::NonEmptyStringValueParser::new(),
+        help = "Before changing a file, copy its original content to a file with \
+        the same name followed by SUFFIX. Default suffix is `.bak`. \
+        Fails if the backup file already exists."
+    )]
+    pub backup: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        value_parser = clap::builder::NonEmptyStringValueParser::new(),
+        conflicts_with_all = ["check_only", "list_only", "backup"],
+        help = "Write each formatted file to a file with the same name followed by SUFFIX, \
+        e.g. `.clean`, instead of overwriting it. The original files are left unchanged. \
+        Nothing is written for files that do not need formatting."
+    )]
+    pub suffix: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["check_only", "list_only", "backup", "suffix", "verify", "text"],
+        help = "Write each file, formatted, to the directory DIR instead of overwriting it, \
+        e.g. `DIR/src/main.rs` for `src/main.rs`. The original files are left unchanged. \
+        The root of absolute paths is dropped, e.g. `/home/a.txt` is written to `DIR/home/a.txt`."
+    )]
+    pub output_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        value_parser = clap::builder::NonEmptyStringValueParser::new(),
+        help = "Pipe the formatted content of each changed file through the shell COMMAND \
+        before writing it. The output of the command is written to the file. \
+        The command runs only on files that formatting changes; files that are already \
+        formatted are left as they are, and the command is not run for them. \
+        If the command fails, the file is not written. The command is run by the shell, \
+        so never pass untrusted input to this option."
+    )]
+    pub pipe_through: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Keep the modification time of the files that are changed. \
+        The permissions of the files are always kept."
+    )]
+    pub preserve_mtime: bool,
+
+    #[arg(
+        long,
+        value_name = "MARKER",
+        requires = "region_end",
+        value_parser = clap::builder::NonEmptyStringValueParser::new(),
+        help = "Format only the lines between a line containing the begin marker \
+        and a line containing the end marker. Everything else, including the marker lines, \
+        is left untouched. Options that change the whole file (e.g. removal of trailing \
+        empty lines) are ignored."
+    )]
+    pub region_begin: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "MARKER",
+        requires = "region_begin",
+        value_parser = clap::builder::NonEmptyStringValueParser::new(),
+        help = "End marker of the regions to format. See `--region-begin`."
+    )]
+    pub region_end: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "START:END",
+        conflicts_with = "region_begin",
+        help = "Format only the lines from START to END, inclusive. Can be repeated. \
+        Everything else is left untouched. Options that change the whole file \
+        (e.g. removal of trailing empty lines) are ignored."
+    )]
+    pub line_range: Vec<LineRange>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Number of files to process in parallel. \
+        The default value is the number of available CPUs."
+    )]
+    pub jobs: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "F",
+        value_parser = parse_threads_per_core,
+        conflicts_with = "jobs",
+        help = "Process files in parallel with F threads per available CPU, \
+        rounded up, e.g. 1.5 gives 6 threads on 4 CPUs. \
+        Values above 1 can speed up processing if reading the files is slow."
+    )]
+    pub threads_per_core: Option<f64>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Read the content of up to N files ahead in a background thread \
+        while the current file is being formatted. \
+        If the parameter is zero, files are read one by one when they are formatted. \
+        A positive value implies `--jobs=1`, and it cannot be combined with more jobs \
+        or with --threads-per-core."
+    )]
+    pub read_ahead: usize,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "no_writes",
+        conflicts_with = "read_ahead",
+        help = "Memory-map the files instead of reading them into memory. \
+        If a file cannot be memory-mapped, it is read as usual. \
+        Requires --check-only or --list-only."
+    )]
+    pub mmap: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Read the list of files to process from FILE, one path per line, \
+        or from the standard input if FILE is `-`. Empty lines and lines starting with `#` \
+        are skipped. The files are processed together with the paths given as arguments."
+    )]
+    pub files_from: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "TEXT",
+        conflicts_with_all = ["paths", "files_from"],
+        help = "Format the given text instead of files. The formatted text is written \
+        to the standard output and the report to the standard error output."
+    )]
+    pub text: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Path of the file whose content is given on the standard input '-'. \
+        The file is not read. The path selects the configuration file and decides whether \
+        the content is formatted, e.g. by `--extensions`; otherwise it is passed through unchanged."
+    )]
+    pub stdin_filename: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Do not read the options from the configuration file `.whitespace-format.toml`."
+    )]
+    pub no_config: bool,
+
     #[arg(num_args = 1..,
-    required = true,
+    required_unless_present_any = ["dump_options_json", "files_from", "text"],
     value_delimiter = ' ',
     help = "List of files and/or directories to process. \
-    Files in directories are discovered recursively.")]
+    Files in directories are discovered recursively. \
+    Use '-' to format the standard input and write the result to the standard output.")]
     pub paths: Vec<PathBuf>,
 }
 
+/// Parses the value of `--custom-new-line-marker`. Besides ordinary characters, the value
+/// may contain the escape sequences `\xHH`, `\n`, `\r`, `\t`, `\0` and `\\`.
+fn parse_custom_new_line_marker(value: &str) -> Result<OutputNewLineMarkerMode, String> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(char.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('0') => bytes.push(0),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == 2)
+                    .ok_or_else(|| format!("invalid escape sequence '\\x{}'", digits))?;
+                bytes.push(byte);
+            }
+            Some(other) => return Err(format!("invalid escape sequence '\\{}'", other)),
+            None => return Err("incomplete escape sequence at the end".to_string()),
+        }
+    }
+    if bytes.is_empty() {
+        return Err("the new line marker cannot be empty".to_string());
+    }
+    Ok(OutputNewLineMarkerMode::Custom(bytes))
+}
+
+/// Parses the value of `--threads-per-core`, which must be a positive number.
+fn parse_threads_per_core(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|threads_per_core| threads_per_core.is_finite() && *threads_per_core > 0.0)
+        .ok_or_else(|| "expected a positive number".to_string())
+}
+
+/// Number of threads for the given number of CPUs and threads per CPU, rounded up.
+/// At least one thread is used.
+fn number_of_threads(number_of_cpus: usize, threads_per_core: f64) -> usize {
+    ((number_of_cpus as f64 * threads_per_core).ceil() as usize).max(1)
+}
+
+/// Parses the value of `--since`, i.e., a non-negative integer followed by a unit:
+/// `s` (seconds), `m` (minutes), `h` (hours), `d` (days) or `w` (weeks).
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid_duration = || "expected a number followed by a unit s, m, h, d or w, e.g. '7d'";
+    let unit = value.chars().last().ok_or_else(invalid_duration)?;
+    let seconds_per_unit: u64 = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return Err(invalid_duration().to_string()),
+    };
+    value[..value.len() - 1]
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(seconds_per_unit))
+        .map(Duration::from_secs)
+        .ok_or_else(|| invalid_duration().to_string())
+}
+
+/// Parses the value of `--retab`, which must be of the form `tabs-to-spaces:N` with N > 0.
+fn parse_retab(value: &str) -> Result<usize, String> {
+    value
+        .strip_prefix("tabs-to-spaces:")
+        .and_then(|tab_width| tab_width.parse::<usize>().ok())
+        .filter(|tab_width| *tab_width > 0)
+        .ok_or_else(|| "expected 'tabs-to-spaces:N' where N is a positive integer".to_string())
+}
+
 impl CommandLineArguments {
+    /// Number of files to process in parallel.
+    pub fn number_of_jobs(&self) -> usize {
+        let number_of_cpus = || thread::available_parallelism().map_or(1, |jobs| jobs.get());
+        match (self.jobs, self.threads_per_core) {
+            (Some(jobs), _) => jobs as usize,
+            // Reading ahead works only if the files are processed sequentially.
+            (None, None) if self.read_ahead > 0 => 1,
+            (None, Some(threads_per_core)) => number_of_threads(number_of_cpus(), threads_per_core),
+            (None, None) => number_of_cpus(),
+        }
+    }
+
     /// Validates command line arguments.
     pub fn validate(&self) {
+        if self.read_ahead > 0
+            && (self.jobs.is_some_and(|jobs| jobs > 1) || self.threads_per_core.is_some())
+        {
+            CommandLineArguments::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "the argument '--read-ahead' cannot be used with more than one job",
+                )
+                .exit();
+        }
+
         if self.normalize_whitespace_only_files == TrivialFileReplacementMode::Empty
             && self.normalize_empty_files == TrivialFileReplacementMode::OneLine
         {
@@ -212,6 +1178,87 @@ impl CommandLineArguments {
                 "the argument '--normalize-whitespace-only-files=empty' cannot be used with '--normalize-empty-files=one-line'"
             ).exit();
         }
+
+        if self.retab.is_some() && self.replace_tabs_with_spaces >= 0 {
+            CommandLineArguments::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "the argument '--retab' cannot be used with '--replace-tabs-with-spaces'",
+                )
+                .exit();
+        }
+
+        if self.expand_tabs_to_stops.is_some() && self.replace_tabs_with_spaces >= 0 {
+            CommandLineArguments::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "the argument '--expand-tabs-to-stops' cannot be used with '--replace-tabs-with-spaces'",
+                )
+                .exit();
+        }
+
+        if self.new_line_marker == OutputNewLineMarkerMode::KeepPerLine
+            && self.normalize_new_line_markers
+        {
+            CommandLineArguments::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "the argument '--new-line-marker=keep-per-line' cannot be used with '--normalize-new-line-markers'",
+                )
+                .exit();
+        }
+
+        if self.paths.len() > 1 && self.paths.contains(&PathBuf::from("-")) {
+            CommandLineArguments::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "the standard input '-' cannot be combined with other paths",
+                )
+                .exit();
+        }
+
+        if self.output_dir.is_some() && self.paths.contains(&PathBuf::from("-")) {
+            CommandLineArguments::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "the argument '--output-dir' cannot be used with the standard input '-'",
+                )
+                .exit();
+        }
+
+        if self.output_report.is_some() && self.paths.contains(&PathBuf::from("-")) {
+            CommandLineArguments::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "the argument '--output-report' cannot be used with the standard input '-'",
+                )
+                .exit();
+        }
+
+        if self.verify && self.paths.contains(&PathBuf::from("-")) {
+            CommandLineArguments::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "the argument '--verify' cannot be used with the standard input '-'",
+                )
+                .exit();
+        }
+
+        if self.stdin_filename.is_some() && self.paths != [PathBuf::from("-")] {
+            CommandLineArguments::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "the argument '--stdin-filename' requires the standard input '-' as the only path",
+                )
+                .exit();
+        }
+
+        if self.ensure_trailing_newlines.is_some() && self.remove_new_line_marker_from_end_of_file {
+            CommandLineArguments::command().error(
+                ErrorKind::ArgumentConflict,
+                "the argument '--ensure-trailing-newlines' cannot be used with '--remove-new-line-marker-from-end-of-file'"
+            ).exit();
+        }
     }
 }
 
@@ -231,20 +1278,60 @@ mod tests {
         let command_line_parameters = vec![
             "whitespace-format",
             "--check-only",
+            "--max-violations=10",
             "--follow-symlinks",
+            "--no-respect-gitignore",
             "--exclude=^.git/",
+            "--content-matches=Copyright",
+            "--include=**/*.rs",
+            "--include=*.md",
+            "--exclude-glob=target/**",
+            "--exclude-dir=target",
+            "--exclude-dir=node_modules",
+            "--max-depth=2",
+            "--extensions=rs,md",
+            "--include-files-without-extension",
             "--color=off",
+            "--ascii",
+            "--format=json",
+            "--show-diff",
+            "--print-hashes=hashes.txt",
+            "--dump-options-json",
+            "--stats",
+            "--timings",
+            "--sort-changes-by-line",
+            "--verbose",
+            "--assert-idempotent",
             "--new-line-marker",
             "linux",
             "--normalize-new-line-markers",
+            "--normalize-unicode-line-separators",
             "--add-new-line-marker-at-end-of-file",
             "--remove-trailing-whitespace",
+            "--only=trailing-whitespace,tabs",
+            "--trailing-whitespace-chars=space,form-feed",
             "--remove-trailing-empty-lines",
+            "--ensure-trailing-newlines=1",
+            "--blank-lines-include-whitespace",
             "--normalize-empty-files=empty",
             "--normalize-whitespace-only-files=empty",
+            "--max-whitespace-only-size=1024",
             "--normalize-non-standard-whitespace",
             "replace-with-space",
+            "--normalize-unicode-whitespace=remove",
             "--replace-tabs-with-spaces=4",
+            "--strip-bom",
+            "--warn-exotic-trailing-whitespace",
+            "--warn-if-minified",
+            "--warn-double-encoded-bom",
+            "--honor-ignore-file-directive",
+            "--process-binary",
+            "--backup",
+            "--preserve-mtime",
+            "--region-begin=# BEGIN MANAGED",
+            "--region-end=# END MANAGED",
+            "--jobs=1",
+            "--read-ahead=8",
             "src/",
             "README.md",
             "LICENSE",
@@ -255,21 +1342,66 @@ mod tests {
 
         command_line_arguments.validate();
 
-        assert_eq!(command_line_arguments.check_only, true);
-        assert_eq!(command_line_arguments.follow_symlinks, true);
+        assert!(command_line_arguments.check_only);
+        assert_eq!(command_line_arguments.max_violations, Some(10));
+        assert!(command_line_arguments.follow_symlinks);
+        assert!(command_line_arguments.no_respect_gitignore);
         assert_eq!(command_line_arguments.exclude, "^.git/");
+        assert_eq!(
+            command_line_arguments.content_matches,
+            Some("Copyright".parse().unwrap())
+        );
+        assert_eq!(command_line_arguments.include, vec!["**/*.rs", "*.md"]);
+        assert_eq!(command_line_arguments.exclude_glob, vec!["target/**"]);
+        assert_eq!(
+            command_line_arguments.exclude_dir,
+            vec!["target", "node_modules"]
+        );
+        assert_eq!(command_line_arguments.max_depth, Some(2));
+        assert_eq!(command_line_arguments.extensions, vec!["rs", "md"]);
+        assert!(command_line_arguments.include_files_without_extension);
         assert_eq!(command_line_arguments.color, ColoredOutputMode::Off);
+        assert!(command_line_arguments.ascii);
+        assert_eq!(command_line_arguments.format, OutputFormat::Json);
+        assert!(command_line_arguments.show_diff);
+        assert_eq!(
+            command_line_arguments.print_hashes,
+            Some(Some(PathBuf::from("hashes.txt")))
+        );
+        assert!(command_line_arguments.dump_options_json);
+        assert!(command_line_arguments.stats);
+        assert!(command_line_arguments.timings);
+        assert!(command_line_arguments.sort_changes_by_line);
+        assert!(command_line_arguments.verbose);
+        assert!(command_line_arguments.assert_idempotent);
+        assert!(!command_line_arguments.verify_writes);
+        assert!(!command_line_arguments.handle_gzip);
+        assert!(!command_line_arguments.markdown_hard_breaks);
         assert_eq!(
             command_line_arguments.new_line_marker,
             OutputNewLineMarkerMode::Linux
         );
-        assert_eq!(command_line_arguments.normalize_new_line_markers, true);
+        assert!(command_line_arguments.normalize_new_line_markers);
+        assert!(command_line_arguments.normalize_unicode_line_separators);
+        assert!(command_line_arguments.add_new_line_marker_at_end_of_file);
         assert_eq!(
-            command_line_arguments.add_new_line_marker_at_end_of_file,
-            true
+            command_line_arguments.remove_trailing_whitespace,
+            Some(TrailingWhitespaceLines::All)
+        );
+        assert_eq!(
+            command_line_arguments.only,
+            vec![Rule::TrailingWhitespace, Rule::Tabs]
+        );
+        assert_eq!(
+            command_line_arguments.trailing_whitespace_chars,
+            vec![
+                TrailingWhitespaceChar::Space,
+                TrailingWhitespaceChar::FormFeed
+            ]
         );
-        assert_eq!(command_line_arguments.remove_trailing_whitespace, true);
-        assert_eq!(command_line_arguments.remove_trailing_empty_lines, true);
+        assert!(command_line_arguments.remove_trailing_empty_lines);
+        assert_eq!(command_line_arguments.ensure_trailing_newlines, Some(1));
+        assert!(command_line_arguments.blank_lines_include_whitespace);
         assert_eq!(
             command_line_arguments.normalize_empty_files,
             TrivialFileReplacementMode::Empty
@@ -278,11 +1410,36 @@ mod tests {
             command_line_arguments.normalize_whitespace_only_files,
             TrivialFileReplacementMode::Empty
         );
+        assert_eq!(command_line_arguments.max_whitespace_only_size, Some(1024));
         assert_eq!(
             command_line_arguments.normalize_non_standard_whitespace,
             NonStandardWhitespaceReplacementMode::ReplaceWithSpace
         );
+        assert_eq!(
+            command_line_arguments.normalize_unicode_whitespace,
+            UnicodeWhitespaceReplacementMode::Remove
+        );
         assert_eq!(command_line_arguments.replace_tabs_with_spaces, 4);
+        assert!(command_line_arguments.strip_bom);
+        assert!(!command_line_arguments.add_bom);
+        assert!(command_line_arguments.warn_exotic_trailing_whitespace);
+        assert!(command_line_arguments.warn_if_minified);
+        assert!(command_line_arguments.warn_double_encoded_bom);
+        assert!(command_line_arguments.honor_ignore_file_directive);
+        assert!(command_line_arguments.process_binary);
+        assert_eq!(command_line_arguments.backup, Some(".bak".to_string()));
+        assert!(command_line_arguments.preserve_mtime);
+        assert_eq!(
+            command_line_arguments.region_begin,
+            Some("# BEGIN MANAGED".to_string())
+        );
+        assert_eq!(
+            command_line_arguments.region_end,
+            Some("# END MANAGED".to_string())
+        );
+        assert_eq!(command_line_arguments.jobs, Some(1));
+        assert_eq!(command_line_arguments.number_of_jobs(), 1);
+        assert_eq!(command_line_arguments.read_ahead, 8);
         assert_eq!(
             command_line_arguments.paths,
             vec![
@@ -293,4 +1450,616 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_print_hashes() {
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "src/"]);
+        assert_eq!(command_line_arguments.print_hashes, None);
+
+        // Without a value, the option does not consume the following path.
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--print-hashes", "src/"]);
+        assert_eq!(command_line_arguments.print_hashes, Some(None));
+        assert_eq!(command_line_arguments.paths, vec![PathBuf::from("src/")]);
+    }
+
+    #[test]
+    fn test_strip_bom_conflicts_with_add_bom() {
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--strip-bom",
+            "--add-bom",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_retab() {
+        assert_eq!(parse_retab("tabs-to-spaces:4"), Ok(4));
+        assert!(parse_retab("tabs-to-spaces:0").is_err());
+        assert!(parse_retab("tabs-to-spaces:").is_err());
+        assert!(parse_retab("spaces-to-tabs:4").is_err());
+        assert!(parse_retab("4").is_err());
+
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--retab=tabs-to-spaces:8",
+            "src/",
+        ]);
+        command_line_arguments.validate();
+        assert_eq!(command_line_arguments.retab, Some(8));
+    }
+
+    #[test]
+    fn test_expand_tabs_to_stops() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--expand-tabs-to-stops=4",
+            "src/",
+        ]);
+        command_line_arguments.validate();
+        assert_eq!(command_line_arguments.expand_tabs_to_stops, Some(4));
+        assert_eq!(command_line_arguments.tab_stop_origin, 0);
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--expand-tabs-to-stops=4",
+            "--tab-stop-origin=6",
+            "src/",
+        ]);
+        assert_eq!(command_line_arguments.tab_stop_origin, 6);
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--tab-stop-origin=6",
+            "src/"
+        ])
+        .is_err());
+        for invalid_arguments in [
+            ["--expand-tabs-to-stops=0", "src/"],
+            ["--expand-tabs-to-stops=4", "--retab=tabs-to-spaces:4"],
+        ] {
+            assert!(CommandLineArguments::try_parse_from(
+                ["whitespace-format"].into_iter().chain(invalid_arguments)
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_threads_per_core() {
+        assert_eq!(number_of_threads(4, 1.5), 6);
+        assert_eq!(number_of_threads(4, 1.0), 4);
+        assert_eq!(number_of_threads(3, 1.5), 5);
+        assert_eq!(number_of_threads(8, 0.1), 1);
+        assert_eq!(number_of_threads(1, 0.01), 1);
+
+        assert_eq!(parse_threads_per_core("1.5"), Ok(1.5));
+        assert!(parse_threads_per_core("0").is_err());
+        assert!(parse_threads_per_core("-1").is_err());
+        assert!(parse_threads_per_core("inf").is_err());
+        assert!(parse_threads_per_core("NaN").is_err());
+
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--threads-per-core=2", "src/"]);
+        assert_eq!(command_line_arguments.threads_per_core, Some(2.0));
+        assert!(command_line_arguments.number_of_jobs() >= 2);
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--threads-per-core=2",
+            "--jobs=3",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_read_ahead_implies_one_job() {
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--read-ahead=4", "src/"]);
+        command_line_arguments.validate();
+        assert_eq!(command_line_arguments.number_of_jobs(), 1);
+    }
+
+    #[test]
+    fn test_max_trailing_blank_lines() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--max-trailing-blank-lines=2",
+            "src/",
+        ]);
+        assert_eq!(command_line_arguments.max_trailing_blank_lines, Some(2));
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--max-trailing-blank-lines=2",
+            "--remove-trailing-empty-lines",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_new_line_marker_stats() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--new-line-marker-stats",
+            "src/",
+        ]);
+        assert!(command_line_arguments.new_line_marker_stats);
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--new-line-marker-stats",
+            "--dry-plan",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_fail_on_change() {
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--fail-on-change", "src/"]);
+        assert!(command_line_arguments.fail_on_change);
+        for conflicting_argument in ["--check-only", "--list-only"] {
+            assert!(CommandLineArguments::try_parse_from([
+                "whitespace-format",
+                "--fail-on-change",
+                conflicting_argument,
+                "src/"
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_remove_trailing_whitespace_lines() {
+        for (argument, lines) in [
+            ("--remove-trailing-whitespace", TrailingWhitespaceLines::All),
+            (
+                "--remove-trailing-whitespace=all",
+                TrailingWhitespaceLines::All,
+            ),
+            (
+                "--remove-trailing-whitespace=blank-only",
+                TrailingWhitespaceLines::BlankOnly,
+            ),
+            (
+                "--remove-trailing-whitespace=content-only",
+                TrailingWhitespaceLines::ContentOnly,
+            ),
+        ] {
+            let command_line_arguments =
+                CommandLineArguments::parse_from(["whitespace-format", argument, "src/"]);
+            assert_eq!(
+                command_line_arguments.remove_trailing_whitespace,
+                Some(lines)
+            );
+        }
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "src/"]);
+        assert_eq!(command_line_arguments.remove_trailing_whitespace, None);
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--remove-trailing-whitespace=some",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_output_report() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--format=csv",
+            "--output-report=report.json",
+            "src/",
+        ]);
+        assert_eq!(
+            command_line_arguments.output_report,
+            Some(PathBuf::from("report.json"))
+        );
+        assert_eq!(command_line_arguments.format, OutputFormat::Csv);
+
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--output-report=report.json",
+            "--dry-plan",
+            "src/",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_output_dir() {
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--output-dir=out", "src/"]);
+        assert_eq!(
+            command_line_arguments.output_dir,
+            Some(PathBuf::from("out"))
+        );
+
+        for conflicting_argument in ["--check-only", "--suffix=.clean", "--backup"] {
+            assert!(CommandLineArguments::try_parse_from([
+                "whitespace-format",
+                "--output-dir=out",
+                conflicting_argument,
+                "src/",
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--text=a  "]);
+        assert_eq!(command_line_arguments.text, Some("a  ".to_string()));
+        assert!(command_line_arguments.paths.is_empty());
+        for conflicting_argument in ["src/", "--files-from=files.txt"] {
+            assert!(CommandLineArguments::try_parse_from([
+                "whitespace-format",
+                "--text=a  ",
+                conflicting_argument
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_line_range() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--line-range=2:3",
+            "--line-range=10:10",
+            "src/",
+        ]);
+        assert_eq!(
+            command_line_arguments.line_range,
+            vec![
+                LineRange { start: 2, end: 3 },
+                LineRange { start: 10, end: 10 }
+            ]
+        );
+        assert!(LineRange { start: 2, end: 3 }.contains(2));
+        assert!(LineRange { start: 2, end: 3 }.contains(3));
+        assert!(!LineRange { start: 2, end: 3 }.contains(4));
+
+        for invalid_range in ["3", "0:2", "3:2", "a:2", "2:"] {
+            assert!(
+                invalid_range.parse::<LineRange>().is_err(),
+                "{}",
+                invalid_range
+            );
+        }
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--line-range=2:3",
+            "--region-begin=BEGIN",
+            "--region-end=END",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--verify", "src/"]);
+        assert!(command_line_arguments.verify);
+        for conflicting_argument in ["--fail-on-change", "--list-only", "--verify-writes"] {
+            assert!(CommandLineArguments::try_parse_from([
+                "whitespace-format",
+                "--verify",
+                conflicting_argument,
+                "src/"
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_verify_writes() {
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--verify-writes", "src/"]);
+        assert!(command_line_arguments.verify_writes);
+        for conflicting_argument in ["--check-only", "--pipe-through=cat"] {
+            assert!(CommandLineArguments::try_parse_from([
+                "whitespace-format",
+                "--verify-writes",
+                conflicting_argument,
+                "src/"
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_apply_line_rules_to_whitespace_only_files() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--apply-line-rules-to-whitespace-only-files",
+            "src/",
+        ]);
+        assert!(command_line_arguments.apply_line_rules_to_whitespace_only_files);
+        for conflicting_argument in [
+            "--normalize-whitespace-only-files=empty",
+            "--max-whitespace-only-size=10",
+        ] {
+            assert!(CommandLineArguments::try_parse_from([
+                "whitespace-format",
+                "--apply-line-rules-to-whitespace-only-files",
+                conflicting_argument,
+                "src/"
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_new_line_marker_keep_per_line() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--new-line-marker=keep-per-line",
+            "--add-new-line-marker-at-end-of-file",
+            "src/",
+        ]);
+        command_line_arguments.validate();
+        assert_eq!(
+            command_line_arguments.new_line_marker,
+            OutputNewLineMarkerMode::KeepPerLine
+        );
+    }
+
+    #[test]
+    fn test_mmap() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--check-only",
+            "--mmap",
+            "src/",
+        ]);
+        assert!(command_line_arguments.mmap);
+        assert!(
+            CommandLineArguments::try_parse_from(["whitespace-format", "--mmap", "src/"]).is_err()
+        );
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--list-only",
+            "--mmap",
+            "--max-violations=1",
+            "src/"
+        ])
+        .is_ok());
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--max-violations=1",
+            "src/"
+        ])
+        .is_err());
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--check-only",
+            "--mmap",
+            "--read-ahead=2",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("0s"), Ok(Duration::from_secs(0)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(
+            parse_duration("7d"),
+            Ok(Duration::from_secs(7 * 24 * 60 * 60))
+        );
+        assert_eq!(
+            parse_duration("1w"),
+            Ok(Duration::from_secs(7 * 24 * 60 * 60))
+        );
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("-1d").is_err());
+        assert!(parse_duration("1.5h").is_err());
+        assert!(parse_duration("7y").is_err());
+        assert!(parse_duration("7é").is_err());
+        assert!(parse_duration("99999999999999999w").is_err());
+
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--since=2h", "src/"]);
+        assert_eq!(
+            command_line_arguments.since,
+            Some(Duration::from_secs(2 * 60 * 60))
+        );
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--since=2h",
+            "--newer-than=Cargo.toml",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_custom_new_line_marker() {
+        assert_eq!(
+            parse_custom_new_line_marker("\\x1e"),
+            Ok(OutputNewLineMarkerMode::Custom(vec![0x1E]))
+        );
+        assert_eq!(
+            parse_custom_new_line_marker("\\r\\n;\\\\\\t\\0é"),
+            Ok(OutputNewLineMarkerMode::Custom(
+                b"\r\n;\\\t\0\xC3\xA9".to_vec()
+            ))
+        );
+        assert!(parse_custom_new_line_marker("").is_err());
+        assert!(parse_custom_new_line_marker("\\x1").is_err());
+        assert!(parse_custom_new_line_marker("\\xZZ").is_err());
+        assert!(parse_custom_new_line_marker("\\q").is_err());
+        assert!(parse_custom_new_line_marker("a\\").is_err());
+
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--custom-new-line-marker=\\x1e",
+            "src/",
+        ]);
+        assert_eq!(
+            command_line_arguments.custom_new_line_marker,
+            Some(OutputNewLineMarkerMode::Custom(vec![0x1E]))
+        );
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--custom-new-line-marker=\\x1e",
+            "--new-line-marker=linux",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_explain_line_endings() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--explain-line-endings",
+            "src/",
+        ]);
+        assert!(command_line_arguments.explain_line_endings);
+        for conflicting_argument in ["--format=json", "--hook-output", "--list-only"] {
+            assert!(CommandLineArguments::try_parse_from([
+                "whitespace-format",
+                "--explain-line-endings",
+                conflicting_argument,
+                "src/",
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_suffix() {
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--suffix=.clean", "src/"]);
+        assert_eq!(command_line_arguments.suffix, Some(".clean".to_string()));
+        for conflicting_argument in ["--check-only", "--list-only", "--backup"] {
+            assert!(CommandLineArguments::try_parse_from([
+                "whitespace-format",
+                "--suffix=.clean",
+                conflicting_argument,
+                "src/",
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_hook_output_conflicts() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--check-only",
+            "--hook-output",
+            "src/",
+        ]);
+        assert!(command_line_arguments.hook_output);
+        for conflicting_argument in ["--format=json", "--show-diff", "--stats"] {
+            assert!(CommandLineArguments::try_parse_from([
+                "whitespace-format",
+                "--hook-output",
+                conflicting_argument,
+                "src/",
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_list_only() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--list-only",
+            "--null",
+            "src/",
+        ]);
+        assert!(command_line_arguments.list_only);
+        assert!(command_line_arguments.null);
+        assert!(
+            CommandLineArguments::try_parse_from(["whitespace-format", "--null", "src/"]).is_err()
+        );
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--list-only",
+            "--hook-output",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_quiet() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--quiet",
+            "--list-only",
+            "src/",
+        ]);
+        assert!(command_line_arguments.quiet);
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--quiet",
+            "--stats",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_stat() {
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--stat", "src/"]);
+        assert!(command_line_arguments.stat);
+        for conflicting_argument in ["--format=json", "--hook-output", "--list-only"] {
+            assert!(CommandLineArguments::try_parse_from([
+                "whitespace-format",
+                "--stat",
+                conflicting_argument,
+                "src/",
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_content_matches_invalid() {
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--content-matches=(unclosed",
+            "src/"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_list_change_types_conflicts() {
+        let command_line_arguments =
+            CommandLineArguments::parse_from(["whitespace-format", "--list-change-types", "src/"]);
+        assert!(command_line_arguments.list_change_types);
+
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--list-change-types",
+            "--format=json",
+            "src/"
+        ])
+        .is_err());
+        assert!(CommandLineArguments::try_parse_from([
+            "whitespace-format",
+            "--list-change-types",
+            "--show-diff",
+            "src/"
+        ])
+        .is_err());
+    }
 }
@@ -0,0 +1,364 @@
+// Library imports
+use clap::CommandFactory;
+use clap::FromArgMatches;
+use std::ffi::OsString;
+use std::fs;
+use std::iter;
+use std::path;
+use std::path::Path;
+use std::path::PathBuf;
+
+// Internal imports
+use crate::cli::CommandLineArguments;
+use crate::error::Error;
+
+/// Name of the configuration file.
+pub const CONFIG_FILE_NAME: &str = ".whitespace-format.toml";
+
+/// Command line arguments that cannot be set in a configuration file.
+const NOT_CONFIGURABLE: [&str; 2] = ["paths", "no_config"];
+
+/// Command line arguments that run commands or read or write files other than
+/// the formatted ones. A configuration file comes with the files being formatted,
+/// e.g. in a cloned repository, so it must not be able to set them.
+const COMMAND_LINE_ONLY: [&str; 10] = [
+    "pipe_through",
+    "output_dir",
+    "suffix",
+    "backup",
+    "output_report",
+    "print_hashes",
+    "files_from",
+    "newer_than",
+    "stdin_filename",
+    "text",
+];
+
+/// Finds the configuration file that applies to a path, i.e., the configuration file
+/// in the directory of the path or in the closest ancestor directory.
+/// The search for a directory starts in the directory itself.
+pub fn find_config_file(path: &Path) -> Option<PathBuf> {
+    let absolute_path = path::absolute(path).ok()?;
+    let start = if absolute_path.is_dir() {
+        absolute_path.as_path()
+    } else {
+        absolute_path.parent()?
+    };
+    start
+        .ancestors()
+        .map(|directory| directory.join(CONFIG_FILE_NAME))
+        .find(|config_path| config_path.is_file())
+}
+
+/// Finds the configuration file that applies to all the paths, i.e., the configuration
+/// file of their closest common ancestor directory. Without paths, the search starts
+/// in the current directory.
+pub fn find_config_file_for_paths(paths: &[PathBuf]) -> Option<PathBuf> {
+    let mut common_directory: Option<PathBuf> = None;
+    for path in paths {
+        let absolute_path = path::absolute(path).ok()?;
+        let directory = if absolute_path.is_dir() {
+            absolute_path
+        } else {
+            absolute_path.parent()?.to_path_buf()
+        };
+        common_directory = Some(match common_directory {
+            None => directory,
+            Some(common_directory) => common_directory
+                .ancestors()
+                .find(|ancestor| directory.starts_with(ancestor))?
+                .to_path_buf(),
+        });
+    }
+    find_config_file(&common_directory.unwrap_or_else(|| PathBuf::from(".")))
+}
+
+/// Converts the content of a configuration file to command line arguments.
+/// The keys are the names of the command line options with underscores instead of dashes,
+/// e.g. `remove_trailing_whitespace = true` or `new_line_marker = "linux"`.
+/// An array is converted to the option repeated for each item.
+pub fn config_to_arguments(content: &str) -> Result<Vec<OsString>, String> {
+    let table: toml::Table = content
+        .parse()
+        .map_err(|error: toml::de::Error| error.message().to_string())?;
+    let command = CommandLineArguments::command();
+    let mut arguments: Vec<OsString> = Vec::new();
+    for (key, value) in &table {
+        let argument = command
+            .get_arguments()
+            .find(|argument| argument.get_id() == key.as_str())
+            .filter(|_| !NOT_CONFIGURABLE.contains(&key.as_str()));
+        let Some(long) = argument.and_then(|argument| argument.get_long()) else {
+            return Err(format!("unknown option '{}'", key));
+        };
+        if COMMAND_LINE_ONLY.contains(&key.as_str()) {
+            return Err(format!(
+                "option '{}' can be given only on the command line",
+                key
+            ));
+        }
+        // Options whose value is optional are flags too, e.g. `--remove-trailing-whitespace`.
+        let is_flag = argument.is_some_and(|argument| {
+            !argument.get_action().takes_values()
+                || argument
+                    .get_num_args()
+                    .is_some_and(|num_args| num_args.min_values() == 0)
+        });
+        let values = match value {
+            toml::Value::Array(items) => items.iter().collect(),
+            _ => vec![value],
+        };
+        for value in values {
+            match value {
+                toml::Value::Boolean(flag) if is_flag => {
+                    if *flag {
+                        arguments.push(format!("--{}", long).into());
+                    }
+                }
+                toml::Value::Boolean(flag) => arguments.push(format!("--{}={}", long, flag).into()),
+                toml::Value::Integer(number) => {
+                    arguments.push(format!("--{}={}", long, number).into())
+                }
+                toml::Value::String(string) => {
+                    arguments.push(format!("--{}={}", long, string).into())
+                }
+                _ => return Err(format!("unsupported value of option '{}'", key)),
+            }
+        }
+    }
+    Ok(arguments)
+}
+
+/// Parses command line arguments together with the options from a configuration file.
+/// The options from the configuration file are placed before the command line arguments,
+/// so an option given on the command line overrides the same option from the file.
+/// The first argument is the name of the program.
+pub fn parse_with_config_file<I, T>(
+    config_path: &Path,
+    args: I,
+) -> Result<CommandLineArguments, Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let invalid_config_file =
+        |reason: String| Error::InvalidConfigFile(config_path.display().to_string(), reason);
+    let content = fs::read_to_string(config_path)
+        .map_err(|_| Error::CannotReadFile(config_path.display().to_string()))?;
+    let config_arguments = config_to_arguments(&content).map_err(invalid_config_file)?;
+
+    let mut args = args.into_iter().map(Into::into);
+    let program = args.next().unwrap_or_default();
+    let matches = CommandLineArguments::command()
+        .args_override_self(true)
+        .try_get_matches_from(iter::once(program).chain(config_arguments).chain(args))
+        .and_then(|matches| CommandLineArguments::from_arg_matches(&matches))
+        .map_err(|error| {
+            let message = error.to_string();
+            let first_line = message.lines().next().unwrap_or_default();
+            invalid_config_file(first_line.trim_start_matches("error: ").to_string())
+        })?;
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::OutputNewLineMarkerMode;
+
+    #[test]
+    fn test_find_config_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("project/src/nested")).unwrap();
+        fs::write(root.join("project/src/nested/file.txt"), "hello\n").unwrap();
+        fs::write(root.join("file.txt"), "hello\n").unwrap();
+        let config_path = root.join("project").join(CONFIG_FILE_NAME);
+        fs::write(&config_path, "remove_trailing_whitespace = true\n").unwrap();
+
+        assert_eq!(
+            find_config_file(&root.join("project/src/nested/file.txt")),
+            Some(config_path.clone())
+        );
+        assert_eq!(
+            find_config_file(&root.join("project/src")),
+            Some(config_path.clone())
+        );
+        assert_eq!(
+            find_config_file(&root.join("project")),
+            Some(config_path.clone())
+        );
+        assert_eq!(find_config_file(&root.join("file.txt")), None);
+
+        // The closest configuration file wins.
+        let nested_config_path = root.join("project/src").join(CONFIG_FILE_NAME);
+        fs::write(&nested_config_path, "").unwrap();
+        assert_eq!(
+            find_config_file(&root.join("project/src/nested/file.txt")),
+            Some(nested_config_path.clone())
+        );
+
+        // The configuration file of the common ancestor directory applies to all the paths.
+        assert_eq!(
+            find_config_file_for_paths(&[
+                root.join("project/src/nested/file.txt"),
+                root.join("project/src/nested"),
+            ]),
+            Some(nested_config_path.clone())
+        );
+        assert_eq!(
+            find_config_file_for_paths(&[
+                root.join("project/src/nested/file.txt"),
+                root.join("project"),
+            ]),
+            Some(config_path.clone())
+        );
+        assert_eq!(
+            find_config_file_for_paths(&[
+                root.join("project/src/nested/file.txt"),
+                root.join("file.txt"),
+            ]),
+            None
+        );
+        assert_eq!(find_config_file_for_paths(&[root.join("file.txt")]), None);
+    }
+
+    #[test]
+    fn test_config_to_arguments() {
+        let arguments = config_to_arguments(
+            "remove_trailing_whitespace = true\n\
+             normalize_new_line_markers = false\n\
+             new_line_marker = \"linux\"\n\
+             replace_tabs_with_spaces = 4\n\
+             only = [\"tabs\", \"trailing-whitespace\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            arguments,
+            vec![
+                OsString::from("--new-line-marker=linux"),
+                OsString::from("--only=tabs"),
+                OsString::from("--only=trailing-whitespace"),
+                OsString::from("--remove-trailing-whitespace"),
+                OsString::from("--replace-tabs-with-spaces=4"),
+            ]
+        );
+        assert_eq!(
+            config_to_arguments("no_such_option = true"),
+            Err("unknown option 'no_such_option'".to_string())
+        );
+        assert_eq!(
+            config_to_arguments("paths = [\"src/\"]"),
+            Err("unknown option 'paths'".to_string())
+        );
+        assert_eq!(
+            config_to_arguments("new_line_marker = { a = 1 }"),
+            Err("unsupported value of option 'new_line_marker'".to_string())
+        );
+        assert!(config_to_arguments("remove_trailing_whitespace = ").is_err());
+        assert_eq!(
+            config_to_arguments("pipe_through = \"touch PWNED; cat\""),
+            Err("option 'pipe_through' can be given only on the command line".to_string())
+        );
+        for key in COMMAND_LINE_ONLY {
+            assert!(
+                config_to_arguments(&format!("{} = \"file.txt\"", key)).is_err(),
+                "{}",
+                key
+            );
+        }
+        assert_eq!(
+            config_to_arguments("remove_trailing_whitespace = \"blank-only\""),
+            Ok(vec![OsString::from(
+                "--remove-trailing-whitespace=blank-only"
+            )])
+        );
+        assert_eq!(
+            config_to_arguments("remove_trailing_whitespace = false"),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn test_parse_with_config_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let config_path = directory.path().join(CONFIG_FILE_NAME);
+        fs::write(
+            &config_path,
+            "remove_trailing_whitespace = true\n\
+             new_line_marker = \"windows\"\n\
+             replace_tabs_with_spaces = 2\n",
+        )
+        .unwrap();
+
+        // The options from the configuration file are used.
+        let command_line_arguments =
+            parse_with_config_file(&config_path, ["whitespace-format", "src/"]).unwrap();
+        assert!(command_line_arguments.remove_trailing_whitespace.is_some());
+        assert_eq!(
+            command_line_arguments.new_line_marker,
+            OutputNewLineMarkerMode::Windows
+        );
+        assert_eq!(command_line_arguments.replace_tabs_with_spaces, 2);
+        assert_eq!(command_line_arguments.paths, vec![PathBuf::from("src/")]);
+
+        // The command line overrides the configuration file.
+        let command_line_arguments = parse_with_config_file(
+            &config_path,
+            [
+                "whitespace-format",
+                "--new-line-marker=linux",
+                "--check-only",
+                "src/",
+            ],
+        )
+        .unwrap();
+        assert!(command_line_arguments.remove_trailing_whitespace.is_some());
+        assert!(command_line_arguments.check_only);
+        assert_eq!(
+            command_line_arguments.new_line_marker,
+            OutputNewLineMarkerMode::Linux
+        );
+        assert_eq!(command_line_arguments.replace_tabs_with_spaces, 2);
+    }
+
+    #[test]
+    fn test_parse_with_config_file_errors() {
+        let directory = tempfile::tempdir().unwrap();
+        let config_path = directory.path().join(CONFIG_FILE_NAME);
+        let config_path_string = config_path.display().to_string();
+
+        fs::write(&config_path, "remove_trailing_whitespace = \n").unwrap();
+        let error = parse_with_config_file(&config_path, ["whitespace-format", "src/"]);
+        assert!(
+            matches!(&error, Err(Error::InvalidConfigFile(path, _)) if *path == config_path_string),
+            "{:?}",
+            error
+        );
+
+        fs::write(&config_path, "new_line_marker = \"unix\"\n").unwrap();
+        let error = parse_with_config_file(&config_path, ["whitespace-format", "src/"]);
+        assert!(
+            matches!(&error, Err(Error::InvalidConfigFile(_, reason)) if reason.contains("unix")),
+            "{:?}",
+            error
+        );
+
+        fs::write(&config_path, "remove_trailing_whitespace = \"yes\"\n").unwrap();
+        let error = parse_with_config_file(&config_path, ["whitespace-format", "src/"]);
+        assert!(
+            matches!(&error, Err(Error::InvalidConfigFile(_, _))),
+            "{:?}",
+            error
+        );
+
+        let error = parse_with_config_file(
+            &directory.path().join("missing.toml"),
+            ["whitespace-format", "src/"],
+        );
+        assert_eq!(
+            error.unwrap_err(),
+            Error::CannotReadFile(directory.path().join("missing.toml").display().to_string())
+        );
+    }
+}
@@ -1,19 +1,44 @@
 // Library imports
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
 use std::cmp::max;
 use std::fmt;
 use std::fs;
+use std::io;
+use std::ops::AddAssign;
+use std::path::Component;
+use std::path::Path;
 use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
 // Internal imports
 use crate::change::Change;
 use crate::change::ChangeType;
 use crate::cli::CommandLineArguments;
+use crate::cli::ContentPattern;
+use crate::cli::LineRange;
 use crate::cli::NonStandardWhitespaceReplacementMode;
 use crate::cli::OutputNewLineMarkerMode;
+use crate::cli::Rule;
+use crate::cli::TrailingWhitespaceChar;
+use crate::cli::TrailingWhitespaceLines;
 use crate::cli::TrivialFileReplacementMode;
-use crate::error::die;
+use crate::cli::UnicodeWhitespaceReplacementMode;
+use crate::diff::diff_stat;
+use crate::diff::unified_diff;
+use crate::diff::DiffStat;
+use crate::error::print_warning;
 use crate::error::Error;
 use crate::writer::CountingWriter;
+use crate::writer::StreamingWriter;
 use crate::writer::Writer;
 
 // ASCII codes of characters that we care about.
@@ -26,6 +51,50 @@ const TAB: u8 = b'\t';
 const VERTICAL_TAB: u8 = 0x0B; // The same as '\v' in C, C++, Java and Python.
 const FORM_FEED: u8 = 0x0C; // The same as '\f' in C, C++, Java and Python.
 
+// UTF-8 encoding of the byte order mark U+FEFF.
+const BYTE_ORDER_MARK: &[u8] = b"\xEF\xBB\xBF";
+
+// The UTF-8 byte order mark decoded as Latin-1 and encoded again as UTF-8,
+// i.e., the characters "ï»¿".
+const DOUBLE_ENCODED_BYTE_ORDER_MARK: &[u8] = b"\xC3\xAF\xC2\xBB\xC2\xBF";
+
+// Trailing spaces that mean a hard line break in Markdown.
+const MARKDOWN_HARD_LINE_BREAK: &[u8] = b"  ";
+
+// Magic number at the beginning of gzip-compressed data.
+const GZIP_MAGIC_NUMBER: &[u8] = b"\x1F\x8B";
+
+// UTF-16 byte order marks, little endian and big endian.
+const UTF16_LE_BYTE_ORDER_MARK: &[u8] = b"\xFF\xFE";
+const UTF16_BE_BYTE_ORDER_MARK: &[u8] = b"\xFE\xFF";
+
+// UTF-8 encodings of the Unicode line separator U+2028 and paragraph separator U+2029.
+pub const LINE_SEPARATOR: &[u8] = b"\xE2\x80\xA8";
+pub const PARAGRAPH_SEPARATOR: &[u8] = b"\xE2\x80\xA9";
+
+// Unicode whitespace characters recognized by `--normalize-unicode-whitespace`.
+// The zero-width space U+200B is not whitespace according to Unicode,
+// but it is invisible and it confuses compilers the same way.
+const UNICODE_WHITESPACE: &[char] = &[
+    '\u{00A0}', // No-break space
+    '\u{1680}', // Ogham space mark
+    '\u{2000}', // En quad
+    '\u{2001}', // Em quad
+    '\u{2002}', // En space
+    '\u{2003}', // Em space
+    '\u{2004}', // Three-per-em space
+    '\u{2005}', // Four-per-em space
+    '\u{2006}', // Six-per-em space
+    '\u{2007}', // Figure space
+    '\u{2008}', // Punctuation space
+    '\u{2009}', // Thin space
+    '\u{200A}', // Hair space
+    '\u{200B}', // Zero-width space
+    '\u{202F}', // Narrow no-break space
+    '\u{205F}', // Medium mathematical space
+    '\u{3000}', // Ideographic space
+];
+
 /// Converts an ASCII code to a human-readable string.
 pub fn char_to_str(char: u8) -> &'static str {
     match char {
@@ -43,6 +112,9 @@ pub fn char_to_str(char: u8) -> &'static str {
 /// 1) Linux (`\n`)
 /// 2) MacOS (`\r`)
 /// 3) Windows/DOS (`\r\n`)
+///
+/// Additionally, an arbitrary sequence of bytes can be used as a new line marker
+/// in the output. It is never detected in the input.
 #[derive(PartialEq, Debug, Clone)]
 pub enum NewLineMarker {
     // Linux line ending is a single line feed character '\n'.
@@ -54,16 +126,20 @@ pub enum NewLineMarker {
     // Windows/DOS line ending is a sequence of two characters:
     // carriage return character followed by line feed character.
     Windows,
+
+    // Custom new line marker given by the user, e.g. the record separator `\x1E`.
+    Custom(Vec<u8>),
 }
 
 impl NewLineMarker {
     /// Byte representation of a new line marker.
     ///
-    fn to_bytes(&self) -> &'static [u8] {
+    fn to_bytes(&self) -> &[u8] {
         match &self {
             NewLineMarker::Linux => &[LINE_FEED],
             NewLineMarker::Mac => &[CARRIAGE_RETURN],
             NewLineMarker::Windows => &[CARRIAGE_RETURN, LINE_FEED],
+            NewLineMarker::Custom(bytes) => bytes,
         }
     }
 }
@@ -76,43 +152,293 @@ impl fmt::Display for NewLineMarker {
             NewLineMarker::Linux => f.write_str("\\n"),
             NewLineMarker::Mac => f.write_str("\\r"),
             NewLineMarker::Windows => f.write_str("\\r\\n"),
+            NewLineMarker::Custom(bytes) => {
+                for byte in bytes {
+                    write!(f, "{}", std::ascii::escape_default(*byte))?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
 /// Options for formatting a single file.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct Options {
     add_new_line_marker_at_end_of_file: bool,
     remove_new_line_marker_from_end_of_file: bool,
     normalize_new_line_markers: bool,
     remove_trailing_whitespace: bool,
+    trailing_whitespace_lines: TrailingWhitespaceLines,
+    markdown_hard_breaks: bool,
+    trailing_whitespace_chars: Vec<TrailingWhitespaceChar>,
     remove_trailing_empty_lines: bool,
+    max_trailing_blank_lines: Option<usize>,
     new_line_marker: OutputNewLineMarkerMode,
     normalize_empty_files: TrivialFileReplacementMode,
     normalize_whitespace_only_files: TrivialFileReplacementMode,
     replace_tabs_with_spaces: isize,
+    retab_tabs_to_spaces: Option<usize>,
+    expand_tabs_to_stops: Option<usize>,
+    tab_stop_origin: usize,
     normalize_non_standard_whitespace: NonStandardWhitespaceReplacementMode,
+    normalize_unicode_whitespace: UnicodeWhitespaceReplacementMode,
+    warn_exotic_trailing_whitespace: bool,
+    warn_if_minified: bool,
+    warn_double_encoded_bom: bool,
+    strip_bom: bool,
+    add_bom: bool,
+    max_whitespace_only_size: Option<usize>,
+    apply_line_rules_to_whitespace_only_files: bool,
+    normalize_unicode_line_separators: bool,
+    ensure_trailing_newlines: Option<usize>,
+    blank_lines_include_whitespace: bool,
+    region_begin: Option<String>,
+    region_end: Option<String>,
+    line_ranges: Vec<LineRange>,
+}
+
+impl Options {
+    /// Returns the begin and end markers of the regions to format, if they are set.
+    fn region_markers(&self) -> Option<(&[u8], &[u8])> {
+        match (&self.region_begin, &self.region_end) {
+            (Some(begin), Some(end)) => Some((begin.as_bytes(), end.as_bytes())),
+            _ => None,
+        }
+    }
+
+    /// Determines if the trailing whitespace is removed from a line,
+    /// depending on whether the line is blank, i.e., consists of whitespace only.
+    fn removes_trailing_whitespace(&self, is_blank_line: bool) -> bool {
+        self.remove_trailing_whitespace
+            && match self.trailing_whitespace_lines {
+                TrailingWhitespaceLines::All => true,
+                TrailingWhitespaceLines::BlankOnly => is_blank_line,
+                TrailingWhitespaceLines::ContentOnly => !is_blank_line,
+            }
+    }
+
+    /// Options that apply to the file with the given path. Markdown hard line breaks
+    /// are kept only in Markdown files, see `is_markdown_file`.
+    pub fn for_file(&self, file_path: &Path) -> Options {
+        Options {
+            markdown_hard_breaks: self.markdown_hard_breaks && is_markdown_file(file_path),
+            ..self.clone()
+        }
+    }
+
+    /// Number of spaces kept at the end of a line with content that ends in the given way.
+    /// With `markdown_hard_breaks`, two or more spaces at the end of a line are
+    /// a Markdown hard line break, which is normalized to exactly two spaces.
+    fn hard_line_break_length(&self, line: &[u8]) -> usize {
+        if self.markdown_hard_breaks && line.ends_with(MARKDOWN_HARD_LINE_BREAK) {
+            MARKDOWN_HARD_LINE_BREAK.len()
+        } else {
+            0
+        }
+    }
+
+    /// Determines if a whitespace character is removed from the end of a line
+    /// by `remove_trailing_whitespace`.
+    fn is_trailing_whitespace(&self, char: u8) -> bool {
+        self.trailing_whitespace_chars
+            .iter()
+            .any(|trailing_whitespace_char| {
+                char == match trailing_whitespace_char {
+                    TrailingWhitespaceChar::Space => SPACE,
+                    TrailingWhitespaceChar::Tab => TAB,
+                    TrailingWhitespaceChar::VerticalTab => VERTICAL_TAB,
+                    TrailingWhitespaceChar::FormFeed => FORM_FEED,
+                }
+            })
+    }
+
+    /// Copy of the options that applies exactly the given formatting rules.
+    /// All other formatting operations are disabled. Parameters of the rules,
+    /// such as the output new line marker or the number of spaces per tab, are kept.
+    fn restricted_to_rules(&self, rules: &[Rule]) -> Options {
+        let has_rule = |rule: Rule| rules.contains(&rule);
+        let has_tab_option = self.replace_tabs_with_spaces >= 0
+            || self.retab_tabs_to_spaces.is_some()
+            || self.expand_tabs_to_stops.is_some();
+        let (replace_tabs_with_spaces, retab_tabs_to_spaces, expand_tabs_to_stops) =
+            if !has_rule(Rule::Tabs) {
+                (-1, None, None)
+            } else if has_tab_option {
+                (
+                    self.replace_tabs_with_spaces,
+                    self.retab_tabs_to_spaces,
+                    self.expand_tabs_to_stops,
+                )
+            } else {
+                (4, None, None)
+            };
+        Options {
+            add_new_line_marker_at_end_of_file: has_rule(Rule::NewLineAtEndOfFile),
+            remove_new_line_marker_from_end_of_file: false,
+            normalize_new_line_markers: has_rule(Rule::NewLineMarkers),
+            remove_trailing_whitespace: has_rule(Rule::TrailingWhitespace),
+            remove_trailing_empty_lines: has_rule(Rule::TrailingEmptyLines)
+                && self.max_trailing_blank_lines.is_none(),
+            max_trailing_blank_lines: self
+                .max_trailing_blank_lines
+                .filter(|_| has_rule(Rule::TrailingEmptyLines)),
+            normalize_empty_files: TrivialFileReplacementMode::Ignore,
+            normalize_whitespace_only_files: TrivialFileReplacementMode::Ignore,
+            replace_tabs_with_spaces,
+            retab_tabs_to_spaces,
+            expand_tabs_to_stops,
+            normalize_non_standard_whitespace: match &self.normalize_non_standard_whitespace {
+                _ if !has_rule(Rule::NonStandardWhitespace) => {
+                    NonStandardWhitespaceReplacementMode::Ignore
+                }
+                NonStandardWhitespaceReplacementMode::Ignore => {
+                    NonStandardWhitespaceReplacementMode::ReplaceWithSpace
+                }
+                mode => mode.clone(),
+            },
+            normalize_unicode_whitespace: match &self.normalize_unicode_whitespace {
+                _ if !has_rule(Rule::UnicodeWhitespace) => UnicodeWhitespaceReplacementMode::Ignore,
+                UnicodeWhitespaceReplacementMode::Ignore => {
+                    UnicodeWhitespaceReplacementMode::ReplaceWithSpace
+                }
+                mode => mode.clone(),
+            },
+            strip_bom: false,
+            add_bom: false,
+            normalize_unicode_line_separators: false,
+            ensure_trailing_newlines: None,
+            ..self.clone()
+        }
+    }
+
+    /// Determines if only some lines of a file are formatted,
+    /// i.e., the lines in the regions or in the line ranges.
+    fn formats_only_some_lines(&self) -> bool {
+        self.region_markers().is_some() || !self.line_ranges.is_empty()
+    }
+
+    /// Copy of the options without the operations that change the whole file.
+    /// Used when only some lines of a file are formatted.
+    fn without_whole_file_operations(&self) -> Options {
+        Options {
+            add_new_line_marker_at_end_of_file: false,
+            remove_new_line_marker_from_end_of_file: false,
+            remove_trailing_empty_lines: false,
+            max_trailing_blank_lines: None,
+            normalize_empty_files: TrivialFileReplacementMode::Ignore,
+            normalize_whitespace_only_files: TrivialFileReplacementMode::Ignore,
+            strip_bom: false,
+            add_bom: false,
+            ensure_trailing_newlines: None,
+            ..self.clone()
+        }
+    }
+}
+
+/// Settings for processing files that do not change how their content is formatted,
+/// e.g. which files are skipped, where the formatted content is written
+/// and what is reported about it.
+#[derive(PartialEq, Debug, Clone)]
+pub struct RunSettings {
+    backup_suffix: Option<String>,
+    output_suffix: Option<String>,
+    output_dir: Option<PathBuf>,
+    pipe_through: Option<String>,
+    preserve_mtime: bool,
+    show_diff: bool,
+    explain_line_endings: bool,
+    compute_diff_stat: bool,
+    measure_processing_time: bool,
+    count_lines: bool,
+    compute_hash: bool,
+    streaming_write_threshold: usize,
+    memory_map: bool,
+    assert_idempotent: bool,
+    verify_writes: bool,
+    handle_gzip: bool,
+    honor_ignore_file_directive: bool,
+    content_matches: Option<ContentPattern>,
+    process_binary: bool,
 }
 
 impl CommandLineArguments {
     /// Extracts formatting options from command line arguments.
     pub fn get_options(&self) -> Options {
-        Options {
+        let options = Options {
             add_new_line_marker_at_end_of_file: self.add_new_line_marker_at_end_of_file,
             remove_new_line_marker_from_end_of_file: self.remove_new_line_marker_from_end_of_file,
             normalize_new_line_markers: self.normalize_new_line_markers,
-            remove_trailing_whitespace: self.remove_trailing_whitespace,
+            remove_trailing_whitespace: self.remove_trailing_whitespace.is_some(),
+            trailing_whitespace_lines: self.remove_trailing_whitespace.clone().unwrap_or_default(),
+            markdown_hard_breaks: self.markdown_hard_breaks,
+            trailing_whitespace_chars: self.trailing_whitespace_chars.clone(),
             remove_trailing_empty_lines: self.remove_trailing_empty_lines,
-            new_line_marker: self.new_line_marker.clone(),
+            max_trailing_blank_lines: self.max_trailing_blank_lines,
+            new_line_marker: self
+                .custom_new_line_marker
+                .clone()
+                .unwrap_or_else(|| self.new_line_marker.clone()),
             normalize_empty_files: self.normalize_empty_files.clone(),
             normalize_whitespace_only_files: self.normalize_whitespace_only_files.clone(),
             replace_tabs_with_spaces: self.replace_tabs_with_spaces,
+            retab_tabs_to_spaces: self.retab,
+            expand_tabs_to_stops: self.expand_tabs_to_stops.map(|width| width as usize),
+            tab_stop_origin: self.tab_stop_origin,
             normalize_non_standard_whitespace: self.normalize_non_standard_whitespace.clone(),
+            normalize_unicode_whitespace: self.normalize_unicode_whitespace.clone(),
+            warn_exotic_trailing_whitespace: self.warn_exotic_trailing_whitespace,
+            warn_if_minified: self.warn_if_minified,
+            warn_double_encoded_bom: self.warn_double_encoded_bom,
+            strip_bom: self.strip_bom,
+            add_bom: self.add_bom,
+            max_whitespace_only_size: self.max_whitespace_only_size,
+            apply_line_rules_to_whitespace_only_files: self
+                .apply_line_rules_to_whitespace_only_files,
+            normalize_unicode_line_separators: self.normalize_unicode_line_separators,
+            ensure_trailing_newlines: self.ensure_trailing_newlines,
+            blank_lines_include_whitespace: self.blank_lines_include_whitespace,
+            region_begin: self.region_begin.clone(),
+            region_end: self.region_end.clone(),
+            line_ranges: self.line_range.clone(),
+        };
+        if self.only.is_empty() {
+            options
+        } else {
+            options.restricted_to_rules(&self.only)
+        }
+    }
+
+    /// Extracts the settings for processing files from command line arguments.
+    pub fn get_run_settings(&self) -> RunSettings {
+        RunSettings {
+            backup_suffix: self.backup.clone(),
+            output_suffix: self.suffix.clone(),
+            output_dir: self.output_dir.clone(),
+            pipe_through: self.pipe_through.clone(),
+            preserve_mtime: self.preserve_mtime,
+            show_diff: self.show_diff,
+            explain_line_endings: self.explain_line_endings,
+            compute_diff_stat: self.stat,
+            measure_processing_time: self.timings,
+            // Only the statistics report the number of lines.
+            count_lines: self.stats,
+            compute_hash: self.print_hashes.is_some(),
+            streaming_write_threshold: STREAMING_WRITE_THRESHOLD,
+            memory_map: self.mmap,
+            assert_idempotent: self.assert_idempotent,
+            verify_writes: self.verify_writes,
+            handle_gzip: self.handle_gzip,
+            honor_ignore_file_directive: self.honor_ignore_file_directive,
+            content_matches: self.content_matches.clone(),
+            process_binary: self.process_binary,
         }
     }
 }
 
 /// Determines if a string consists of only whitespace.
+/// The scan stops at the first non-whitespace character, so only files
+/// that really consist of whitespace only are scanned completely.
 fn is_whitespace_only(input_data: &[u8]) -> bool {
     for char in input_data {
         match *char {
@@ -128,10 +454,267 @@ fn is_whitespace_only(input_data: &[u8]) -> bool {
     true
 }
 
-/// Computes the most common new line marker in a string.
-/// If there are ties, prefer Linux to Windows to MacOS.
-/// If there are no new line markers, return Linux.
-fn find_most_common_new_line_marker(input_data: &[u8]) -> NewLineMarker {
+/// Determines if a line ends with a non-ASCII whitespace character
+/// (e.g. no-break space U+00A0 or ideographic space U+3000),
+/// possibly followed by ASCII whitespace.
+/// The line must not contain the new line marker.
+fn ends_with_exotic_whitespace(line: &[u8]) -> bool {
+    let mut end = line.len();
+    while end > 0 && matches!(line[end - 1], SPACE | TAB | VERTICAL_TAB | FORM_FEED) {
+        end -= 1;
+    }
+
+    // Find the first byte of the last UTF-8 encoded character.
+    // A UTF-8 encoded character is at most 4 bytes long and
+    // all its bytes except the first one are of the form 0b10xxxxxx.
+    let mut start = end;
+    while start > 0 && end - start < 4 {
+        start -= 1;
+        if line[start] & 0b1100_0000 != 0b1000_0000 {
+            break;
+        }
+    }
+
+    match std::str::from_utf8(&line[start..end]) {
+        Ok(tail) => tail
+            .chars()
+            .next_back()
+            .is_some_and(|char| char.is_whitespace() && !char.is_ascii()),
+        Err(_) => false,
+    }
+}
+
+/// Files with at most this many lines are checked if they look minified.
+const MINIFIED_MAXIMUM_NUMBER_OF_LINES: usize = 10;
+
+/// Files with lines longer than this on average (in bytes) look minified.
+const MINIFIED_MINIMUM_AVERAGE_LINE_LENGTH: usize = 500;
+
+/// Heuristically decides if a file looks minified, e.g. minified JavaScript or CSS.
+/// Such a file has only a few lines and the lines are very long on average.
+/// Returns the average length of the lines if the file looks minified.
+fn looks_minified(input_data: &[u8]) -> Option<usize> {
+    let number_of_lines = count_lines(input_data);
+    if number_of_lines == 0 || number_of_lines > MINIFIED_MAXIMUM_NUMBER_OF_LINES {
+        return None;
+    }
+    let average_line_length = input_data.len() / number_of_lines;
+    (average_line_length > MINIFIED_MINIMUM_AVERAGE_LINE_LENGTH).then_some(average_line_length)
+}
+
+/// Determines if a file starts with a double-encoded byte order mark,
+/// possibly after a correctly encoded one.
+fn has_double_encoded_byte_order_mark(input_data: &[u8]) -> bool {
+    input_data
+        .strip_prefix(BYTE_ORDER_MARK)
+        .unwrap_or(input_data)
+        .starts_with(DOUBLE_ENCODED_BYTE_ORDER_MARK)
+}
+
+/// Finds lines that end with non-ASCII whitespace.
+/// Returns the line numbers of such lines.
+fn find_lines_with_exotic_trailing_whitespace(input_data: &[u8]) -> Vec<usize> {
+    let mut line_numbers: Vec<usize> = Vec::new();
+    let mut line_number: usize = 1;
+    let mut start_of_line: usize = 0;
+    let mut i: usize = 0;
+
+    while i <= input_data.len() {
+        if i == input_data.len() || input_data[i] == CARRIAGE_RETURN || input_data[i] == LINE_FEED {
+            if ends_with_exotic_whitespace(&input_data[start_of_line..i]) {
+                line_numbers.push(line_number);
+            }
+            if i < input_data.len()
+                && input_data[i] == CARRIAGE_RETURN
+                && i + 1 < input_data.len()
+                && input_data[i + 1] == LINE_FEED
+            {
+                // Skip the second byte of the Windows new line marker.
+                i += 1;
+            }
+            line_number += 1;
+            start_of_line = i + 1;
+        }
+        i += 1;
+    }
+
+    line_numbers
+}
+
+/// Returns the Unicode line separator (U+2028) or paragraph separator (U+2029)
+/// whose UTF-8 encoding starts at position `i`, if there is one.
+///
+/// In valid UTF-8, the byte `0xE2` can only be the first byte of a multibyte sequence.
+/// Hence, a match can never be a part of another character.
+fn unicode_line_separator_at(input_data: &[u8], i: usize) -> Option<char> {
+    let bytes = &input_data[i..];
+    if bytes.starts_with(LINE_SEPARATOR) {
+        Some('\u{2028}')
+    } else if bytes.starts_with(PARAGRAPH_SEPARATOR) {
+        Some('\u{2029}')
+    } else {
+        None
+    }
+}
+
+/// Determines if a string contains a non-empty substring.
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// Returns the Unicode whitespace character whose UTF-8 encoding
+/// starts at position `i`, if there is one.
+///
+/// ASCII bytes are never a part of a multibyte sequence, so they are skipped quickly.
+/// Invalid UTF-8 sequences are not whitespace.
+fn unicode_whitespace_at(input_data: &[u8], i: usize) -> Option<char> {
+    let length = match input_data[i] {
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => return None,
+    };
+    let bytes = input_data.get(i..i + length)?;
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|string| string.chars().next())
+        .filter(|char| UNICODE_WHITESPACE.contains(char))
+}
+
+/// Counts the lines in a string. Every new line marker (`\n`, `\r` or `\r\n`)
+/// ends a line. The last line is counted even if it does not end with a new line marker,
+/// unless it is empty.
+fn count_lines(input_data: &[u8]) -> usize {
+    let mut number_of_lines: usize = 0;
+    for (i, char) in input_data.iter().enumerate() {
+        if *char == LINE_FEED
+            || (*char == CARRIAGE_RETURN && input_data.get(i + 1) != Some(&LINE_FEED))
+        {
+            number_of_lines += 1;
+        }
+    }
+    if input_data
+        .last()
+        .is_some_and(|char| *char != LINE_FEED && *char != CARRIAGE_RETURN)
+    {
+        number_of_lines += 1;
+    }
+    number_of_lines
+}
+
+/// Number of characters in UTF-8 encoded data, i.e., its width on the screen
+/// assuming each character occupies one column.
+fn count_columns(data: &[u8]) -> usize {
+    data.iter().filter(|byte| (**byte & 0xC0) != 0x80).count()
+}
+
+/// Replaces each tab in the indentation of a line by `tab_width` spaces.
+/// The conversion is verified by collapsing each `tab_width` spaces of the result
+/// back to a tab. If it does not give back the original indentation, e.g. because
+/// there is a tab after a space, the conversion is ambiguous and `None` is returned.
+fn expand_indentation(indentation: &[u8], tab_width: usize) -> Option<Vec<u8>> {
+    let number_of_tabs = indentation.iter().filter(|char| **char == TAB).count();
+    let number_of_spaces = indentation.len() - number_of_tabs;
+    let expanded = vec![SPACE; number_of_tabs * tab_width + number_of_spaces];
+
+    let mut collapsed = vec![TAB; expanded.len() / tab_width];
+    collapsed.resize(collapsed.len() + expanded.len() % tab_width, SPACE);
+    if collapsed == indentation {
+        Some(expanded)
+    } else {
+        None
+    }
+}
+
+/// Returns the length of the indentation, i.e., the spaces and tabs at the start of the data.
+fn indentation_length(data: &[u8]) -> usize {
+    data.iter()
+        .position(|char| *char != SPACE && *char != TAB)
+        .unwrap_or(data.len())
+}
+
+/// Finds lines whose indentation contains tabs that cannot be expanded unambiguously.
+/// Returns the line numbers of such lines.
+fn find_lines_with_ambiguous_indentation(input_data: &[u8], tab_width: usize) -> Vec<usize> {
+    let mut line_numbers: Vec<usize> = Vec::new();
+    let mut line_number: usize = 1;
+    let mut i: usize = 0;
+
+    while i < input_data.len() {
+        let indentation = &input_data[i..i + indentation_length(&input_data[i..])];
+        if indentation.contains(&TAB) && expand_indentation(indentation, tab_width).is_none() {
+            line_numbers.push(line_number);
+        }
+        match input_data[i..]
+            .iter()
+            .position(|char| *char == CARRIAGE_RETURN || *char == LINE_FEED)
+        {
+            Some(position) => {
+                i += position;
+                if input_data[i..].starts_with(b"\r\n") {
+                    i += 1;
+                }
+                i += 1;
+                line_number += 1;
+            }
+            None => break,
+        }
+    }
+
+    line_numbers
+}
+
+/// Numbers of new line markers of each type in a string.
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
+pub struct NewLineMarkerCounts {
+    pub linux: usize,
+    pub mac: usize,
+    pub windows: usize,
+}
+
+impl AddAssign for NewLineMarkerCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.linux += other.linux;
+        self.mac += other.mac;
+        self.windows += other.windows;
+    }
+}
+
+impl fmt::Display for NewLineMarkerCounts {
+    /// Human-readable numbers of new line markers, e.g.
+    /// "2 Windows (\r\n), 0 Linux (\n), 0 MacOS (\r) line ending(s)".
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} Windows (\\r\\n), {} Linux (\\n), {} MacOS (\\r) line ending(s)",
+            self.windows, self.linux, self.mac
+        )
+    }
+}
+
+impl NewLineMarkerCounts {
+    /// The most common new line marker.
+    /// If there are ties, prefer Linux to Windows to MacOS.
+    /// If there are no new line markers, return Linux.
+    fn most_common(&self) -> NewLineMarker {
+        // The markers in the order of preference. A marker wins only if it is strictly
+        // more common than all the markers before it, so the earlier marker wins a tie.
+        let mut most_common = (NewLineMarker::Linux, self.linux);
+        for (new_line_marker, count) in [
+            (NewLineMarker::Windows, self.windows),
+            (NewLineMarker::Mac, self.mac),
+        ] {
+            if count > most_common.1 {
+                most_common = (new_line_marker, count);
+            }
+        }
+        most_common.0
+    }
+}
+
+/// Counts the new line markers of each type in a string.
+pub fn count_new_line_markers(input_data: &[u8]) -> NewLineMarkerCounts {
     let mut linux_count: usize = 0;
     let mut mac_count: usize = 0;
     let mut windows_count: usize = 0;
@@ -139,7 +722,7 @@ fn find_most_common_new_line_marker(input_data: &[u8]) -> NewLineMarker {
 
     while i < input_data.len() {
         if input_data[i] == CARRIAGE_RETURN {
-            if i < input_data.len() - 1 && input_data[i + 1] == LINE_FEED {
+            if i + 1 < input_data.len() && input_data[i + 1] == LINE_FEED {
                 windows_count += 1;
                 i += 1;
             } else {
@@ -151,12 +734,86 @@ fn find_most_common_new_line_marker(input_data: &[u8]) -> NewLineMarker {
         i += 1;
     }
 
-    if mac_count > windows_count && mac_count > linux_count {
-        return NewLineMarker::Mac;
-    } else if windows_count > linux_count {
-        return NewLineMarker::Windows;
+    NewLineMarkerCounts {
+        linux: linux_count,
+        mac: mac_count,
+        windows: windows_count,
+    }
+}
+
+/// Computes the most common new line marker in a string.
+/// If there are ties, prefer Linux to Windows to MacOS.
+/// If there are no new line markers, return Linux.
+fn find_most_common_new_line_marker(input_data: &[u8]) -> NewLineMarker {
+    count_new_line_markers(input_data).most_common()
+}
+
+/// Finds the last new line marker in a string, if there is any.
+fn find_last_new_line_marker(input_data: &[u8]) -> Option<NewLineMarker> {
+    let i = input_data
+        .iter()
+        .rposition(|char| *char == CARRIAGE_RETURN || *char == LINE_FEED)?;
+    if input_data[i] == CARRIAGE_RETURN {
+        Some(NewLineMarker::Mac)
+    } else if i > 0 && input_data[i - 1] == CARRIAGE_RETURN {
+        Some(NewLineMarker::Windows)
+    } else {
+        Some(NewLineMarker::Linux)
+    }
+}
+
+/// Explains the line endings of a file: how many new line markers of each type it contains,
+/// which one is dominant, and whether the formatting normalizes them.
+/// If the file contains Windows new line markers and the program does not run on Windows,
+/// a hint about the `core.autocrlf` setting of git is added.
+fn explain_line_endings(
+    input_data: &[u8],
+    changes: &[Change],
+    check_only: bool,
+    is_windows: bool,
+) -> String {
+    let counts = count_new_line_markers(input_data);
+    let mut explanation = format!("{}.", counts);
+    if counts.windows + counts.linux + counts.mac > 0 {
+        explanation.push_str(&format!(
+            " The dominant line ending is {}.",
+            counts.most_common()
+        ));
+    }
+
+    let mut number_of_normalized: usize = 0;
+    let mut normalized_to: Option<&NewLineMarker> = None;
+    for change in changes {
+        if let ChangeType::ReplacedNewLineMarker(_, new_line_marker) = change.change_type() {
+            number_of_normalized += 1;
+            normalized_to = Some(new_line_marker);
+        }
+    }
+    let is_mixed = [counts.windows, counts.linux, counts.mac]
+        .iter()
+        .filter(|count| **count > 0)
+        .count()
+        > 1;
+    match normalized_to {
+        Some(new_line_marker) => explanation.push_str(&format!(
+            " {} line ending(s){}normalized to {}.",
+            number_of_normalized,
+            if check_only { " would be " } else { " " },
+            new_line_marker
+        )),
+        None if is_mixed => explanation.push_str(
+            " The line endings are mixed, but they are not normalized without --normalize-new-line-markers.",
+        ),
+        None => explanation.push_str(" No normalization is needed."),
+    }
+
+    if counts.windows > 0 && !is_windows {
+        explanation.push_str(
+            " Hint: Windows line endings on a non-Windows system are often caused by \
+             the git setting core.autocrlf. Consider running `git config core.autocrlf input`.",
+        );
     }
-    NewLineMarker::Linux
+    explanation
 }
 
 /// The core formatting algorithm for making changes in a file.
@@ -164,127 +821,332 @@ fn find_most_common_new_line_marker(input_data: &[u8]) -> NewLineMarker {
 /// that supports writing bytes and rewinds. The rewinds are used when deleting
 /// trailing whitespace.
 fn modify_content<T: Writer>(input_data: &[u8], options: &Options, writer: &mut T) -> Vec<Change> {
+    // If only regions or line ranges of the file are formatted, the operations
+    // that change the whole file are suppressed.
+    let restricted_options: Options;
+    let options = if options.formats_only_some_lines() {
+        restricted_options = options.without_whole_file_operations();
+        &restricted_options
+    } else {
+        options
+    };
+
     // Figure out what new line marker to use when writing to the output buffer.
     let output_new_line_marker = match options.new_line_marker {
         OutputNewLineMarkerMode::Auto => find_most_common_new_line_marker(input_data),
         OutputNewLineMarkerMode::Linux => NewLineMarker::Linux,
         OutputNewLineMarkerMode::Mac => NewLineMarker::Mac,
         OutputNewLineMarkerMode::Windows => NewLineMarker::Windows,
+        OutputNewLineMarkerMode::Custom(ref bytes) => NewLineMarker::Custom(bytes.clone()),
+        OutputNewLineMarkerMode::KeepPerLine => {
+            find_last_new_line_marker(input_data).unwrap_or(NewLineMarker::Linux)
+        }
+    };
+
+    // Existing new line markers are never replaced if each line keeps its own marker.
+    let normalize_new_line_markers = options.normalize_new_line_markers
+        && options.new_line_marker != OutputNewLineMarkerMode::KeepPerLine;
+
+    // List of changes between input and output.
+    let mut changes: Vec<Change> = Vec::new();
+
+    // Handle byte order mark. The byte order mark is not part of the content.
+    // In particular, it is not taken into account when deciding whether
+    // the file is empty or consists of whitespace only.
+    let has_byte_order_mark = input_data.starts_with(BYTE_ORDER_MARK);
+    if has_byte_order_mark && options.strip_bom {
+        changes.push(Change::new(1, ChangeType::RemovedByteOrderMark));
+    } else if !has_byte_order_mark && options.add_bom {
+        changes.push(Change::new(1, ChangeType::AddedByteOrderMark));
+    }
+    if (has_byte_order_mark && !options.strip_bom) || options.add_bom {
+        writer.write_bytes(BYTE_ORDER_MARK);
+    }
+    let input_data = if has_byte_order_mark {
+        &input_data[BYTE_ORDER_MARK.len()..]
+    } else {
+        input_data
     };
 
     // Handle empty file.
     if input_data.is_empty() {
-        return match options.normalize_empty_files {
-            TrivialFileReplacementMode::Empty | TrivialFileReplacementMode::Ignore => Vec::new(),
+        match options.normalize_empty_files {
+            TrivialFileReplacementMode::Empty | TrivialFileReplacementMode::Ignore => {}
             TrivialFileReplacementMode::OneLine => {
                 writer.write_bytes(output_new_line_marker.to_bytes());
-                Vec::from([Change::new(1, ChangeType::ReplacedEmptyFileWithOneLine)])
+                changes.push(Change::new(1, ChangeType::ReplacedEmptyFileWithOneLine));
             }
         };
+        return changes;
     }
 
     // Handle non-empty file consisting of whitespace only.
-    if is_whitespace_only(input_data) {
-        return match options.normalize_whitespace_only_files {
-            TrivialFileReplacementMode::Empty => Vec::from([Change::new(
+    // This takes precedence over the line rules, such as removing trailing whitespace.
+    // Files larger than the limit are never considered to be whitespace-only,
+    // and neither are any files if the line rules apply to whitespace-only files.
+    // They are processed line by line like any other file.
+    let is_within_whitespace_only_size_limit = options
+        .max_whitespace_only_size
+        .is_none_or(|limit| input_data.len() <= limit);
+    if !options.apply_line_rules_to_whitespace_only_files
+        && is_within_whitespace_only_size_limit
+        && is_whitespace_only(input_data)
+    {
+        match options.normalize_whitespace_only_files {
+            TrivialFileReplacementMode::Empty => changes.push(Change::new(
                 1,
                 ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile,
-            )]),
+            )),
             TrivialFileReplacementMode::Ignore => {
                 writer.write_bytes(input_data);
-                Vec::new()
             }
             TrivialFileReplacementMode::OneLine => {
                 writer.write_bytes(output_new_line_marker.to_bytes());
-                if input_data == output_new_line_marker.to_bytes() {
-                    Vec::new()
-                } else {
-                    Vec::from([Change::new(
+                if input_data != output_new_line_marker.to_bytes() {
+                    changes.push(Change::new(
                         1,
                         ChangeType::ReplacedWhiteSpaceOnlyFileWithOneLine,
-                    )])
+                    ));
                 }
             }
         };
+        return changes;
     }
 
     // Index into the input buffer.
     let mut i: usize = 0;
 
-    // List of changes between input and output.
-    let mut changes: Vec<Change> = Vec::new();
+    // Position in the output buffer where the content starts, i.e., after the byte order mark.
+    let start_of_content: usize = writer.position();
 
     // Line number. It is incremented every time we encounter a new end of line marker.
     let mut line_number: usize = 1;
 
+    // Index into the input buffer where the current line starts.
+    // Columns of changes are computed relative to it.
+    let mut start_of_line: usize = 0;
+
+    // Index into the input buffer one byte past the last non-whitespace character.
+    let mut last_non_whitespace_in_input: usize = 0;
+
+    // Position in the output buffer and index into the input buffer one byte past
+    // the last whitespace character that is not removed as trailing whitespace,
+    // see `trailing_whitespace_chars`.
+    let mut last_kept_whitespace: usize = start_of_content;
+    let mut last_kept_whitespace_in_input: usize = 0;
+
     // Position one byte past the end of last line in the output buffer
     // including the last end of line marker.
-    let mut last_end_of_line_including_eol_marker: usize = 0;
+    let mut last_end_of_line_including_eol_marker: usize = start_of_content;
 
     // Position one byte past the last non-whitespace character in the output buffer.
-    let mut last_non_whitespace: usize = 0;
+    let mut last_non_whitespace: usize = start_of_content;
 
     // Position one byte past the end of last non-empty line in the output buffer
     // excluding the last end of line marker.
-    let mut last_end_of_non_empty_line_excluding_eol_marker: usize = 0;
+    let mut last_end_of_non_empty_line_excluding_eol_marker: usize = start_of_content;
 
     // Position one byte past the end of last non-empty line in the output buffer,
     // including the last end of line marker.
-    let mut last_end_of_non_empty_line_including_eol_marker: usize = 0;
+    let mut last_end_of_non_empty_line_including_eol_marker: usize = start_of_content;
 
     // Line number of the last non-empty line.
     let mut last_non_empty_line_number: usize = 0;
 
+    // New line markers in the output buffer after the end of the last non-empty line,
+    // including the new line marker of the last non-empty line.
+    let mut trailing_new_line_markers: Vec<NewLineMarker> = Vec::new();
+
+    // Positions one byte past the end of each empty line after the last non-empty line
+    // in the output buffer, including the new line markers.
+    let mut trailing_empty_line_ends: Vec<usize> = Vec::new();
+
+    // Whether the current line is inside a region delimited by the region markers.
+    let mut is_in_region: bool = false;
+
+    // Visual column (zero-based) in the output reached at the index `column_index`
+    // into the input buffer. Used for expanding tabs to tab stops. It is updated
+    // lazily whenever a tab is encountered, and it is reset at the start of each line.
+    let mut visual_column: usize = 0;
+    let mut column_index: usize = 0;
+
     while i < input_data.len() {
-        if input_data[i] == CARRIAGE_RETURN || input_data[i] == LINE_FEED {
+        // Lines outside of the regions or the line ranges are copied verbatim.
+        if options.formats_only_some_lines() && i == start_of_line {
+            let end_of_line = input_data[i..]
+                .iter()
+                .position(|char| *char == CARRIAGE_RETURN || *char == LINE_FEED)
+                .map_or(input_data.len(), |position| i + position);
+            let line = &input_data[i..end_of_line];
+            let is_formatted_line = match options.region_markers() {
+                Some((region_begin, region_end)) => {
+                    let is_formatted_line = is_in_region && !contains(line, region_end);
+                    is_in_region = if is_in_region {
+                        !contains(line, region_end)
+                    } else {
+                        contains(line, region_begin)
+                    };
+                    is_formatted_line
+                }
+                None => options
+                    .line_ranges
+                    .iter()
+                    .any(|line_range| line_range.contains(line_number)),
+            };
+
+            if !is_formatted_line {
+                let mut end_of_line_including_eol_marker = end_of_line;
+                if input_data[end_of_line..].starts_with(b"\r\n") {
+                    end_of_line_including_eol_marker += 2;
+                } else if end_of_line < input_data.len() {
+                    end_of_line_including_eol_marker += 1;
+                }
+
+                writer.write_bytes(line);
+                last_non_whitespace = writer.position();
+                last_non_whitespace_in_input = end_of_line;
+                last_end_of_non_empty_line_excluding_eol_marker = writer.position();
+                writer.write_bytes(&input_data[end_of_line..end_of_line_including_eol_marker]);
+                last_end_of_non_empty_line_including_eol_marker = writer.position();
+                last_non_empty_line_number = line_number;
+                trailing_new_line_markers.clear();
+                trailing_empty_line_ends.clear();
+                writer.commit(last_end_of_non_empty_line_excluding_eol_marker);
+
+                if end_of_line < end_of_line_including_eol_marker {
+                    last_end_of_line_including_eol_marker = writer.position();
+                    line_number += 1;
+                }
+                i = end_of_line_including_eol_marker;
+                start_of_line = i;
+                continue;
+            }
+        }
+
+        // Expand tabs in the indentation of the line, unless the conversion is ambiguous.
+        if let Some(tab_width) = options.retab_tabs_to_spaces {
+            if i == start_of_line {
+                let indentation = &input_data[i..i + indentation_length(&input_data[i..])];
+                if indentation.contains(&TAB) {
+                    if let Some(expanded) = expand_indentation(indentation, tab_width) {
+                        writer.write_bytes(&expanded);
+                        changes.push(
+                            Change::new(line_number, ChangeType::ExpandedIndentationTabs)
+                                .with_column(1),
+                        );
+                        i += indentation.len();
+                        continue;
+                    }
+                }
+            }
+        }
+
+        // Unicode line separators are treated as new line markers only if requested.
+        let unicode_line_separator: Option<char> = if options.normalize_unicode_line_separators {
+            unicode_line_separator_at(input_data, i)
+        } else {
+            None
+        };
+
+        // Unicode whitespace characters are recognized only if requested.
+        let unicode_whitespace: Option<char> =
+            if options.normalize_unicode_whitespace != UnicodeWhitespaceReplacementMode::Ignore {
+                unicode_whitespace_at(input_data, i)
+            } else {
+                None
+            };
+
+        if input_data[i] == CARRIAGE_RETURN
+            || input_data[i] == LINE_FEED
+            || unicode_line_separator.is_some()
+        {
+            // Position of the new line marker in the input.
+            let end_of_line_in_input = i;
+
             // Parse the new line marker
             let new_line_marker: NewLineMarker;
             if input_data[i] == LINE_FEED {
                 new_line_marker = NewLineMarker::Linux;
-            } else if i < input_data.len() - 1 && input_data[i + 1] == LINE_FEED {
+            } else if input_data[i] == CARRIAGE_RETURN
+                && i + 1 < input_data.len()
+                && input_data[i + 1] == LINE_FEED
+            {
                 new_line_marker = NewLineMarker::Windows;
                 // Windows new line marker consists of two bytes.
                 // Skip the extra byte.
                 i += 1;
-            } else {
+            } else if input_data[i] == CARRIAGE_RETURN {
                 new_line_marker = NewLineMarker::Mac;
+            } else {
+                // Unicode line separator is always replaced by the output new line marker.
+                new_line_marker = output_new_line_marker.clone();
+                // The UTF-8 encoding of the separator consists of three bytes.
+                // Skip the extra bytes.
+                i += 2;
             }
 
             // Remove trailing whitespace
-            if options.remove_trailing_whitespace
-                && max(last_non_whitespace, last_end_of_line_including_eol_marker)
-                    < writer.position()
+            let end_of_kept_content = max(
+                max(last_non_whitespace, last_kept_whitespace),
+                last_end_of_line_including_eol_marker,
+            );
+            let is_blank_line = last_non_whitespace <= last_end_of_line_including_eol_marker;
+            let kept_spaces = if is_blank_line {
+                0
+            } else {
+                options.hard_line_break_length(&input_data[..end_of_line_in_input])
+            };
+            if options.removes_trailing_whitespace(is_blank_line)
+                && end_of_kept_content + kept_spaces < writer.position()
             {
-                changes.push(Change::new(
-                    line_number,
-                    ChangeType::RemovedTrailingWhitespace,
-                ));
-                writer.rewind(max(
-                    last_non_whitespace,
-                    last_end_of_line_including_eol_marker,
-                ));
+                let end_of_kept_content_in_input = max(
+                    max(last_non_whitespace_in_input, last_kept_whitespace_in_input),
+                    start_of_line,
+                );
+                changes.push(
+                    Change::new(line_number, ChangeType::RemovedTrailingWhitespace)
+                        .with_column(end_of_kept_content_in_input - start_of_line + 1),
+                );
+                writer.rewind(end_of_kept_content);
+                for _ in 0..kept_spaces {
+                    writer.write(SPACE);
+                }
             }
 
-            // Determine if the last line is empty
-            let is_empty_line: bool = last_end_of_line_including_eol_marker == writer.position();
+            // Determine if the last line is empty. Optionally, lines consisting of
+            // whitespace only (spaces, tabs, '\v', '\f') are considered empty too.
+            let is_empty_line: bool = last_end_of_line_including_eol_marker == writer.position()
+                || (options.blank_lines_include_whitespace
+                    && last_non_whitespace <= last_end_of_line_including_eol_marker);
 
             // Position one byte past the end of last line in the output buffer
             // excluding the last end of line marker.
             let last_end_of_line_excluding_eol_marker: usize = writer.position();
 
             // Add new line marker
-            if options.normalize_new_line_markers && output_new_line_marker != new_line_marker {
+            let written_new_line_marker: NewLineMarker;
+            if let Some(separator) = unicode_line_separator {
+                changes.push(Change::new(
+                    line_number,
+                    ChangeType::ReplacedUnicodeLineSeparator(
+                        separator,
+                        output_new_line_marker.clone(),
+                    ),
+                ));
+                written_new_line_marker = output_new_line_marker.clone();
+            } else if normalize_new_line_markers && output_new_line_marker != new_line_marker {
                 changes.push(Change::new(
                     line_number,
                     ChangeType::ReplacedNewLineMarker(
-                        new_line_marker.clone(),
+                        new_line_marker,
                         output_new_line_marker.clone(),
                     ),
                 ));
-                writer.write_bytes(output_new_line_marker.to_bytes());
+                written_new_line_marker = output_new_line_marker.clone();
             } else {
-                writer.write_bytes(new_line_marker.to_bytes());
+                written_new_line_marker = new_line_marker;
             }
+            writer.write_bytes(written_new_line_marker.to_bytes());
             last_end_of_line_including_eol_marker = writer.position();
 
             // Update position of last non-empty line.
@@ -294,21 +1156,65 @@ fn modify_content<T: Writer>(input_data: &[u8], options: &Options, writer: &mut
                 last_end_of_non_empty_line_including_eol_marker =
                     last_end_of_line_including_eol_marker;
                 last_non_empty_line_number = line_number;
+                trailing_new_line_markers.clear();
+                trailing_empty_line_ends.clear();
+
+                // The output before the end of the last non-empty line is never rewound.
+                writer.commit(last_end_of_non_empty_line_excluding_eol_marker);
+            } else {
+                trailing_empty_line_ends.push(last_end_of_line_including_eol_marker);
             }
+            trailing_new_line_markers.push(written_new_line_marker);
             line_number += 1;
+            start_of_line = i + 1;
         } else if input_data[i] == SPACE {
             writer.write(input_data[i]);
         } else if input_data[i] == TAB {
-            if options.replace_tabs_with_spaces < 0 {
+            if let Some(tab_width) = options.expand_tabs_to_stops {
+                if column_index < start_of_line {
+                    column_index = start_of_line;
+                    visual_column = 0;
+                }
+                visual_column += count_columns(&input_data[column_index..i]);
+                // Tab stops are at the origin and every tab width columns after it.
+                let number_of_spaces = if visual_column < options.tab_stop_origin {
+                    options.tab_stop_origin - visual_column
+                } else {
+                    tab_width - (visual_column - options.tab_stop_origin) % tab_width
+                };
+                changes.push(
+                    Change::new(
+                        line_number,
+                        ChangeType::ReplacedTabWithSpaces(number_of_spaces),
+                    )
+                    .with_column(i - start_of_line + 1),
+                );
+                for _ in 0..number_of_spaces {
+                    writer.write(SPACE);
+                }
+                visual_column += number_of_spaces;
+                column_index = i + 1;
+            } else if options.replace_tabs_with_spaces < 0 {
                 writer.write(input_data[i]);
             } else if options.replace_tabs_with_spaces > 0 {
-                changes.push(Change::new(line_number, ChangeType::ReplacedTabWithSpaces));
+                changes.push(
+                    Change::new(
+                        line_number,
+                        ChangeType::ReplacedTabWithSpaces(
+                            options.replace_tabs_with_spaces as usize,
+                        ),
+                    )
+                    .with_column(i - start_of_line + 1),
+                );
                 for _ in 0..options.replace_tabs_with_spaces {
                     writer.write(SPACE);
                 }
             } else {
                 // Remove the tab character.
-                changes.push(Change::new(line_number, ChangeType::RemovedTab));
+                changes.push(
+                    Change::new(line_number, ChangeType::RemovedTab)
+                        .with_column(i - start_of_line + 1),
+                );
             }
         } else if input_data[i] == VERTICAL_TAB || input_data[i] == FORM_FEED {
             match options.normalize_non_standard_whitespace {
@@ -317,22 +1223,62 @@ fn modify_content<T: Writer>(input_data: &[u8], options: &Options, writer: &mut
                 }
                 NonStandardWhitespaceReplacementMode::ReplaceWithSpace => {
                     writer.write(SPACE);
-                    changes.push(Change::new(
-                        line_number,
-                        ChangeType::ReplacedNonstandardWhitespaceBySpace(input_data[i]),
-                    ));
+                    changes.push(
+                        Change::new(
+                            line_number,
+                            ChangeType::ReplacedNonstandardWhitespaceBySpace(input_data[i]),
+                        )
+                        .with_column(i - start_of_line + 1),
+                    );
                 }
                 NonStandardWhitespaceReplacementMode::Remove => {
                     // Remove the non-standard whitespace character.
-                    changes.push(Change::new(
-                        line_number,
-                        ChangeType::RemovedNonstandardWhitespace(input_data[i]),
-                    ));
+                    changes.push(
+                        Change::new(
+                            line_number,
+                            ChangeType::RemovedNonstandardWhitespace(input_data[i]),
+                        )
+                        .with_column(i - start_of_line + 1),
+                    );
                 }
             }
+        } else if let Some(char) = unicode_whitespace {
+            let column = i - start_of_line + 1;
+            if options.normalize_unicode_whitespace
+                == UnicodeWhitespaceReplacementMode::ReplaceWithSpace
+            {
+                writer.write(SPACE);
+                changes.push(
+                    Change::new(
+                        line_number,
+                        ChangeType::ReplacedUnicodeWhitespaceBySpace(char),
+                    )
+                    .with_column(column),
+                );
+            } else {
+                // Remove the Unicode whitespace character.
+                changes.push(
+                    Change::new(line_number, ChangeType::RemovedUnicodeWhitespace(char))
+                        .with_column(column),
+                );
+            }
+            // Skip the extra bytes of the UTF-8 encoding.
+            i += char.len_utf8() - 1;
         } else {
             writer.write(input_data[i]);
             last_non_whitespace = writer.position();
+            last_non_whitespace_in_input = i + 1;
+        }
+
+        // Whitespace characters that are not trailing whitespace are kept at the end of the line.
+        if (input_data[i] == SPACE
+            || input_data[i] == TAB
+            || input_data[i] == VERTICAL_TAB
+            || input_data[i] == FORM_FEED)
+            && !options.is_trailing_whitespace(input_data[i])
+        {
+            last_kept_whitespace = writer.position();
+            last_kept_whitespace_in_input = i + 1;
         }
 
         // Move to the next byte
@@ -340,26 +1286,63 @@ fn modify_content<T: Writer>(input_data: &[u8], options: &Options, writer: &mut
     }
 
     // Remove trailing whitespace from the last line.
-    if options.remove_trailing_whitespace
+    let end_of_kept_content = max(
+        max(last_non_whitespace, last_kept_whitespace),
+        last_end_of_line_including_eol_marker,
+    );
+    let is_blank_line = last_non_whitespace <= last_end_of_line_including_eol_marker;
+    let kept_spaces = if is_blank_line {
+        0
+    } else {
+        options.hard_line_break_length(input_data)
+    };
+    if options.removes_trailing_whitespace(is_blank_line)
         && last_end_of_line_including_eol_marker < writer.position()
-        && last_non_whitespace < writer.position()
+        && end_of_kept_content + kept_spaces < writer.position()
     {
-        changes.push(Change::new(
-            line_number,
-            ChangeType::RemovedTrailingWhitespace,
-        ));
-        writer.rewind(last_non_whitespace);
+        let end_of_kept_content_in_input = max(
+            max(last_non_whitespace_in_input, last_kept_whitespace_in_input),
+            start_of_line,
+        );
+        changes.push(
+            Change::new(line_number, ChangeType::RemovedTrailingWhitespace)
+                .with_column(end_of_kept_content_in_input - start_of_line + 1),
+        );
+        writer.rewind(end_of_kept_content);
+        for _ in 0..kept_spaces {
+            writer.write(SPACE);
+        }
     }
 
-    // Remove trailing empty lines.
-    if options.remove_trailing_empty_lines
-        && last_end_of_line_including_eol_marker == writer.position()
-        && last_end_of_non_empty_line_including_eol_marker < writer.position()
-    {
-        line_number = last_non_empty_line_number + 1;
-        last_end_of_line_including_eol_marker = last_end_of_non_empty_line_including_eol_marker;
-        changes.push(Change::new(line_number, ChangeType::RemovedEmptyLines));
-        writer.rewind(last_end_of_non_empty_line_including_eol_marker);
+    // Remove trailing empty lines, except for the given number of them.
+    // Optionally, the last line is considered empty even if it is not terminated
+    // by a new line marker, as long as it consists of whitespace only.
+    let is_last_line_blank = last_end_of_line_including_eol_marker == writer.position()
+        || (options.blank_lines_include_whitespace
+            && last_non_whitespace <= last_end_of_line_including_eol_marker);
+    let max_trailing_blank_lines = if options.remove_trailing_empty_lines {
+        Some(0)
+    } else {
+        options.max_trailing_blank_lines
+    };
+    if let (Some(max_trailing_blank_lines), true) = (max_trailing_blank_lines, is_last_line_blank) {
+        if last_end_of_line_including_eol_marker < writer.position() {
+            trailing_empty_line_ends.push(writer.position());
+        }
+        if trailing_empty_line_ends.len() > max_trailing_blank_lines {
+            let end_of_kept_lines = match max_trailing_blank_lines {
+                0 => last_end_of_non_empty_line_including_eol_marker,
+                _ => trailing_empty_line_ends[max_trailing_blank_lines - 1],
+            };
+            line_number = last_non_empty_line_number + 1 + max_trailing_blank_lines;
+            last_end_of_line_including_eol_marker = end_of_kept_lines;
+            changes.push(Change::new(
+                line_number,
+                ChangeType::RemovedTrailingEmptyLines,
+            ));
+            writer.rewind(end_of_kept_lines);
+            trailing_new_line_markers.truncate(1 + max_trailing_blank_lines);
+        }
     }
 
     // Add new line marker at the end of the file
@@ -370,8 +1353,11 @@ fn modify_content<T: Writer>(input_data: &[u8], options: &Options, writer: &mut
             line_number,
             ChangeType::NewLineMarkerAddedToEndOfFile,
         ));
+        last_end_of_non_empty_line_excluding_eol_marker = writer.position();
         writer.write_bytes(output_new_line_marker.to_bytes());
         last_end_of_line_including_eol_marker = writer.position();
+        last_non_empty_line_number = line_number;
+        trailing_new_line_markers = vec![output_new_line_marker.clone()];
         line_number += 1;
     }
 
@@ -380,7 +1366,8 @@ fn modify_content<T: Writer>(input_data: &[u8], options: &Options, writer: &mut
         && last_end_of_line_including_eol_marker == writer.position()
         && line_number >= 2
     {
-        line_number = last_non_empty_line_number;
+        // If there is no non-empty line, report the change on the first line.
+        line_number = max(last_non_empty_line_number, 1);
         changes.push(Change::new(
             line_number,
             ChangeType::NewLineMarkerRemovedFromEndOfFile,
@@ -388,36 +1375,1011 @@ fn modify_content<T: Writer>(input_data: &[u8], options: &Options, writer: &mut
         writer.rewind(last_end_of_non_empty_line_excluding_eol_marker);
     }
 
+    // Make the file end with exactly the given number of new line markers.
+    if let Some(number_of_new_line_markers) = options.ensure_trailing_newlines {
+        let is_last_line_terminated = last_end_of_line_including_eol_marker == writer.position();
+        let (current_new_line_markers, end_of_content, line_number) = if is_last_line_terminated {
+            (
+                trailing_new_line_markers.as_slice(),
+                last_end_of_non_empty_line_excluding_eol_marker,
+                max(last_non_empty_line_number, 1),
+            )
+        } else {
+            (&[] as &[NewLineMarker], writer.position(), line_number)
+        };
+        let expected_new_line_markers =
+            vec![output_new_line_marker.clone(); number_of_new_line_markers];
+        if current_new_line_markers != expected_new_line_markers.as_slice() {
+            changes.push(Change::new(
+                line_number,
+                ChangeType::EnsuredTrailingNewLineMarkers(number_of_new_line_markers),
+            ));
+            writer.rewind(end_of_content);
+            for _ in 0..number_of_new_line_markers {
+                writer.write_bytes(output_new_line_marker.to_bytes());
+            }
+        }
+    }
+
     changes
 }
 
-/// Formats or checks a single file and returns the list of changes tha have been
-/// made or would have been made. If check_only is set to true, the file is not modified.
+/// Files of at least this size (in bytes) are formatted directly into the file,
+/// without keeping the formatted content in memory.
+const STREAMING_WRITE_THRESHOLD: usize = 64 * 1024 * 1024;
+
+/// Number of bytes at the beginning of a file that are searched for a NUL byte
+/// to decide if the file is binary.
+const BINARY_DETECTION_LENGTH: usize = 8192;
+
+/// Comment that, if present on the first line of a file,
+/// causes the file to be left untouched.
+const IGNORE_FILE_DIRECTIVE: &[u8] = b"whitespace-format: ignore-file";
+
+/// Reason why a file was not formatted.
+#[derive(PartialEq, Debug, Clone)]
+pub enum SkipReason {
+    /// The first line of the file contains the `whitespace-format: ignore-file` directive.
+    IgnoreFileDirective,
+
+    /// The file starts with a UTF-16 byte order mark. The formatting works on bytes
+    /// of ASCII characters, so it would corrupt UTF-16 encoded files.
+    Utf16Encoding,
+
+    /// The file contains a NUL byte near the beginning, so it is probably a binary file.
+    BinaryFile,
+
+    /// The content of the file does not match the regular expression given by `--content-matches`.
+    ContentDoesNotMatch,
+}
+
+impl fmt::Display for SkipReason {
+    /// Human-readable explanation why the file was skipped.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            SkipReason::IgnoreFileDirective => {
+                f.write_str("whitespace-format: ignore-file directive")
+            }
+            SkipReason::Utf16Encoding => f.write_str("UTF-16 encoded file"),
+            SkipReason::BinaryFile => f.write_str("binary file"),
+            SkipReason::ContentDoesNotMatch => f.write_str("content does not match"),
+        }
+    }
+}
+
+/// Result of formatting or checking a single file.
+#[derive(PartialEq, Debug, Default)]
+pub struct FormatResult {
+    /// Changes that have been made or would have been made to the file.
+    pub changes: Vec<Change>,
+
+    /// Reason why the file was not formatted, if it was skipped.
+    pub skipped: Option<SkipReason>,
+
+    /// Unified diff between the original and the formatted content.
+    /// Computed only if the `show_diff` option is set and the file changes.
+    pub diff: Option<String>,
+
+    /// Number of lines in the original content of the file.
+    /// Computed only if the `count_lines` setting is set. It is zero for skipped files.
+    pub number_of_lines: usize,
+
+    /// Hexadecimal SHA-256 hash of the formatted content of the file.
+    /// In check-only mode, it is the hash of the content the file would have.
+    /// Computed only if the `compute_hash` option is set.
+    pub hash: Option<String>,
+
+    /// Number of lines inserted and deleted by the formatting.
+    /// Computed only if the `compute_diff_stat` option is set and the file changes.
+    pub diff_stat: Option<DiffStat>,
+
+    /// Explanation of the line endings of the file.
+    /// Computed only if the `explain_line_endings` option is set.
+    pub line_endings_explanation: Option<String>,
+
+    /// Size of the original content of the file in bytes.
+    pub input_size: usize,
+
+    /// Size of the formatted content of the file in bytes, before it is piped through
+    /// the `pipe_through` command. It is the same as `input_size` for skipped files.
+    pub output_size: usize,
+
+    /// Wall-clock time spent processing the file, including reading and writing it.
+    /// Computed only if the `measure_processing_time` option is set.
+    pub processing_time: Option<Duration>,
+}
+
+/// Computes the SHA-256 hash of the data as a hexadecimal string.
+fn sha256(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Determines if the first line of a file contains the ignore-file directive.
+fn has_ignore_file_directive(input_data: &[u8]) -> bool {
+    let end_of_first_line = input_data
+        .iter()
+        .position(|char| *char == CARRIAGE_RETURN || *char == LINE_FEED)
+        .unwrap_or(input_data.len());
+    contains(&input_data[..end_of_first_line], IGNORE_FILE_DIRECTIVE)
+}
+
+/// Determines if a file is a Markdown file, i.e., if its extension is `.md` or `.markdown`,
+/// ignoring case. The extension of a gzip-compressed file is the one before `.gz`,
+/// e.g. `README.md.gz` is a Markdown file.
+fn is_markdown_file(file_path: &Path) -> bool {
+    let has_extension = |file_path: &Path, extensions: &[&str]| {
+        file_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                extensions
+                    .iter()
+                    .any(|expected| extension.eq_ignore_ascii_case(expected))
+            })
+    };
+    if has_extension(file_path, &["gz"]) {
+        is_markdown_file(&file_path.with_extension(""))
+    } else {
+        has_extension(file_path, &["md", "markdown"])
+    }
+}
+
+/// Determines if the data starts with a UTF-16 byte order mark,
+/// either little endian (`FF FE`) or big endian (`FE FF`).
+fn has_utf16_byte_order_mark(input_data: &[u8]) -> bool {
+    input_data.starts_with(UTF16_LE_BYTE_ORDER_MARK)
+        || input_data.starts_with(UTF16_BE_BYTE_ORDER_MARK)
+}
+
+/// Determines if the data looks like the content of a binary file,
+/// i.e., if there is a NUL byte near the beginning. Text files never contain NUL bytes.
+fn is_binary(input_data: &[u8]) -> bool {
+    let length = input_data.len().min(BINARY_DETECTION_LENGTH);
+    input_data[..length].contains(&0)
+}
+
+/// Determines if the data is gzip-compressed, i.e., if it starts with the gzip magic number.
+/// Text files never start with it, since it is not valid UTF-8.
+fn is_gzip(input_data: &[u8]) -> bool {
+    input_data.starts_with(GZIP_MAGIC_NUMBER)
+}
+
+/// Decompresses gzip-compressed data.
+fn gzip_decompress(compressed_data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut data: Vec<u8> = Vec::new();
+    io::Read::read_to_end(&mut GzDecoder::new(compressed_data), &mut data)?;
+    Ok(data)
+}
+
+/// Compresses data with gzip.
+fn gzip_compress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    io::Write::write_all(&mut encoder, data)?;
+    encoder.finish()
+}
+
+/// Determines whether a file must be left untouched, and why.
+fn find_skip_reason(input_data: &[u8], settings: &RunSettings) -> Option<SkipReason> {
+    if has_utf16_byte_order_mark(input_data) {
+        Some(SkipReason::Utf16Encoding)
+    } else if !settings.process_binary && is_binary(input_data) {
+        Some(SkipReason::BinaryFile)
+    } else if settings.honor_ignore_file_directive && has_ignore_file_directive(input_data) {
+        Some(SkipReason::IgnoreFileDirective)
+    } else if settings
+        .content_matches
+        .as_ref()
+        .is_some_and(|pattern| !pattern.is_match(input_data))
+    {
+        Some(SkipReason::ContentDoesNotMatch)
+    } else {
+        None
+    }
+}
+
+/// Runs a shell command with the data on its standard input and returns its standard output.
+/// Fails if the command cannot be started or if it exits with a non-zero exit code.
+fn pipe_through(command: &str, data: &[u8]) -> io::Result<Vec<u8>> {
+    let (shell, shell_argument) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = process::Command::new(shell)
+        .arg(shell_argument)
+        .arg(command)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .spawn()?;
+
+    // Write the input in a separate thread, so that the command cannot block
+    // on writing its output while we are blocked on writing its input.
+    let mut stdin = child.stdin.take().expect("The standard input is piped.");
+    let data = data.to_vec();
+    let writer = thread::spawn(move || io::Write::write_all(&mut stdin, &data));
+    let output = child.wait_with_output()?;
+    // The command may exit without reading all of its input, which is not an error.
+    let _ = writer.join();
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(io::Error::other(format!("exited with {}", output.status)))
+    }
+}
+
+/// Path of the file followed by the suffix, e.g. `file.txt.bak`.
+fn path_with_suffix(file_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Writes the original content of a file to a backup file. The name of the backup file
+/// is the name of the file followed by the suffix. An existing backup file is never overwritten.
+fn write_backup(file_path: &Path, input_data: &[u8], suffix: &str) -> Result<(), Error> {
+    let backup_path = path_with_suffix(file_path, suffix);
+    let backup_file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&backup_path);
+    match backup_file {
+        Ok(mut backup_file) => io::Write::write_all(&mut backup_file, input_data)
+            .map_err(|_| Error::CannotWriteFile(backup_path.display().to_string())),
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+            Err(Error::BackupFileExists(backup_path.display().to_string()))
+        }
+        Err(_) => Err(Error::CannotWriteFile(backup_path.display().to_string())),
+    }
+}
+
+/// Overwrites a file with new content. The permissions of the file are preserved,
+/// and so is the modification time if `preserve_mtime` is set.
+/// The file is written in place, so symbolic links and hard links to it still point
+/// to the formatted content. The writing is not atomic: if it fails midway,
+/// the file is left partially written.
+fn write_file(file_path: &Path, output_data: &[u8], preserve_mtime: bool) -> Result<(), Error> {
+    let cannot_write_file = |_| Error::CannotWriteFile(file_path.display().to_string());
+    let metadata = fs::metadata(file_path).map_err(cannot_write_file)?;
+    fs::write(file_path, output_data).map_err(cannot_write_file)?;
+    fs::set_permissions(file_path, metadata.permissions()).map_err(cannot_write_file)?;
+    if preserve_mtime {
+        let modified = metadata.modified().map_err(cannot_write_file)?;
+        fs::File::options()
+            .write(true)
+            .open(file_path)
+            .and_then(|file| file.set_modified(modified))
+            .map_err(cannot_write_file)?;
+    }
+    Ok(())
+}
+
+/// Formats the content of a file and writes it directly over the file, in place,
+/// the same way as `write_file`. Only the tail of the formatted content
+/// that can still change is kept in memory.
+fn write_file_streaming(
+    file_path: &Path,
+    input_data: &[u8],
+    options: &Options,
+    preserve_mtime: bool,
+) -> Result<(), Error> {
+    let cannot_write_file = |_| Error::CannotWriteFile(file_path.display().to_string());
+    let metadata = fs::metadata(file_path).map_err(cannot_write_file)?;
+    let file = fs::File::create(file_path).map_err(cannot_write_file)?;
+    write_formatted_content(file, &metadata, input_data, options, preserve_mtime)
+        .map_err(cannot_write_file)
+}
+
+/// Formats the content of a file and writes it to an open file, which gets
+/// the permissions and, optionally, the modification time of the original file.
+fn write_formatted_content(
+    file: fs::File,
+    metadata: &fs::Metadata,
+    input_data: &[u8],
+    options: &Options,
+    preserve_mtime: bool,
+) -> io::Result<()> {
+    let mut streaming_writer = StreamingWriter::new(io::BufWriter::new(file));
+    modify_content(input_data, options, &mut streaming_writer);
+    let file = streaming_writer
+        .finish()?
+        .into_inner()
+        .map_err(|error| error.into_error())?;
+    file.set_permissions(metadata.permissions())?;
+    if preserve_mtime {
+        file.set_modified(metadata.modified()?)?;
+    }
+    Ok(())
+}
+
+/// Path of the file inside the output directory, e.g. `out/src/file.txt` for `src/file.txt`.
+/// The path is mirrored lexically: the root of an absolute path is dropped, e.g.
+/// `/home/file.txt` becomes `out/home/file.txt`, `..` removes the preceding directory,
+/// and `..` that would leave the output directory is dropped.
+fn path_in_output_dir(file_path: &Path, output_dir: &Path) -> PathBuf {
+    let mut relative_path = PathBuf::new();
+    for component in file_path.components() {
+        match component {
+            Component::Normal(name) => relative_path.push(name),
+            Component::ParentDir => {
+                relative_path.pop();
+            }
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
+    }
+    output_dir.join(relative_path)
+}
+
+/// Writes the formatted content of a file to another path. An existing file is overwritten.
+/// The permissions of the original file are copied to the new file.
+fn write_file_copy(file_path: &Path, output_path: &Path, output_data: &[u8]) -> Result<(), Error> {
+    let cannot_write_file = |_| Error::CannotWriteFile(output_path.display().to_string());
+    fs::write(output_path, output_data).map_err(cannot_write_file)?;
+    let metadata = fs::metadata(file_path).map_err(cannot_write_file)?;
+    fs::set_permissions(output_path, metadata.permissions()).map_err(cannot_write_file)
+}
+
+/// Writes the content of a file to the output directory, creating the parent directories.
+/// Returns the path of the written file.
+fn write_file_to_output_dir(
+    file_path: &Path,
+    output_data: &[u8],
+    output_dir: &Path,
+) -> Result<PathBuf, Error> {
+    let output_path = path_in_output_dir(file_path, output_dir);
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|_| Error::CannotWriteFile(output_path.display().to_string()))?;
+    }
+    write_file_copy(file_path, &output_path, output_data)?;
+    Ok(output_path)
+}
+
+/// Writes the formatted content of a file to the output directory, to a file with
+/// the output suffix, or over the original file, whichever the settings say.
+/// Optionally, the original file is backed up first and the written file is verified.
+fn write_output(
+    file_path: &Path,
+    input_data: &[u8],
+    output_data: &[u8],
+    options: &Options,
+    settings: &RunSettings,
+) -> Result<(), Error> {
+    let output_path = if let Some(output_dir) = &settings.output_dir {
+        write_file_to_output_dir(file_path, output_data, output_dir)?
+    } else if let Some(suffix) = &settings.output_suffix {
+        let output_path = path_with_suffix(file_path, suffix);
+        write_file_copy(file_path, &output_path, output_data)?;
+        output_path
+    } else {
+        if let Some(suffix) = &settings.backup_suffix {
+            write_backup(file_path, input_data, suffix)?;
+        }
+        write_file(file_path, output_data, settings.preserve_mtime)?;
+        file_path.to_path_buf()
+    };
+    if settings.verify_writes {
+        verify_written_file(&output_path, options, settings)?;
+    }
+    Ok(())
+}
+
+/// Reads a written file back from the disk and checks that formatting it again
+/// would not change it, i.e., that the file on the disk is formatted.
+fn verify_written_file(
+    file_path: &Path,
+    options: &Options,
+    settings: &RunSettings,
+) -> Result<(), Error> {
+    let cannot_read_file = |_| Error::CannotReadFile(file_path.display().to_string());
+    let mut written_data = fs::read(file_path).map_err(cannot_read_file)?;
+    if settings.handle_gzip && is_gzip(&written_data) {
+        written_data = gzip_decompress(&written_data).map_err(cannot_read_file)?;
+    }
+    if modify_content(&written_data, options, &mut CountingWriter::new()).is_empty() {
+        Ok(())
+    } else {
+        Err(Error::WriteNotVerified(file_path.display().to_string()))
+    }
+}
+
+/// Content of a file, either read into memory or memory-mapped.
+enum FileData {
+    Read(Vec<u8>),
+    Mapped(memmap2::Mmap),
+}
+
+impl std::ops::Deref for FileData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileData::Read(data) => data,
+            FileData::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+/// Reads the content of a file. If `memory_map` is true, the file is memory-mapped
+/// instead. If the file cannot be memory-mapped, it is read into memory.
+fn read_file(file_path: &Path, memory_map: bool) -> io::Result<FileData> {
+    if memory_map {
+        let file = fs::File::open(file_path)?;
+        // SAFETY: The mapping is read-only and the file is only checked, never written
+        // while it is mapped. The file must not be modified by other programs meanwhile.
+        if let Ok(mmap) = unsafe { memmap2::Mmap::map(&file) } {
+            return Ok(FileData::Mapped(mmap));
+        }
+    }
+    fs::read(file_path).map(FileData::Read)
+}
+
+/// Counts the new line markers of each type in a file. The file is not formatted.
+pub fn count_file_new_line_markers(file_path: &Path) -> Result<NewLineMarkerCounts, Error> {
+    let input_data =
+        fs::read(file_path).map_err(|_| Error::CannotReadFile(file_path.display().to_string()))?;
+    Ok(count_new_line_markers(&input_data))
+}
+
+/// Formats or checks a single file and returns the list of changes tha have been
+/// made or would have been made. If check_only is set to true, the file is not modified.
 /// Otherwise, the file is overwritten in place.
-pub fn process_file(file_path: &PathBuf, options: &Options, check_only: bool) -> Vec<Change> {
-    match fs::read(file_path) {
-        Err(_) => {
-            die(Error::CannotReadFile(file_path.display().to_string()));
-        }
-        Ok(input_data) => {
-            let mut counting_writer = CountingWriter::new();
-            let changes: Vec<Change> = modify_content(&input_data, options, &mut counting_writer);
-            if !check_only && !changes.is_empty() {
-                let mut output_writer = Vec::with_capacity(counting_writer.maximum_position());
-                modify_content(&input_data, options, &mut output_writer);
-                if fs::write(file_path, output_writer).is_err() {
-                    die(Error::CannotWriteFile(file_path.display().to_string()));
-                };
+pub fn process_file(
+    file_path: &Path,
+    options: &Options,
+    settings: &RunSettings,
+    check_only: bool,
+) -> Result<FormatResult, Error> {
+    let start_time = settings.measure_processing_time.then(Instant::now);
+    // Memory-mapped files are never written.
+    let input_data = read_file(file_path, settings.memory_map && check_only)
+        .map_err(|_| Error::CannotReadFile(file_path.display().to_string()))?;
+    let mut result = process_file_content(file_path, &input_data, options, settings, check_only)?;
+    if let Some(start_time) = start_time {
+        result.processing_time = Some(start_time.elapsed());
+    }
+    Ok(result)
+}
+
+/// Same as `process_file` except that the content of the file has already been read.
+/// The processing time does not include reading the file.
+pub fn process_file_content(
+    file_path: &Path,
+    input_data: &[u8],
+    options: &Options,
+    settings: &RunSettings,
+    check_only: bool,
+) -> Result<FormatResult, Error> {
+    let start_time = settings.measure_processing_time.then(Instant::now);
+    let file_options: Options;
+    let options = if options.markdown_hard_breaks && !is_markdown_file(file_path) {
+        file_options = options.for_file(file_path);
+        &file_options
+    } else {
+        options
+    };
+    if settings.handle_gzip && is_gzip(input_data) {
+        let decompressed_data = gzip_decompress(input_data)
+            .map_err(|_| Error::CannotReadFile(file_path.display().to_string()))?;
+        let mut result = format_file_content(
+            file_path,
+            &decompressed_data,
+            Some(input_data),
+            options,
+            settings,
+            check_only,
+        )?;
+        result.processing_time = start_time.map(|start_time| start_time.elapsed());
+        return Ok(result);
+    }
+    format_file_content(file_path, input_data, None, options, settings, check_only)
+}
+
+/// Formats or checks the content of a file and writes the formatted content.
+///
+/// If the file is gzip-compressed, `input_data` is the decompressed content and
+/// `compressed_data` is the content of the file. The decompressed content is checked
+/// and formatted, and the formatted content is compressed again before it is written.
+/// The backup file and the copy in the output directory of an unchanged file are copies
+/// of the compressed file. The sizes in the result are the sizes of the decompressed content.
+fn format_file_content(
+    file_path: &Path,
+    input_data: &[u8],
+    compressed_data: Option<&[u8]>,
+    options: &Options,
+    settings: &RunSettings,
+    check_only: bool,
+) -> Result<FormatResult, Error> {
+    let start_time = settings.measure_processing_time.then(Instant::now);
+    // Content of the file as it is stored on the disk.
+    let file_data = compressed_data.unwrap_or(input_data);
+    if let Some(skip_reason) = find_skip_reason(input_data, settings) {
+        // The output directory mirrors all the files, including the skipped ones.
+        if let (false, Some(output_dir)) = (check_only, &settings.output_dir) {
+            write_file_to_output_dir(file_path, file_data, output_dir)?;
+        }
+        return Ok(FormatResult {
+            changes: Vec::new(),
+            skipped: Some(skip_reason),
+            diff: None,
+            number_of_lines: 0,
+            hash: None,
+            diff_stat: None,
+            line_endings_explanation: None,
+            input_size: input_data.len(),
+            output_size: input_data.len(),
+            processing_time: start_time.map(|start_time| start_time.elapsed()),
+        });
+    }
+
+    if options.warn_exotic_trailing_whitespace {
+        for line_number in find_lines_with_exotic_trailing_whitespace(input_data) {
+            print_warning(&format!(
+                "{}: line {}: Line ends with non-ASCII whitespace, which is not removed by --remove-trailing-whitespace.",
+                file_path.display(),
+                line_number
+            ));
+        }
+    }
+
+    if options.warn_if_minified {
+        if let Some(average_line_length) = looks_minified(input_data) {
+            print_warning(&format!(
+                "{}: File looks minified (average line length is {} bytes). Changes at the end of the file may be unwanted.",
+                file_path.display(),
+                average_line_length
+            ));
+        }
+    }
+
+    if options.warn_double_encoded_bom && has_double_encoded_byte_order_mark(input_data) {
+        print_warning(&format!(
+            "{}: File starts with a double-encoded byte order mark (\"\u{ef}\u{bb}\u{bf}\"). \
+             Remove these characters, or replace them with a byte order mark if one is intended.",
+            file_path.display()
+        ));
+    }
+
+    if let Some(tab_width) = options.retab_tabs_to_spaces {
+        for line_number in find_lines_with_ambiguous_indentation(input_data, tab_width) {
+            print_warning(&format!(
+                "{}: line {}: Indentation mixes tabs and spaces, so it is not converted by --retab.",
+                file_path.display(),
+                line_number
+            ));
+        }
+    }
+
+    // Large files are written in place while they are formatted, unless the formatted
+    // content is needed for something else, e.g. for compressing it. The changes are found
+    // first, so that the files that do not need to change are not written.
+    let streams_output = !check_only
+        && compressed_data.is_none()
+        && input_data.len() >= settings.streaming_write_threshold
+        && !settings.show_diff
+        && !settings.compute_hash
+        && !settings.compute_diff_stat
+        && !settings.assert_idempotent
+        && settings.pipe_through.is_none()
+        && settings.output_suffix.is_none()
+        && settings.output_dir.is_none();
+    let needs_output = !streams_output
+        && (!check_only
+            || settings.show_diff
+            || settings.compute_hash
+            || settings.compute_diff_stat
+            || settings.assert_idempotent);
+    // The formatted content is usually about as long as the original content.
+    let mut output_writer: Vec<u8> = if needs_output {
+        Vec::with_capacity(input_data.len())
+    } else {
+        Vec::new()
+    };
+    let (changes, output_size) =
+        format_content(input_data, options, needs_output, &mut output_writer);
+    let mut diff: Option<String> = None;
+    let mut hash: Option<String> = None;
+    let mut stat: Option<DiffStat> = None;
+    if changes.is_empty() {
+        // The formatted content is the same as the original content.
+        if settings.compute_hash {
+            hash = Some(sha256(input_data));
+        }
+        if let (false, Some(output_dir)) = (check_only, &settings.output_dir) {
+            write_file_to_output_dir(file_path, file_data, output_dir)?;
+        }
+    } else if streams_output {
+        if let Some(suffix) = &settings.backup_suffix {
+            write_backup(file_path, input_data, suffix)?;
+        }
+        write_file_streaming(file_path, input_data, options, settings.preserve_mtime)?;
+        if settings.verify_writes {
+            verify_written_file(file_path, options, settings)?;
+        }
+    } else if needs_output {
+        // Formatting the formatted content must not change it.
+        if settings.assert_idempotent
+            && !modify_content(&output_writer, options, &mut CountingWriter::new()).is_empty()
+        {
+            return Err(Error::NotIdempotent(file_path.display().to_string()));
+        }
+        if settings.show_diff {
+            diff = Some(unified_diff(file_path, input_data, &output_writer));
+        }
+        if settings.compute_hash {
+            hash = Some(sha256(&output_writer));
+        }
+        if settings.compute_diff_stat {
+            stat = Some(diff_stat(input_data, &output_writer));
+        }
+        // Optionally, the formatted content is piped through an external command.
+        // If the command fails, the file is not written.
+        let output_data: Option<Vec<u8>> = match &settings.pipe_through {
+            Some(command) if !check_only => match pipe_through(command, &output_writer) {
+                Ok(piped_output) => Some(piped_output),
+                Err(error) => {
+                    print_warning(&format!(
+                        "{}: Command `{}` failed ({}), so the file is not written.",
+                        file_path.display(),
+                        command,
+                        error
+                    ));
+                    None
+                }
+            },
+            _ => Some(output_writer),
+        };
+        if let (false, Some(output_data)) = (check_only, output_data) {
+            let output_data = match compressed_data {
+                Some(_) => gzip_compress(&output_data)
+                    .map_err(|_| Error::CannotWriteFile(file_path.display().to_string()))?,
+                None => output_data,
+            };
+            write_output(file_path, file_data, &output_data, options, settings)?;
+        }
+    }
+    let line_endings_explanation = settings
+        .explain_line_endings
+        .then(|| explain_line_endings(input_data, &changes, check_only, cfg!(windows)));
+    Ok(FormatResult {
+        changes,
+        skipped: None,
+        diff,
+        number_of_lines: if settings.count_lines {
+            count_lines(input_data)
+        } else {
+            0
+        },
+        hash,
+        diff_stat: stat,
+        line_endings_explanation,
+        input_size: input_data.len(),
+        output_size,
+        processing_time: start_time.map(|start_time| start_time.elapsed()),
+    })
+}
+
+/// Formats the content in a single pass and returns the list of changes
+/// and the size of the formatted content.
+///
+/// If `needs_output` is true, the formatted content is written to `output_writer`.
+/// Otherwise, the formatted content is only counted and `output_writer` is not touched,
+/// which is enough to find the changes in check-only mode.
+fn format_content<W: Writer>(
+    input_data: &[u8],
+    options: &Options,
+    needs_output: bool,
+    output_writer: &mut W,
+) -> (Vec<Change>, usize) {
+    if needs_output {
+        let changes = modify_content(input_data, options, output_writer);
+        (changes, output_writer.position())
+    } else {
+        let mut counting_writer = CountingWriter::new();
+        let changes = modify_content(input_data, options, &mut counting_writer);
+        (changes, counting_writer.position())
+    }
+}
+
+/// Formats data read from a reader and writes the formatted data to a writer.
+/// Returns the list of changes that have been made.
+///
+/// With the default options, the whole input is read into memory before it is formatted,
+/// because the new line marker is detected from the content. Set a fixed new line marker,
+/// e.g. `OutputNewLineMarkerMode::Linux`, to stream the input.
+///
+/// The output is streamed. Only its tail that can still change is kept in memory,
+/// i.e., the new line marker of the last non-empty line and the empty lines after it.
+/// The tail is needed by `remove_trailing_empty_lines`, `remove_new_line_marker_from_end_of_file`,
+/// `add_new_line_marker_at_end_of_file` and `ensure_trailing_newlines`; the other options
+/// change each line independently.
+///
+/// The input is streamed too. It is read in blocks of `READ_BLOCK_SIZE` bytes, and the lines
+/// read so far are formatted as soon as a later line with visible content has been read.
+/// Only the lines after the last line with visible content are kept in memory,
+/// so a file consisting of whitespace only is detected at the end of the input.
+/// The input is read completely before formatting starts if the new line marker is detected
+/// automatically, i.e., with `--new-line-marker=auto` or `--new-line-marker=keep-per-line`,
+/// or if only regions or line ranges are formatted, because these need to see all of it.
+pub fn format_reader<R: io::Read, W: io::Write>(
+    reader: R,
+    writer: W,
+    options: &Options,
+) -> io::Result<Vec<Change>> {
+    format_reader_in_blocks(reader, writer, options, READ_BLOCK_SIZE)
+}
+
+/// Number of bytes `format_reader` reads at a time.
+const READ_BLOCK_SIZE: usize = 64 * 1024;
+
+/// Implementation of `format_reader` that reads blocks of the given size.
+fn format_reader_in_blocks<R: io::Read, W: io::Write>(
+    mut reader: R,
+    writer: W,
+    options: &Options,
+    block_size: usize,
+) -> io::Result<Vec<Change>> {
+    let mut input_data: Vec<u8> = Vec::new();
+    let mut streaming_writer = StreamingWriter::new(writer);
+
+    let is_streamed = !matches!(
+        options.new_line_marker,
+        OutputNewLineMarkerMode::Auto | OutputNewLineMarkerMode::KeepPerLine
+    ) && !options.formats_only_some_lines();
+    if !is_streamed {
+        reader.read_to_end(&mut input_data)?;
+        let changes = modify_content(&input_data, options, &mut streaming_writer);
+        streaming_writer.finish()?;
+        return Ok(changes);
+    }
+
+    // The byte order mark is handled with the first part of the input,
+    // and the end of the file with the last part.
+    let first_part_options = Options {
+        strip_bom: options.strip_bom,
+        add_bom: options.add_bom,
+        ..options.without_whole_file_operations()
+    };
+    let middle_part_options = options.without_whole_file_operations();
+    let last_part_options = Options {
+        strip_bom: false,
+        add_bom: false,
+        ..options.clone()
+    };
+
+    let mut changes: Vec<Change> = Vec::new();
+    let mut number_of_lines: usize = 0;
+    let mut is_first_part = true;
+    loop {
+        let length = input_data.len();
+        input_data.resize(length + block_size, 0);
+        let number_of_bytes_read = match reader.read(&mut input_data[length..]) {
+            Ok(number_of_bytes_read) => number_of_bytes_read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {
+                input_data.truncate(length);
+                continue;
             }
-            changes
+            Err(error) => return Err(error),
+        };
+        input_data.truncate(length + number_of_bytes_read);
+        if number_of_bytes_read == 0 {
+            break;
+        }
+
+        let Some(end_of_part) = find_end_of_formattable_part(&input_data) else {
+            continue;
+        };
+        let part = &input_data[..end_of_part];
+        let part_options = if is_first_part {
+            &first_part_options
+        } else {
+            &middle_part_options
+        };
+        changes.extend(
+            modify_content(part, part_options, &mut streaming_writer)
+                .into_iter()
+                .map(|change| change.with_line_offset(number_of_lines)),
+        );
+        number_of_lines += count_lines(part);
+        if options.normalize_unicode_line_separators {
+            number_of_lines += part
+                .windows(LINE_SEPARATOR.len())
+                .filter(|window| *window == LINE_SEPARATOR || *window == PARAGRAPH_SEPARATOR)
+                .count();
         }
+        let position = streaming_writer.position();
+        streaming_writer.commit(position);
+        input_data.drain(..end_of_part);
+        is_first_part = false;
+    }
+
+    let last_part_options = if is_first_part {
+        options
+    } else {
+        &last_part_options
+    };
+    changes.extend(
+        modify_content(&input_data, last_part_options, &mut streaming_writer)
+            .into_iter()
+            .map(|change| change.with_line_offset(number_of_lines)),
+    );
+    streaming_writer.finish()?;
+    Ok(changes)
+}
+
+/// Finds where the input read so far can be split, so that both parts can be formatted
+/// independently of each other. Returns the length of the first part, if there is such a split.
+///
+/// The input is split at the start of the last line with visible content (see
+/// `is_visible_char_at`). Both parts contain visible content, so neither of them is empty
+/// or consists of whitespace only, and the empty lines at the end of the file are all
+/// in the second part. The second part must not start with a byte order mark, which is
+/// handled differently at the start of a file.
+fn find_end_of_formattable_part(input_data: &[u8]) -> Option<usize> {
+    let is_visible = |i: usize| is_visible_char_at(input_data, i);
+    let last_visible = (0..input_data.len()).rposition(is_visible)?;
+    let mut end_of_part = input_data[..last_visible]
+        .iter()
+        .rposition(|char| *char == CARRIAGE_RETURN || *char == LINE_FEED)?
+        + 1;
+    if input_data[end_of_part - 1] == CARRIAGE_RETURN && input_data[end_of_part] == LINE_FEED {
+        end_of_part += 1;
     }
+    if !(0..end_of_part).any(is_visible) || input_data[end_of_part..].starts_with(BYTE_ORDER_MARK) {
+        return None;
+    }
+    Some(end_of_part)
+}
+
+/// Determines if the character whose encoding starts at position `i` is visible, i.e.,
+/// an ASCII graphic character, or a non-ASCII character that is neither whitespace
+/// nor one of `UNICODE_WHITESPACE`, e.g. a Cyrillic letter. The bytes of a multi-byte
+/// UTF-8 sequence are never new line markers, so the whole sequence counts as visible.
+/// A continuation byte, an incomplete sequence and invalid UTF-8 are not visible.
+fn is_visible_char_at(input_data: &[u8], i: usize) -> bool {
+    let length = match input_data[i] {
+        char if char.is_ascii() => return char.is_ascii_graphic(),
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return false,
+    };
+    input_data
+        .get(i..i + length)
+        .and_then(|bytes| std::str::from_utf8(bytes).ok())
+        .and_then(|string| string.chars().next())
+        .is_some_and(|char| !char.is_whitespace() && !UNICODE_WHITESPACE.contains(&char))
+}
+
+/// Determines if two buffers differ only in whitespace that formatting with the given
+/// options normalizes, i.e., if formatting both of them gives the same output.
+/// Useful for comparisons that ignore such whitespace differences.
+pub fn is_whitespace_only_difference(a: &[u8], b: &[u8], options: &Options) -> bool {
+    let format = |input_data: &[u8]| {
+        let mut output: Vec<u8> = Vec::with_capacity(input_data.len());
+        modify_content(input_data, options, &mut output);
+        output
+    };
+    a == b || format(a) == format(b)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::discover::discover_files;
+    use crate::writer::HashingWriter;
+    use clap::Parser;
+
+    impl RunSettings {
+        fn new() -> Self {
+            Self {
+                backup_suffix: None,
+                output_suffix: None,
+                output_dir: None,
+                pipe_through: None,
+                preserve_mtime: false,
+                show_diff: false,
+                explain_line_endings: false,
+                compute_diff_stat: false,
+                measure_processing_time: false,
+                count_lines: false,
+                compute_hash: false,
+                streaming_write_threshold: STREAMING_WRITE_THRESHOLD,
+                memory_map: false,
+                assert_idempotent: false,
+                verify_writes: false,
+                handle_gzip: false,
+                honor_ignore_file_directive: false,
+                content_matches: None,
+                process_binary: false,
+            }
+        }
+
+        fn backup_suffix(mut self, suffix: &str) -> Self {
+            self.backup_suffix = Some(suffix.to_string());
+            self
+        }
+
+        fn pipe_through(mut self, command: &str) -> Self {
+            self.pipe_through = Some(command.to_string());
+            self
+        }
+
+        fn output_suffix(mut self, suffix: &str) -> Self {
+            self.output_suffix = Some(suffix.to_string());
+            self
+        }
+
+        fn output_dir(mut self, output_dir: &Path) -> Self {
+            self.output_dir = Some(output_dir.to_path_buf());
+            self
+        }
+
+        fn preserve_mtime(mut self) -> Self {
+            self.preserve_mtime = true;
+            self
+        }
+
+        fn show_diff(mut self) -> Self {
+            self.show_diff = true;
+            self
+        }
+
+        fn explain_line_endings(mut self) -> Self {
+            self.explain_line_endings = true;
+            self
+        }
+
+        fn compute_diff_stat(mut self) -> Self {
+            self.compute_diff_stat = true;
+            self
+        }
+
+        fn compute_hash(mut self) -> Self {
+            self.compute_hash = true;
+            self
+        }
+
+        fn count_lines(mut self) -> Self {
+            self.count_lines = true;
+            self
+        }
+
+        fn assert_idempotent(mut self) -> Self {
+            self.assert_idempotent = true;
+            self
+        }
+
+        fn verify_writes(mut self) -> Self {
+            self.verify_writes = true;
+            self
+        }
+
+        fn handle_gzip(mut self) -> Self {
+            self.handle_gzip = true;
+            self
+        }
+
+        fn streaming_write_threshold(mut self, threshold: usize) -> Self {
+            self.streaming_write_threshold = threshold;
+            self
+        }
+
+        fn content_matches(mut self, pattern: &str) -> Self {
+            self.content_matches = Some(pattern.parse().unwrap());
+            self
+        }
+
+        fn process_binary(mut self) -> Self {
+            self.process_binary = true;
+            self
+        }
+
+        fn honor_ignore_file_directive(mut self) -> Self {
+            self.honor_ignore_file_directive = true;
+            self
+        }
+    }
 
     impl Options {
         fn new() -> Self {
@@ -426,60 +2388,121 @@ mod tests {
                 remove_new_line_marker_from_end_of_file: false,
                 normalize_new_line_markers: false,
                 remove_trailing_whitespace: false,
+                trailing_whitespace_lines: TrailingWhitespaceLines::All,
+                markdown_hard_breaks: false,
+                trailing_whitespace_chars: vec![
+                    TrailingWhitespaceChar::Space,
+                    TrailingWhitespaceChar::Tab,
+                    TrailingWhitespaceChar::VerticalTab,
+                    TrailingWhitespaceChar::FormFeed,
+                ],
                 remove_trailing_empty_lines: false,
+                max_trailing_blank_lines: None,
                 new_line_marker: OutputNewLineMarkerMode::Auto,
                 normalize_empty_files: TrivialFileReplacementMode::Ignore,
                 normalize_whitespace_only_files: TrivialFileReplacementMode::Ignore,
                 replace_tabs_with_spaces: -1,
+                retab_tabs_to_spaces: None,
+                expand_tabs_to_stops: None,
+                tab_stop_origin: 0,
                 normalize_non_standard_whitespace: NonStandardWhitespaceReplacementMode::Ignore,
+                normalize_unicode_whitespace: UnicodeWhitespaceReplacementMode::Ignore,
+                warn_exotic_trailing_whitespace: false,
+                warn_if_minified: false,
+                warn_double_encoded_bom: false,
+                strip_bom: false,
+                add_bom: false,
+                max_whitespace_only_size: None,
+                apply_line_rules_to_whitespace_only_files: false,
+                normalize_unicode_line_separators: false,
+                ensure_trailing_newlines: None,
+                blank_lines_include_whitespace: false,
+                region_begin: None,
+                region_end: None,
+                line_ranges: Vec::new(),
             }
         }
 
         fn add_new_line_marker_at_end_of_file(mut self) -> Self {
             self.add_new_line_marker_at_end_of_file = true;
             self.remove_new_line_marker_from_end_of_file = false;
-            return self;
+            self
         }
 
         fn remove_new_line_marker_from_end_of_file(mut self) -> Self {
             self.remove_new_line_marker_from_end_of_file = true;
             self.add_new_line_marker_at_end_of_file = false;
-            return self;
+            self
         }
 
         fn normalize_new_line_markers(mut self) -> Self {
             self.normalize_new_line_markers = true;
-            return self;
+            self
+        }
+
+        fn trailing_whitespace_chars(mut self, chars: &[TrailingWhitespaceChar]) -> Self {
+            self.trailing_whitespace_chars = chars.to_vec();
+            self
         }
 
         fn remove_trailing_whitespace(mut self) -> Self {
             self.remove_trailing_whitespace = true;
-            return self;
+            self
+        }
+
+        fn trailing_whitespace_lines(mut self, lines: TrailingWhitespaceLines) -> Self {
+            self.trailing_whitespace_lines = lines;
+            self
+        }
+
+        fn markdown_hard_breaks(mut self) -> Self {
+            self.markdown_hard_breaks = true;
+            self
         }
 
         fn remove_trailing_empty_lines(mut self) -> Self {
             self.remove_trailing_empty_lines = true;
-            return self;
+            self
+        }
+
+        fn max_trailing_blank_lines(mut self, max_trailing_blank_lines: usize) -> Self {
+            self.max_trailing_blank_lines = Some(max_trailing_blank_lines);
+            self
         }
 
         fn new_line_marker(mut self, output_new_line_marker_mode: OutputNewLineMarkerMode) -> Self {
             self.new_line_marker = output_new_line_marker_mode;
-            return self;
+            self
         }
 
         fn normalize_empty_files(mut self, mode: TrivialFileReplacementMode) -> Self {
             self.normalize_empty_files = mode;
-            return self;
+            self
         }
 
         fn normalize_whitespace_only_files(mut self, mode: TrivialFileReplacementMode) -> Self {
             self.normalize_whitespace_only_files = mode;
-            return self;
+            self
         }
 
         fn replace_tabs_with_spaces(mut self, num_spaces: isize) -> Self {
             self.replace_tabs_with_spaces = num_spaces;
-            return self;
+            self
+        }
+
+        fn expand_tabs_to_stops(mut self, tab_width: usize) -> Self {
+            self.expand_tabs_to_stops = Some(tab_width);
+            self
+        }
+
+        fn tab_stop_origin(mut self, tab_stop_origin: usize) -> Self {
+            self.tab_stop_origin = tab_stop_origin;
+            self
+        }
+
+        fn retab_tabs_to_spaces(mut self, tab_width: usize) -> Self {
+            self.retab_tabs_to_spaces = Some(tab_width);
+            self
         }
 
         fn normalize_non_standard_whitespace(
@@ -487,28 +2510,194 @@ mod tests {
             mode: NonStandardWhitespaceReplacementMode,
         ) -> Self {
             self.normalize_non_standard_whitespace = mode;
-            return self;
+            self
+        }
+
+        fn normalize_unicode_whitespace(mut self, mode: UnicodeWhitespaceReplacementMode) -> Self {
+            self.normalize_unicode_whitespace = mode;
+            self
+        }
+
+        fn strip_bom(mut self) -> Self {
+            self.strip_bom = true;
+            self.add_bom = false;
+            self
+        }
+
+        fn add_bom(mut self) -> Self {
+            self.add_bom = true;
+            self.strip_bom = false;
+            self
+        }
+
+        fn max_whitespace_only_size(mut self, limit: usize) -> Self {
+            self.max_whitespace_only_size = Some(limit);
+            self
+        }
+
+        fn apply_line_rules_to_whitespace_only_files(mut self) -> Self {
+            self.apply_line_rules_to_whitespace_only_files = true;
+            self
+        }
+
+        fn normalize_unicode_line_separators(mut self) -> Self {
+            self.normalize_unicode_line_separators = true;
+            self
+        }
+
+        fn line_range(mut self, start: usize, end: usize) -> Self {
+            self.line_ranges.push(LineRange { start, end });
+            self
+        }
+
+        fn region(mut self, begin: &str, end: &str) -> Self {
+            self.region_begin = Some(begin.to_string());
+            self.region_end = Some(end.to_string());
+            self
+        }
+
+        fn blank_lines_include_whitespace(mut self) -> Self {
+            self.blank_lines_include_whitespace = true;
+            self
+        }
+
+        fn ensure_trailing_newlines(mut self, number_of_new_line_markers: usize) -> Self {
+            self.ensure_trailing_newlines = Some(number_of_new_line_markers);
+            self
         }
     }
 
     #[test]
     fn test_is_whitespace_only() {
-        assert_eq!(is_whitespace_only(&[]), true);
-        assert_eq!(is_whitespace_only(b"    "), true);
-        assert_eq!(is_whitespace_only(b"\n\n\n"), true);
-        assert_eq!(is_whitespace_only(b"\r\r\r"), true);
-        assert_eq!(is_whitespace_only(b" \t\n\r"), true);
-        assert_eq!(is_whitespace_only(b"hello"), false);
-        assert_eq!(is_whitespace_only(b"hello world\n"), false);
-        assert_eq!(is_whitespace_only(b"\n\t \x0B \x0C \n  "), true);
+        assert!(is_whitespace_only(&[]));
+        assert!(is_whitespace_only(b"    "));
+        assert!(is_whitespace_only(b"\n\n\n"));
+        assert!(is_whitespace_only(b"\r\r\r"));
+        assert!(is_whitespace_only(b" \t\n\r"));
+        assert!(!is_whitespace_only(b"hello"));
+        assert!(!is_whitespace_only(b"hello world\n"));
+        assert!(is_whitespace_only(b"\n\t \x0B \x0C \n  "));
     }
 
     #[test]
-    fn test_find_most_common_new_line_marker() {
-        assert_eq!(find_most_common_new_line_marker(&[]), NewLineMarker::Linux);
+    fn test_ends_with_exotic_whitespace() {
+        assert!(ends_with_exotic_whitespace("hello\u{A0}".as_bytes()));
+        assert!(ends_with_exotic_whitespace("hello\u{3000}".as_bytes()));
+        assert!(ends_with_exotic_whitespace("hello\u{202F} \t".as_bytes()));
+        assert!(ends_with_exotic_whitespace("\u{2007}".as_bytes()));
+        assert!(!ends_with_exotic_whitespace(b""));
+        assert!(!ends_with_exotic_whitespace(b"hello "));
+        assert!(!ends_with_exotic_whitespace(b"hello\t"));
+        assert!(!ends_with_exotic_whitespace("hello\u{A0}world".as_bytes()));
+        assert!(!ends_with_exotic_whitespace("ahoj svet čšť".as_bytes()));
+        assert!(!ends_with_exotic_whitespace(b"invalid \xA0"));
+    }
+
+    #[test]
+    fn test_find_lines_with_exotic_trailing_whitespace() {
         assert_eq!(
-            find_most_common_new_line_marker(b"\n"),
-            NewLineMarker::Linux
+            find_lines_with_exotic_trailing_whitespace("a\u{A0}\r\nb \nc\u{3000}".as_bytes()),
+            vec![1, 3]
+        );
+        assert_eq!(
+            find_lines_with_exotic_trailing_whitespace(b"a \nb\t\r\rc"),
+            Vec::<usize>::new()
+        );
+        assert_eq!(
+            find_lines_with_exotic_trailing_whitespace("\n\r\u{A0}\n".as_bytes()),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn test_has_double_encoded_byte_order_mark() {
+        assert!(has_double_encoded_byte_order_mark(
+            b"\xC3\xAF\xC2\xBB\xC2\xBFhello\n"
+        ));
+        assert!(has_double_encoded_byte_order_mark(
+            "\u{ef}\u{bb}\u{bf}hello\n".as_bytes()
+        ));
+        assert!(has_double_encoded_byte_order_mark(
+            b"\xEF\xBB\xBF\xC3\xAF\xC2\xBB\xC2\xBF"
+        ));
+        assert!(!has_double_encoded_byte_order_mark(b"\xEF\xBB\xBFhello\n"));
+        assert!(!has_double_encoded_byte_order_mark(
+            b"hello \xC3\xAF\xC2\xBB\xC2\xBF\n"
+        ));
+        assert!(!has_double_encoded_byte_order_mark(b"\xC3\xAF\xC2\xBB"));
+        assert!(!has_double_encoded_byte_order_mark(b""));
+    }
+
+    #[test]
+    fn test_looks_minified() {
+        let minified = format!("var a=1;{}", "b=2;".repeat(1000));
+        assert_eq!(looks_minified(minified.as_bytes()), Some(4008));
+        assert_eq!(
+            looks_minified(format!("{}\n", minified).as_bytes()),
+            Some(4009)
+        );
+        assert_eq!(
+            looks_minified(format!("{}\n{}", minified, minified).as_bytes()),
+            Some(4008)
+        );
+
+        let normal = "fn main() {\n    println!(\"hello\");\n}\n".repeat(100);
+        assert_eq!(looks_minified(normal.as_bytes()), None);
+        assert_eq!(looks_minified(b"short\n"), None);
+        assert_eq!(looks_minified(b""), None);
+
+        // Many long lines do not look minified.
+        let long_lines = format!("{}\n", "x".repeat(1000)).repeat(20);
+        assert_eq!(looks_minified(long_lines.as_bytes()), None);
+    }
+
+    #[test]
+    fn test_most_common_new_line_marker_ties() {
+        let counts = |linux: usize, mac: usize, windows: usize| NewLineMarkerCounts {
+            linux,
+            mac,
+            windows,
+        };
+
+        // No new line markers.
+        assert_eq!(counts(0, 0, 0).most_common(), NewLineMarker::Linux);
+
+        // All three are equally common.
+        assert_eq!(counts(2, 2, 2).most_common(), NewLineMarker::Linux);
+
+        // Two are equally common and more common than the third one.
+        assert_eq!(counts(2, 1, 2).most_common(), NewLineMarker::Linux);
+        assert_eq!(counts(2, 2, 1).most_common(), NewLineMarker::Linux);
+        assert_eq!(counts(1, 2, 2).most_common(), NewLineMarker::Windows);
+        assert_eq!(counts(0, 2, 2).most_common(), NewLineMarker::Windows);
+
+        // Two are equally common, but less common than the third one.
+        assert_eq!(counts(1, 1, 2).most_common(), NewLineMarker::Windows);
+        assert_eq!(counts(1, 2, 1).most_common(), NewLineMarker::Mac);
+        assert_eq!(counts(2, 1, 1).most_common(), NewLineMarker::Linux);
+
+        // No ties.
+        assert_eq!(counts(3, 2, 1).most_common(), NewLineMarker::Linux);
+        assert_eq!(counts(1, 3, 2).most_common(), NewLineMarker::Mac);
+        assert_eq!(counts(1, 2, 3).most_common(), NewLineMarker::Windows);
+
+        // The same order applies to the detection in a string.
+        assert_eq!(
+            find_most_common_new_line_marker(b"\n\r\r\n"),
+            NewLineMarker::Linux
+        );
+        assert_eq!(
+            find_most_common_new_line_marker(b"\r\r\n"),
+            NewLineMarker::Windows
+        );
+    }
+
+    #[test]
+    fn test_find_most_common_new_line_marker() {
+        assert_eq!(find_most_common_new_line_marker(&[]), NewLineMarker::Linux);
+        assert_eq!(
+            find_most_common_new_line_marker(b"\n"),
+            NewLineMarker::Linux
         );
         assert_eq!(find_most_common_new_line_marker(b"\r"), NewLineMarker::Mac);
         assert_eq!(
@@ -674,6 +2863,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_modify_content_remove_new_line_marker_from_end_of_file_single_line() {
+        let options: Options = Options::new().remove_new_line_marker_from_end_of_file();
+        for input_data in [b"hello\n".as_slice(), b"hello\r\n", b"hello\r"] {
+            let mut output = Vec::new();
+            let changes = modify_content(input_data, &options, &mut output);
+            assert_eq!(output, b"hello");
+            assert_eq!(
+                changes,
+                vec![Change::new(
+                    1,
+                    ChangeType::NewLineMarkerRemovedFromEndOfFile
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn test_modify_content_remove_new_line_marker_from_end_of_file_empty_lines_only() {
+        // Whitespace-only files are processed line by line if they exceed the size limit.
+        // There is no non-empty line, so the change is reported on the first line.
+        let options: Options = Options::new()
+            .remove_new_line_marker_from_end_of_file()
+            .max_whitespace_only_size(0);
+        for input_data in [b"\n".as_slice(), b"\r\n\r\n", b"\r"] {
+            let mut output = Vec::new();
+            let changes = modify_content(input_data, &options, &mut output);
+            assert_eq!(output, b"");
+            assert_eq!(
+                changes,
+                vec![Change::new(
+                    1,
+                    ChangeType::NewLineMarkerRemovedFromEndOfFile
+                )]
+            );
+        }
+    }
+
     #[test]
     fn test_modify_content_normalize_new_line_markers_auto() {
         let options: Options = Options::new().normalize_new_line_markers();
@@ -689,6 +2916,74 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_modify_content_normalize_new_line_markers_adjacent_mixed() {
+        use NewLineMarker::{Linux, Mac, Windows};
+
+        // Lines: "a\r", "\r\n", "\n", "\r", "b".
+        let input_data: &[u8] = b"a\r\r\n\n\rb";
+        for (output_new_line_marker_mode, expected_output, expected_changes) in [
+            (
+                OutputNewLineMarkerMode::Linux,
+                b"a\n\n\n\nb".as_slice(),
+                vec![(1, Mac, Linux), (2, Windows, Linux), (4, Mac, Linux)],
+            ),
+            (
+                OutputNewLineMarkerMode::Mac,
+                b"a\r\r\r\rb".as_slice(),
+                vec![(2, Windows, Mac), (3, Linux, Mac)],
+            ),
+            (
+                OutputNewLineMarkerMode::Windows,
+                b"a\r\n\r\n\r\n\r\nb".as_slice(),
+                vec![(1, Mac, Windows), (3, Linux, Windows), (4, Mac, Windows)],
+            ),
+        ] {
+            let options: Options = Options::new()
+                .normalize_new_line_markers()
+                .new_line_marker(output_new_line_marker_mode.clone());
+            let mut output = Vec::new();
+            let changes = modify_content(input_data, &options, &mut output);
+            assert_eq!(output, expected_output, "{:?}", output_new_line_marker_mode);
+            let expected_changes: Vec<Change> = expected_changes
+                .into_iter()
+                .map(|(line_number, old, new)| {
+                    Change::new(line_number, ChangeType::ReplacedNewLineMarker(old, new))
+                })
+                .collect();
+            assert_eq!(
+                changes, expected_changes,
+                "{:?}",
+                output_new_line_marker_mode
+            );
+        }
+
+        // A lone '\r' at the end of a file with mostly Windows new line markers.
+        let options: Options = Options::new().normalize_new_line_markers();
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\r\n\r\n\r\nb\r", &options, &mut output);
+        assert_eq!(output, b"a\r\n\r\n\r\nb\r\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(
+                4,
+                ChangeType::ReplacedNewLineMarker(Mac, Windows)
+            )]
+        );
+
+        // A lone '\r' right before a Windows new line marker.
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\r\r\nb\r\nc\r\n", &options, &mut output);
+        assert_eq!(output, b"a\r\n\r\nb\r\nc\r\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(
+                1,
+                ChangeType::ReplacedNewLineMarker(Mac, Windows)
+            )]
+        );
+    }
+
     #[test]
     fn test_modify_content_normalize_new_line_markers_linux() {
         let options: Options = Options::new()
@@ -757,209 +3052,326 @@ mod tests {
     }
 
     #[test]
-    fn test_modify_content_remove_trailing_empty_lines() {
-        let options: Options = Options::new().remove_trailing_empty_lines();
+    fn test_modify_content_normalize_new_line_markers_custom() {
+        let options: Options = Options::new()
+            .normalize_new_line_markers()
+            .new_line_marker(OutputNewLineMarkerMode::Custom(vec![0x1E]));
         let mut output = Vec::new();
-        let changes = modify_content(b"hello\r\n\rworld\r\n\n\n\n\n\n", &options, &mut output);
-        assert_eq!(output, b"hello\r\n\rworld\r\n");
-        assert_eq!(changes, vec![Change::new(4, ChangeType::RemovedEmptyLines)]);
+        let changes = modify_content(b"a\nb\n", &options, &mut output);
+        assert_eq!(output, b"a\x1Eb\x1E");
+        let record_separator = NewLineMarker::Custom(vec![0x1E]);
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(
+                    1,
+                    ChangeType::ReplacedNewLineMarker(
+                        NewLineMarker::Linux,
+                        record_separator.clone()
+                    )
+                ),
+                Change::new(
+                    2,
+                    ChangeType::ReplacedNewLineMarker(NewLineMarker::Linux, record_separator)
+                ),
+            ]
+        );
+        assert_eq!(
+            changes[0].to_string(false),
+            "line 1: New line marker '\\n' replaced by '\\x1e'."
+        );
     }
 
     #[test]
-    fn test_modify_content_remove_trailing_whitespace_1() {
-        let options: Options = Options::new().remove_trailing_whitespace();
+    fn test_modify_content_normalize_new_line_markers_final_lone_carriage_return() {
+        let options: Options = Options::new()
+            .normalize_new_line_markers()
+            .new_line_marker(OutputNewLineMarkerMode::Linux);
         let mut output = Vec::new();
-        let changes = modify_content(b"hello world   ", &options, &mut output);
-        assert_eq!(output, b"hello world");
+        let changes = modify_content(b"a\r", &options, &mut output);
+        assert_eq!(output, b"a\n");
         assert_eq!(
             changes,
-            vec![Change::new(1, ChangeType::RemovedTrailingWhitespace)]
+            vec![Change::new(
+                1,
+                ChangeType::ReplacedNewLineMarker(NewLineMarker::Mac, NewLineMarker::Linux)
+            )]
+        );
+        assert_eq!(
+            count_new_line_markers(b"a\r"),
+            NewLineMarkerCounts {
+                linux: 0,
+                mac: 1,
+                windows: 0
+            }
         );
     }
 
     #[test]
-    fn test_modify_content_remove_trailing_whitespace_2() {
-        let options: Options = Options::new().remove_trailing_whitespace();
+    fn test_modify_content_normalize_new_line_markers_lone_carriage_return_only() {
+        // A file consisting of a single '\r' is a whitespace-only file.
+        // By default, whitespace-only files are left as they are.
+        let options: Options = Options::new()
+            .normalize_new_line_markers()
+            .new_line_marker(OutputNewLineMarkerMode::Linux);
         let mut output = Vec::new();
-        let changes = modify_content(b"hello\r\n\rworld   ", &options, &mut output);
-        assert_eq!(output, b"hello\r\n\rworld");
+        let changes = modify_content(b"\r", &options, &mut output);
+        assert_eq!(output, b"\r");
+        assert_eq!(changes, vec![]);
+        assert_eq!(count_new_line_markers(b"\r").mac, 1);
+        assert_eq!(find_most_common_new_line_marker(b"\r"), NewLineMarker::Mac);
+
+        // Replaced by a single Linux new line marker.
+        let options = options
+            .clone()
+            .normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\r", &options, &mut output);
+        assert_eq!(output, b"\n");
         assert_eq!(
             changes,
-            vec![Change::new(3, ChangeType::RemovedTrailingWhitespace)]
+            vec![Change::new(
+                1,
+                ChangeType::ReplacedWhiteSpaceOnlyFileWithOneLine
+            )]
         );
-    }
 
-    #[test]
-    fn test_modify_content_remove_trailing_whitespace_3() {
-        let options: Options = Options::new().remove_trailing_whitespace();
+        // Processed line by line if whitespace-only files are not special.
+        let options = options.max_whitespace_only_size(0);
         let mut output = Vec::new();
-        let changes = modify_content(b"hello \t  \r\n \t  \rworld   ", &options, &mut output);
-        assert_eq!(output, b"hello\r\n\rworld");
+        let changes = modify_content(b"\r", &options, &mut output);
+        assert_eq!(output, b"\n");
         assert_eq!(
             changes,
-            vec![
-                Change::new(1, ChangeType::RemovedTrailingWhitespace),
-                Change::new(2, ChangeType::RemovedTrailingWhitespace),
-                Change::new(3, ChangeType::RemovedTrailingWhitespace)
-            ]
+            vec![Change::new(
+                1,
+                ChangeType::ReplacedNewLineMarker(NewLineMarker::Mac, NewLineMarker::Linux)
+            )]
         );
     }
 
     #[test]
-    fn test_modify_content_remove_trailing_whitespace_4() {
-        let options: Options = Options::new().remove_trailing_whitespace();
-        let mut output = Vec::new();
-        let changes = modify_content(b"hello world   \n\n   \n", &options, &mut output);
-        assert_eq!(output, b"hello world\n\n\n");
+    fn test_find_last_new_line_marker() {
+        assert_eq!(find_last_new_line_marker(b""), None);
+        assert_eq!(find_last_new_line_marker(b"hello"), None);
         assert_eq!(
-            changes,
-            vec![
-                Change::new(1, ChangeType::RemovedTrailingWhitespace),
-                Change::new(3, ChangeType::RemovedTrailingWhitespace),
-            ]
+            find_last_new_line_marker(b"a\nb\nc\r\nd"),
+            Some(NewLineMarker::Windows)
+        );
+        assert_eq!(
+            find_last_new_line_marker(b"a\r\nb\r\nc\n"),
+            Some(NewLineMarker::Linux)
+        );
+        assert_eq!(
+            find_last_new_line_marker(b"a\nb\r"),
+            Some(NewLineMarker::Mac)
+        );
+        assert_eq!(
+            find_last_new_line_marker(b"\r\n"),
+            Some(NewLineMarker::Windows)
         );
+        assert_eq!(find_last_new_line_marker(b"\n"), Some(NewLineMarker::Linux));
     }
 
     #[test]
-    fn test_modify_content_remove_trailing_whitespace_5() {
-        let options: Options = Options::new().remove_trailing_whitespace();
+    fn test_modify_content_keep_per_line_add_new_line_marker() {
+        // Most lines end with '\n', but the last new line marker is '\r\n'.
+        let input_data = b"a  \nb\nc\nd\r\ne";
+
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::KeepPerLine)
+            .remove_trailing_whitespace()
+            .add_new_line_marker_at_end_of_file();
         let mut output = Vec::new();
-        let changes = modify_content(b"hello world   \x0C  \n\n \x0B \n", &options, &mut output);
-        assert_eq!(output, b"hello world\n\n\n");
+        let changes = modify_content(input_data, &options, &mut output);
+        assert_eq!(output, b"a\nb\nc\nd\r\ne\r\n");
         assert_eq!(
             changes,
             vec![
-                Change::new(1, ChangeType::RemovedTrailingWhitespace),
-                Change::new(3, ChangeType::RemovedTrailingWhitespace),
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(5, ChangeType::NewLineMarkerAddedToEndOfFile),
             ]
         );
+
+        // The automatic mode uses the most common marker instead.
+        let options: Options = options.new_line_marker(OutputNewLineMarkerMode::Auto);
+        let mut output = Vec::new();
+        modify_content(input_data, &options, &mut output);
+        assert_eq!(output, b"a\nb\nc\nd\r\ne\n");
     }
 
     #[test]
-    fn test_modify_content_remove_trailing_whitespace_and_normalize_non_standard_whitespace_1() {
+    fn test_modify_content_keep_per_line_never_normalizes() {
         let options: Options = Options::new()
-            .remove_trailing_whitespace()
-            .normalize_non_standard_whitespace(NonStandardWhitespaceReplacementMode::Remove);
+            .new_line_marker(OutputNewLineMarkerMode::KeepPerLine)
+            .normalize_new_line_markers();
         let mut output = Vec::new();
-        let changes = modify_content(b"hello world   \x0C  \n\n \x0B \n", &options, &mut output);
-        assert_eq!(output, b"hello world\n\n\n");
+        let changes = modify_content(b"a\r\nb\rc\n", &options, &mut output);
+        assert_eq!(output, b"a\r\nb\rc\n");
+        assert_eq!(changes, vec![]);
+
+        // No new line marker in the file; Linux new line marker is added.
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::KeepPerLine)
+            .add_new_line_marker_at_end_of_file();
+        let mut output = Vec::new();
+        modify_content(b"a", &options, &mut output);
+        assert_eq!(output, b"a\n");
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_empty_lines() {
+        let options: Options = Options::new().remove_trailing_empty_lines();
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello\r\n\rworld\r\n\n\n\n\n\n", &options, &mut output);
+        assert_eq!(output, b"hello\r\n\rworld\r\n");
         assert_eq!(
             changes,
-            vec![
-                Change::new(1, ChangeType::RemovedNonstandardWhitespace(0x0C)),
-                Change::new(1, ChangeType::RemovedTrailingWhitespace),
-                Change::new(3, ChangeType::RemovedNonstandardWhitespace(0x0B)),
-                Change::new(3, ChangeType::RemovedTrailingWhitespace),
-            ]
+            vec![Change::new(4, ChangeType::RemovedTrailingEmptyLines)]
         );
     }
 
     #[test]
-    fn test_modify_content_remove_trailing_whitespace_and_normalize_non_standard_whitespace_2() {
+    fn test_modify_content_remove_trailing_empty_lines_and_add_new_line_marker() {
+        // Together, the options leave exactly one new line marker at the end of the file.
         let options: Options = Options::new()
-            .remove_trailing_whitespace()
-            .normalize_non_standard_whitespace(
-                NonStandardWhitespaceReplacementMode::ReplaceWithSpace,
-            );
+            .remove_trailing_empty_lines()
+            .add_new_line_marker_at_end_of_file();
+        for (input, expected_changes) in [
+            (
+                "a",
+                vec![Change::new(1, ChangeType::NewLineMarkerAddedToEndOfFile)],
+            ),
+            ("a\n", vec![]),
+            (
+                "a\n\n",
+                vec![Change::new(2, ChangeType::RemovedTrailingEmptyLines)],
+            ),
+            (
+                "a\n\n\n",
+                vec![Change::new(2, ChangeType::RemovedTrailingEmptyLines)],
+            ),
+        ] {
+            let mut output = Vec::new();
+            let changes = modify_content(input.as_bytes(), &options, &mut output);
+            assert_eq!(output, b"a\n", "{:?}", input);
+            assert_eq!(changes, expected_changes, "{:?}", input);
+
+            let mut second_output = Vec::new();
+            let second_changes = modify_content(&output, &options, &mut second_output);
+            assert_eq!(second_output, b"a\n", "{:?}", input);
+            assert_eq!(second_changes, vec![], "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_modify_content_max_trailing_blank_lines_0() {
+        let options: Options = Options::new().max_trailing_blank_lines(0);
         let mut output = Vec::new();
-        let changes = modify_content(b"hello world   \x0C  \n\n \x0B \n", &options, &mut output);
-        assert_eq!(output, b"hello world\n\n\n");
+        let changes = modify_content(b"hello\n\n\n\n\n", &options, &mut output);
+        assert_eq!(output, b"hello\n");
         assert_eq!(
             changes,
-            vec![
-                Change::new(1, ChangeType::ReplacedNonstandardWhitespaceBySpace(0x0C)),
-                Change::new(1, ChangeType::RemovedTrailingWhitespace),
-                Change::new(3, ChangeType::ReplacedNonstandardWhitespaceBySpace(0x0B)),
-                Change::new(3, ChangeType::RemovedTrailingWhitespace),
-            ]
+            vec![Change::new(2, ChangeType::RemovedTrailingEmptyLines)]
         );
+
+        let mut expected_output = Vec::new();
+        let expected_changes = modify_content(
+            b"hello\n\n\n\n\n",
+            &Options::new().remove_trailing_empty_lines(),
+            &mut expected_output,
+        );
+        assert_eq!(output, expected_output);
+        assert_eq!(changes, expected_changes);
     }
 
     #[test]
-    fn test_modify_content_remove_trailing_whitespace_and_modify_content_remove_trailing_empty_lines(
-    ) {
-        let options: Options = Options::new()
-            .remove_trailing_whitespace()
-            .remove_trailing_empty_lines();
+    fn test_modify_content_max_trailing_blank_lines_1() {
+        let options: Options = Options::new().max_trailing_blank_lines(1);
         let mut output = Vec::new();
-        let changes = modify_content(b"hello world   \n\n   \n", &options, &mut output);
-        assert_eq!(output, b"hello world\n");
+        let changes = modify_content(b"hello\n\n\n\n\n", &options, &mut output);
+        assert_eq!(output, b"hello\n\n");
         assert_eq!(
             changes,
-            vec![
-                Change::new(1, ChangeType::RemovedTrailingWhitespace),
-                Change::new(3, ChangeType::RemovedTrailingWhitespace),
-                Change::new(2, ChangeType::RemovedEmptyLines),
-            ]
+            vec![Change::new(3, ChangeType::RemovedTrailingEmptyLines)]
         );
+
+        let mut second_output = Vec::new();
+        let second_changes = modify_content(&output, &options, &mut second_output);
+        assert_eq!(second_output, output);
+        assert_eq!(second_changes, vec![]);
     }
 
     #[test]
-    fn test_modify_content_normalize_empty_files_empty() {
-        let options: Options =
-            Options::new().normalize_empty_files(TrivialFileReplacementMode::Empty);
+    fn test_modify_content_max_trailing_blank_lines_2() {
+        let options: Options = Options::new().max_trailing_blank_lines(2);
         let mut output = Vec::new();
-        let changes = modify_content(b"", &options, &mut output);
-        assert_eq!(output, b"");
-        assert_eq!(changes, vec![]);
+        let changes = modify_content(b"hello\r\n\r\n\r\n\r\n\r\n", &options, &mut output);
+        assert_eq!(output, b"hello\r\n\r\n\r\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(4, ChangeType::RemovedTrailingEmptyLines)]
+        );
+
+        let mut second_output = Vec::new();
+        let second_changes = modify_content(&output, &options, &mut second_output);
+        assert_eq!(second_output, output);
+        assert_eq!(second_changes, vec![]);
     }
 
     #[test]
-    fn test_modify_content_normalize_empty_files_ignore() {
-        let options: Options =
-            Options::new().normalize_empty_files(TrivialFileReplacementMode::Ignore);
+    fn test_modify_content_max_trailing_blank_lines_fewer_lines() {
+        let options: Options = Options::new().max_trailing_blank_lines(2);
         let mut output = Vec::new();
-        let changes = modify_content(b"", &options, &mut output);
-        assert_eq!(output, b"");
+        let changes = modify_content(b"hello\n\n", &options, &mut output);
+        assert_eq!(output, b"hello\n\n");
+        assert_eq!(changes, vec![]);
+
+        let changes = modify_content(b"hello", &options, &mut output);
         assert_eq!(changes, vec![]);
     }
 
     #[test]
-    fn test_modify_content_normalize_empty_files_one_line() {
-        let options: Options =
-            Options::new().normalize_empty_files(TrivialFileReplacementMode::OneLine);
+    fn test_modify_content_remove_trailing_empty_lines_mac_1() {
+        let options: Options = Options::new().remove_trailing_empty_lines();
         let mut output = Vec::new();
-        let changes = modify_content(b"", &options, &mut output);
-        assert_eq!(output, b"\n");
+        let changes = modify_content(b"a\rb\r\r\r", &options, &mut output);
+        assert_eq!(output, b"a\rb\r");
         assert_eq!(
             changes,
-            vec![Change::new(1, ChangeType::ReplacedEmptyFileWithOneLine)]
+            vec![Change::new(3, ChangeType::RemovedTrailingEmptyLines)]
         );
     }
 
     #[test]
-    fn test_modify_content_normalize_whitespace_only_files_empty() {
-        let options: Options =
-            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::Empty);
+    fn test_modify_content_remove_trailing_empty_lines_mac_2() {
+        // Empty lines delimited by '\r' followed by an empty line delimited by '\r\n'.
+        let options: Options = Options::new().remove_trailing_empty_lines();
         let mut output = Vec::new();
-        let changes = modify_content(b"\n\t \x0B \x0C \n  ", &options, &mut output);
-        assert_eq!(output, b"");
+        let changes = modify_content(b"a\r\r\r\n\r", &options, &mut output);
+        assert_eq!(output, b"a\r");
         assert_eq!(
             changes,
-            vec![Change::new(
-                1,
-                ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile
-            )]
+            vec![Change::new(2, ChangeType::RemovedTrailingEmptyLines)]
         );
     }
 
     #[test]
-    fn test_modify_content_normalize_whitespace_only_files_ignore() {
-        let options: Options =
-            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::Ignore);
+    fn test_modify_content_remove_trailing_empty_lines_mac_3() {
+        // Files consisting of only new line markers are handled by
+        // the normalization of whitespace-only files, not by this option.
+        let options: Options = Options::new().remove_trailing_empty_lines();
         let mut output = Vec::new();
-        let changes = modify_content(b"\n\t \x0B \x0C \n  ", &options, &mut output);
-        assert_eq!(output, b"\n\t \x0B \x0C \n  ");
+        let changes = modify_content(b"\r\r\r", &options, &mut output);
+        assert_eq!(output, b"\r\r\r");
         assert_eq!(changes, vec![]);
-    }
 
-    #[test]
-    fn test_modify_content_normalize_whitespace_only_files_one_line() {
-        let options: Options =
-            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine);
+        let options: Options = Options::new()
+            .remove_trailing_empty_lines()
+            .normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine);
         let mut output = Vec::new();
-        let changes = modify_content(b"\n\t \x0B \x0C \n  ", &options, &mut output);
-        assert_eq!(output, b"\n");
+        let changes = modify_content(b"\r\r\r", &options, &mut output);
+        assert_eq!(output, b"\r");
         assert_eq!(
             changes,
             vec![Change::new(
@@ -970,147 +3382,2908 @@ mod tests {
     }
 
     #[test]
-    fn test_modify_content_normalize_whitespace_only_files_one_line_2() {
-        let options: Options =
-            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine);
+    fn test_modify_content_remove_trailing_empty_lines_mac_4() {
+        // Whitespace-only lines become empty first and then they are removed.
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines();
         let mut output = Vec::new();
-        let changes = modify_content(b"\n", &options, &mut output);
-        assert_eq!(output, b"\n");
-        assert_eq!(changes, vec![]);
+        let changes = modify_content(b"a\rb \r \r\t\r", &options, &mut output);
+        assert_eq!(output, b"a\rb\r");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(4, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(3, ChangeType::RemovedTrailingEmptyLines),
+            ]
+        );
     }
 
     #[test]
-    fn test_modify_content_normalize_whitespace_only_files_one_line_3() {
+    fn test_modify_content_remove_trailing_empty_lines_mac_5() {
+        // The rewind must not leave a partial new line marker behind.
         let options: Options = Options::new()
-            .normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine)
-            .new_line_marker(OutputNewLineMarkerMode::Linux);
+            .new_line_marker(OutputNewLineMarkerMode::Windows)
+            .normalize_new_line_markers()
+            .remove_trailing_empty_lines();
         let mut output = Vec::new();
-        let changes = modify_content(b"\r\n", &options, &mut output);
-        assert_eq!(output, b"\n");
+        let changes = modify_content(b"a\rb\r\r\r", &options, &mut output);
+        assert_eq!(output, b"a\r\nb\r\n");
         assert_eq!(
             changes,
-            vec![Change::new(
-                1,
-                ChangeType::ReplacedWhiteSpaceOnlyFileWithOneLine
-            )]
+            vec![
+                Change::new(
+                    1,
+                    ChangeType::ReplacedNewLineMarker(NewLineMarker::Mac, NewLineMarker::Windows)
+                ),
+                Change::new(
+                    2,
+                    ChangeType::ReplacedNewLineMarker(NewLineMarker::Mac, NewLineMarker::Windows)
+                ),
+                Change::new(
+                    3,
+                    ChangeType::ReplacedNewLineMarker(NewLineMarker::Mac, NewLineMarker::Windows)
+                ),
+                Change::new(
+                    4,
+                    ChangeType::ReplacedNewLineMarker(NewLineMarker::Mac, NewLineMarker::Windows)
+                ),
+                Change::new(3, ChangeType::RemovedTrailingEmptyLines),
+            ]
         );
     }
 
     #[test]
-    fn test_modify_content_normalize_whitespace_only_files_one_line_4() {
-        let options: Options =
-            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine);
+    fn test_modify_content_normalize_unicode_line_separators_1() {
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_unicode_line_separators();
         let mut output = Vec::new();
-        let changes = modify_content(b"\r\n", &options, &mut output);
-        assert_eq!(output, b"\r\n");
-        assert_eq!(changes, vec![]);
+        let changes = modify_content(
+            "a\u{2028}b\r\nc\u{2029}d\n".as_bytes(),
+            &options,
+            &mut output,
+        );
+        assert_eq!(output, b"a\nb\r\nc\nd\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(
+                    1,
+                    ChangeType::ReplacedUnicodeLineSeparator('\u{2028}', NewLineMarker::Linux)
+                ),
+                Change::new(
+                    3,
+                    ChangeType::ReplacedUnicodeLineSeparator('\u{2029}', NewLineMarker::Linux)
+                ),
+            ]
+        );
     }
 
     #[test]
-    fn test_modify_content_replace_tabs_with_spaces_ignore() {
-        let options: Options = Options::new().replace_tabs_with_spaces(-47);
+    fn test_modify_content_normalize_unicode_line_separators_2() {
+        // Mixed with classic new line markers and normalization.
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Windows)
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .normalize_unicode_line_separators();
         let mut output = Vec::new();
-        let changes = modify_content(b"\t", &options, &mut output);
-        assert_eq!(output, b"\t");
+        let changes = modify_content(
+            "a \u{2028}b\rc\n\u{2029}\u{2028}".as_bytes(),
+            &options,
+            &mut output,
+        );
+        assert_eq!(output, b"a\r\nb\r\nc\r\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(
+                    1,
+                    ChangeType::ReplacedUnicodeLineSeparator('\u{2028}', NewLineMarker::Windows)
+                ),
+                Change::new(
+                    2,
+                    ChangeType::ReplacedNewLineMarker(NewLineMarker::Mac, NewLineMarker::Windows)
+                ),
+                Change::new(
+                    3,
+                    ChangeType::ReplacedNewLineMarker(NewLineMarker::Linux, NewLineMarker::Windows)
+                ),
+                Change::new(
+                    4,
+                    ChangeType::ReplacedUnicodeLineSeparator('\u{2029}', NewLineMarker::Windows)
+                ),
+                Change::new(
+                    5,
+                    ChangeType::ReplacedUnicodeLineSeparator('\u{2028}', NewLineMarker::Windows)
+                ),
+                Change::new(4, ChangeType::RemovedTrailingEmptyLines),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_normalize_unicode_line_separators_3() {
+        // Other characters whose UTF-8 encoding starts with the same bytes are left alone,
+        // and so is a truncated sequence at the end of the file.
+        let options: Options = Options::new().normalize_unicode_line_separators();
+        let input_data: &[u8] = b"en\xE2\x80\x93dash \xE2\x80\xA7\n\xE2\x80";
+        let mut output = Vec::new();
+        let changes = modify_content(input_data, &options, &mut output);
+        assert_eq!(output, input_data);
+        assert_eq!(changes, vec![]);
+
+        // Without the option, the separators are ordinary characters.
+        let options: Options = Options::new().remove_trailing_empty_lines();
+        let input_data = "a\n\u{2028}\n".as_bytes();
+        let mut output = Vec::new();
+        let changes = modify_content(input_data, &options, &mut output);
+        assert_eq!(output, input_data);
         assert_eq!(changes, vec![]);
     }
 
     #[test]
-    fn test_modify_content_replace_tabs_with_spaces_0() {
-        let options: Options = Options::new().replace_tabs_with_spaces(0);
+    fn test_modify_content_columns() {
+        // Columns are 1-based byte columns within the line of the original file.
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .replace_tabs_with_spaces(2)
+            .normalize_non_standard_whitespace(NonStandardWhitespaceReplacementMode::Remove);
         let mut output = Vec::new();
-        let changes = modify_content(b"\thello", &options, &mut output);
-        assert_eq!(output, b"hello");
-        assert_eq!(changes, vec![Change::new(1, ChangeType::RemovedTab)]);
+        let changes = modify_content(b"ab\tc \t\r\n\tx\x0Cy\n  z \x0B", &options, &mut output);
+        assert_eq!(output, b"ab  c\r\n  xy\n  z");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::ReplacedTabWithSpaces(2)).with_column(3),
+                Change::new(1, ChangeType::ReplacedTabWithSpaces(2)).with_column(6),
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(5),
+                Change::new(2, ChangeType::ReplacedTabWithSpaces(2)).with_column(1),
+                Change::new(2, ChangeType::RemovedNonstandardWhitespace(0x0C)).with_column(3),
+                Change::new(3, ChangeType::RemovedNonstandardWhitespace(0x0B)).with_column(5),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(4),
+            ]
+        );
     }
 
     #[test]
-    fn test_modify_content_replace_tabs_with_spaces_3() {
-        let options: Options = Options::new().replace_tabs_with_spaces(3);
+    fn test_modify_content_region() {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .region("# BEGIN MANAGED", "# END MANAGED");
         let mut output = Vec::new();
-        let changes = modify_content(b"\thello", &options, &mut output);
-        assert_eq!(output, b"   hello");
+        let changes = modify_content(
+            b"outside  \n# BEGIN MANAGED  \ninside  \n\tinside 2 \n# END MANAGED \nafter  \n",
+            &options,
+            &mut output,
+        );
+        assert_eq!(
+            output,
+            b"outside  \n# BEGIN MANAGED  \ninside\n\tinside 2\n# END MANAGED \nafter  \n"
+        );
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(7),
+                Change::new(4, ChangeType::RemovedTrailingWhitespace).with_column(10),
+            ]
+        );
+
+        // Several regions, Windows new line markers and an unterminated region at the end.
+        let mut output = Vec::new();
+        let changes = modify_content(
+            b"a \r\n# BEGIN MANAGED\r\nb \r\n# END MANAGED\r\nc \r\n# BEGIN MANAGED\r\nd ",
+            &options,
+            &mut output,
+        );
+        assert_eq!(
+            output,
+            b"a \r\n# BEGIN MANAGED\r\nb\r\n# END MANAGED\r\nc \r\n# BEGIN MANAGED\r\nd"
+        );
         assert_eq!(
             changes,
-            vec![Change::new(1, ChangeType::ReplacedTabWithSpaces)]
+            vec![
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(7, ChangeType::RemovedTrailingWhitespace).with_column(2),
+            ]
         );
     }
 
     #[test]
-    fn test_modify_content_normalize_non_standard_whitespace_ignore() {
+    fn test_modify_content_line_range() {
         let options: Options = Options::new()
-            .normalize_non_standard_whitespace(NonStandardWhitespaceReplacementMode::Ignore);
+            .remove_trailing_whitespace()
+            .replace_tabs_with_spaces(2)
+            .remove_trailing_empty_lines()
+            .add_new_line_marker_at_end_of_file()
+            .line_range(2, 3);
         let mut output = Vec::new();
-        let changes = modify_content(b"\x0B\x0Chello\t ", &options, &mut output);
-        assert_eq!(output, b"\x0B\x0Chello\t ");
-        assert_eq!(changes, vec![]);
+        let changes = modify_content(b"a \n\tb \nc\t\n\td \ne \n\n", &options, &mut output);
+        assert_eq!(output, b"a \n  b\nc\n\td \ne \n\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(2, ChangeType::ReplacedTabWithSpaces(2)).with_column(1),
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(3),
+                Change::new(3, ChangeType::ReplacedTabWithSpaces(2)).with_column(2),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(2),
+            ]
+        );
+
+        // Several ranges. The last line has no new line marker, which is not added.
+        let options = options.line_range(5, 10);
+        let mut output = Vec::new();
+        let changes = modify_content(b"a \n\tb \nc\t\n\td \ne ", &options, &mut output);
+        assert_eq!(output, b"a \n  b\nc\n\td \ne");
+        assert_eq!(changes.len(), 5);
     }
 
     #[test]
-    fn test_modify_content_normalize_non_standard_whitespace_replace_with_space() {
-        let options: Options = Options::new().normalize_non_standard_whitespace(
-            NonStandardWhitespaceReplacementMode::ReplaceWithSpace,
+    fn test_modify_content_region_suppresses_whole_file_operations() {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .add_new_line_marker_at_end_of_file()
+            .normalize_whitespace_only_files(TrivialFileReplacementMode::Empty)
+            .region("BEGIN", "END");
+        for input_data in [
+            b"text  \n\n\n".as_slice(),
+            b"text".as_slice(),
+            b"  \n \n".as_slice(),
+        ] {
+            let mut output = Vec::new();
+            let changes = modify_content(input_data, &options, &mut output);
+            assert_eq!(output, input_data);
+            assert_eq!(changes, vec![]);
+        }
+
+        // Inside the region, empty lines are left as is too.
+        let mut output = Vec::new();
+        let changes = modify_content(b"BEGIN\n\t\n\n", &options, &mut output);
+        assert_eq!(output, b"BEGIN\n\n\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(1)]
         );
+    }
+
+    #[test]
+    fn test_expand_indentation() {
+        assert_eq!(expand_indentation(b"\t", 4), Some(b"    ".to_vec()));
+        assert_eq!(expand_indentation(b"\t\t  ", 4), Some(vec![b' '; 10]));
+        assert_eq!(expand_indentation(b"  ", 4), Some(b"  ".to_vec()));
+        // A tab after spaces.
+        assert_eq!(expand_indentation(b"  \t", 4), None);
+        // Spaces that would collapse to another tab.
+        assert_eq!(expand_indentation(b"\t    ", 4), None);
+        assert_eq!(expand_indentation(b"\t   ", 4), Some(b"       ".to_vec()));
+    }
+
+    #[test]
+    fn test_modify_content_retab_tabs_to_spaces() {
+        let options: Options = Options::new().retab_tabs_to_spaces(4);
         let mut output = Vec::new();
-        let changes = modify_content(b"\x0B\x0Chello\t ", &options, &mut output);
-        assert_eq!(output, b"  hello\t ");
+        let changes = modify_content(
+            b"fn main() {\n\tif x {\n\t\tcall(\t1);\n\t}\n}\n",
+            &options,
+            &mut output,
+        );
+        assert_eq!(
+            output,
+            b"fn main() {\n    if x {\n        call(\t1);\n    }\n}\n"
+        );
         assert_eq!(
             changes,
             vec![
-                Change::new(1, ChangeType::ReplacedNonstandardWhitespaceBySpace(0x0B)),
-                Change::new(1, ChangeType::ReplacedNonstandardWhitespaceBySpace(0x0C)),
+                Change::new(2, ChangeType::ExpandedIndentationTabs).with_column(1),
+                Change::new(3, ChangeType::ExpandedIndentationTabs).with_column(1),
+                Change::new(4, ChangeType::ExpandedIndentationTabs).with_column(1),
             ]
         );
     }
 
     #[test]
-    fn test_modify_content_normalize_non_standard_whitespace_remove() {
-        let options: Options = Options::new()
-            .normalize_non_standard_whitespace(NonStandardWhitespaceReplacementMode::Remove);
+    fn test_modify_content_retab_tabs_to_spaces_ambiguous() {
+        // Lines with mixed indentation are flagged and left unchanged.
+        let options: Options = Options::new().retab_tabs_to_spaces(4);
+        let input_data: &[u8] = b"a\n  \tb\r\n\t    c\r\t d\n\t";
         let mut output = Vec::new();
-        let changes = modify_content(b"\x0B\x0Chello\t ", &options, &mut output);
-        assert_eq!(output, b"hello\t ");
+        let changes = modify_content(input_data, &options, &mut output);
+        assert_eq!(output, b"a\n  \tb\r\n\t    c\r     d\n    ");
         assert_eq!(
             changes,
             vec![
-                Change::new(1, ChangeType::RemovedNonstandardWhitespace(0x0B)),
-                Change::new(1, ChangeType::RemovedNonstandardWhitespace(0x0C)),
+                Change::new(4, ChangeType::ExpandedIndentationTabs).with_column(1),
+                Change::new(5, ChangeType::ExpandedIndentationTabs).with_column(1),
             ]
         );
+        assert_eq!(
+            find_lines_with_ambiguous_indentation(input_data, 4),
+            vec![2, 3]
+        );
+        assert_eq!(
+            find_lines_with_ambiguous_indentation(b"\tclean\n\t\tfile\n", 4),
+            Vec::<usize>::new()
+        );
     }
 
     #[test]
-    fn test_process_file() {
+    fn test_modify_content_blank_lines_include_whitespace_1() {
         let options: Options = Options::new()
-            .new_line_marker(OutputNewLineMarkerMode::Linux)
-            .add_new_line_marker_at_end_of_file()
-            .normalize_new_line_markers()
-            .remove_trailing_whitespace()
             .remove_trailing_empty_lines()
-            .normalize_non_standard_whitespace(NonStandardWhitespaceReplacementMode::Remove)
-            .replace_tabs_with_spaces(4);
+            .blank_lines_include_whitespace();
 
-        let args = vec![
-            "src/",
-            ".gitignore",
-            "Cargo.lock",
-            "Cargo.toml",
-            "DEVELOPING.md",
-            "LICENSE",
-            "README.md",
-        ];
+        // Tab-only lines are handled exactly like space-only lines.
+        for input_data in [
+            b"a\n\t\n\t\t\n".as_slice(),
+            b"a\n \n  \n".as_slice(),
+            b"a\n \t\n\x0B\x0C\n".as_slice(),
+        ] {
+            let mut output = Vec::new();
+            let changes = modify_content(input_data, &options, &mut output);
+            assert_eq!(output, b"a\n");
+            assert_eq!(
+                changes,
+                vec![Change::new(2, ChangeType::RemovedTrailingEmptyLines)]
+            );
+        }
 
-        let path_bufs = args.iter().map(|x| PathBuf::from(x)).collect::<Vec<_>>();
-        let files = discover_files(&path_bufs, false);
+        // Whitespace-only lines that are followed by content are kept.
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\n\t\nb\n\t\n", &options, &mut output);
+        assert_eq!(output, b"a\n\t\nb\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(4, ChangeType::RemovedTrailingEmptyLines)]
+        );
 
-        for file in &files {
-            let changes = process_file(file, &options, true);
+        // The last line does not need to be terminated.
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\n\t\n\t", &options, &mut output);
+        assert_eq!(output, b"a\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(2, ChangeType::RemovedTrailingEmptyLines)]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_blank_lines_include_whitespace_2() {
+        // Without the option, lines with whitespace are not empty.
+        let options: Options = Options::new().remove_trailing_empty_lines();
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\n\t\n\t\t\n", &options, &mut output);
+        assert_eq!(output, b"a\n\t\n\t\t\n");
+        assert_eq!(changes, vec![]);
+
+        // Files consisting of tabs only are whitespace-only files like files consisting of spaces.
+        let options: Options = Options::new()
+            .remove_trailing_empty_lines()
+            .blank_lines_include_whitespace()
+            .normalize_whitespace_only_files(TrivialFileReplacementMode::Empty);
+        for input_data in [b"\t\n\t\t\n".as_slice(), b" \n  \n".as_slice()] {
+            let mut output = Vec::new();
+            let changes = modify_content(input_data, &options, &mut output);
+            assert_eq!(output, b"");
             assert_eq!(
                 changes,
-                vec![],
-                "The file `{:?}` is not properly formatted.",
-                file
+                vec![Change::new(
+                    1,
+                    ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile
+                )]
             );
         }
     }
+
+    #[test]
+    fn test_modify_content_ensure_trailing_newlines_0() {
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .ensure_trailing_newlines(0);
+
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\nb\n\n\n", &options, &mut output);
+        assert_eq!(output, b"a\nb");
+        assert_eq!(
+            changes,
+            vec![Change::new(2, ChangeType::EnsuredTrailingNewLineMarkers(0))]
+        );
+
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\nb", &options, &mut output);
+        assert_eq!(output, b"a\nb");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_ensure_trailing_newlines_1() {
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .ensure_trailing_newlines(1);
+
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\nb", &options, &mut output);
+        assert_eq!(output, b"a\nb\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(2, ChangeType::EnsuredTrailingNewLineMarkers(1))]
+        );
+
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\nb\r\n\n\r", &options, &mut output);
+        assert_eq!(output, b"a\nb\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(2, ChangeType::EnsuredTrailingNewLineMarkers(1))]
+        );
+
+        // The new line marker of the last line is replaced even without normalization.
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\nb\r\n", &options, &mut output);
+        assert_eq!(output, b"a\nb\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(2, ChangeType::EnsuredTrailingNewLineMarkers(1))]
+        );
+
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\nb\n", &options, &mut output);
+        assert_eq!(output, b"a\nb\n");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_ensure_trailing_newlines_3() {
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Windows)
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .ensure_trailing_newlines(3);
+
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\r\nb  ", &options, &mut output);
+        assert_eq!(output, b"a\r\nb\r\n\r\n\r\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(2, ChangeType::EnsuredTrailingNewLineMarkers(3)),
+            ]
+        );
+
+        // Combined with the removal of trailing empty lines and the addition
+        // of the missing new line marker.
+        let options: Options = options
+            .remove_trailing_empty_lines()
+            .add_new_line_marker_at_end_of_file();
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\r\nb\r\n \r\n\r\n\r\n\r\n", &options, &mut output);
+        assert_eq!(output, b"a\r\nb\r\n\r\n\r\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(3, ChangeType::RemovedTrailingEmptyLines),
+                Change::new(2, ChangeType::EnsuredTrailingNewLineMarkers(3)),
+            ]
+        );
+
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\r\nb", &options, &mut output);
+        assert_eq!(output, b"a\r\nb\r\n\r\n\r\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(2, ChangeType::NewLineMarkerAddedToEndOfFile),
+                Change::new(2, ChangeType::EnsuredTrailingNewLineMarkers(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_ensure_trailing_newlines_idempotence() {
+        for number_of_new_line_markers in [0, 1, 3] {
+            let options: Options = Options::new()
+                .new_line_marker(OutputNewLineMarkerMode::Mac)
+                .remove_trailing_whitespace()
+                .ensure_trailing_newlines(number_of_new_line_markers);
+            for input_data in [
+                b"a\r\nb\n\n \n\r".as_slice(),
+                b"a\nb".as_slice(),
+                b"a\r\r\r\r\r".as_slice(),
+            ] {
+                let mut output = Vec::new();
+                modify_content(input_data, &options, &mut output);
+                assert!(output.ends_with(&b"\r".repeat(number_of_new_line_markers)));
+
+                let mut second_output = Vec::new();
+                let changes = modify_content(&output, &options, &mut second_output);
+                assert_eq!(second_output, output);
+                assert_eq!(changes, vec![]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_modify_content_strip_bom() {
+        let options: Options = Options::new()
+            .strip_bom()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines();
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBFhello  \nworld\n\n", &options, &mut output);
+        assert_eq!(output, b"hello\nworld\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedByteOrderMark),
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(6),
+                Change::new(3, ChangeType::RemovedTrailingEmptyLines),
+            ]
+        );
+
+        // File without a byte order mark.
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello\n", &options, &mut output);
+        assert_eq!(output, b"hello\n");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_add_bom() {
+        let options: Options = Options::new()
+            .add_bom()
+            .add_new_line_marker_at_end_of_file();
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello", &options, &mut output);
+        assert_eq!(output, b"\xEF\xBB\xBFhello\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::AddedByteOrderMark),
+                Change::new(1, ChangeType::NewLineMarkerAddedToEndOfFile),
+            ]
+        );
+
+        // File with a byte order mark.
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBFhello\n", &options, &mut output);
+        assert_eq!(output, b"\xEF\xBB\xBFhello\n");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_keep_bom() {
+        // Without --strip-bom and --add-bom, the byte order mark is preserved and
+        // the rewinds never remove it.
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .remove_new_line_marker_from_end_of_file();
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBF  \n\nhello \n\n", &options, &mut output);
+        assert_eq!(output, b"\xEF\xBB\xBF\n\nhello");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(6),
+                Change::new(4, ChangeType::RemovedTrailingEmptyLines),
+                Change::new(3, ChangeType::NewLineMarkerRemovedFromEndOfFile),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_bom_whitespace_only_file() {
+        // The byte order mark is not considered to be content.
+        let options: Options =
+            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::Empty);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBF \t\n", &options, &mut output);
+        assert_eq!(output, b"\xEF\xBB\xBF");
+        assert_eq!(
+            changes,
+            vec![Change::new(
+                1,
+                ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile
+            )]
+        );
+
+        let options: Options = options.strip_bom();
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBF \t\n", &options, &mut output);
+        assert_eq!(output, b"");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedByteOrderMark),
+                Change::new(1, ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_whitespace_only_file_line_rules() {
+        let input_data = b"   \n   \n   \n";
+
+        // By default, the whitespace-only file is left as is, despite the line rules.
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines();
+        let mut output = Vec::new();
+        let changes = modify_content(input_data, &options, &mut output);
+        assert_eq!(output, input_data);
+        assert_eq!(changes, vec![]);
+
+        // With the option, the line rules collapse it into an empty file.
+        let options: Options = options.apply_line_rules_to_whitespace_only_files();
+        let mut output = Vec::new();
+        let changes = modify_content(input_data, &options, &mut output);
+        assert_eq!(output, b"");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(1, ChangeType::RemovedTrailingEmptyLines),
+            ]
+        );
+
+        // Idempotent.
+        let mut second_output = Vec::new();
+        let second_changes = modify_content(&output, &options, &mut second_output);
+        assert_eq!(second_output, b"");
+        assert_eq!(second_changes, vec![]);
+
+        // Only trailing whitespace is removed without removing empty lines.
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .apply_line_rules_to_whitespace_only_files();
+        let mut output = Vec::new();
+        modify_content(input_data, &options, &mut output);
+        assert_eq!(output, b"\n\n\n");
+    }
+
+    #[test]
+    fn test_modify_content_max_whitespace_only_size() {
+        let input_data: Vec<u8> = b"    \n".repeat(1000);
+
+        // Within the limit, the file is replaced with an empty file.
+        let options: Options = Options::new()
+            .normalize_whitespace_only_files(TrivialFileReplacementMode::Empty)
+            .max_whitespace_only_size(input_data.len());
+        let mut output = Vec::new();
+        let changes = modify_content(&input_data, &options, &mut output);
+        assert_eq!(output, b"");
+        assert_eq!(
+            changes,
+            vec![Change::new(
+                1,
+                ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile
+            )]
+        );
+
+        // Over the limit, the file is left to the line-by-line processing.
+        let options: Options = options.max_whitespace_only_size(input_data.len() - 1);
+        let mut output = Vec::new();
+        let changes = modify_content(&input_data, &options, &mut output);
+        assert_eq!(output, input_data);
+        assert_eq!(changes, vec![]);
+
+        let options: Options = options.remove_trailing_whitespace();
+        let mut output = Vec::new();
+        let changes = modify_content(&input_data, &options, &mut output);
+        assert_eq!(output, b"\n".repeat(1000));
+        assert_eq!(changes.len(), 1000);
+    }
+
+    #[test]
+    fn test_modify_content_bom_only_file() {
+        let options: Options = Options::new()
+            .remove_trailing_empty_lines()
+            .add_new_line_marker_at_end_of_file();
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBF", &options, &mut output);
+        assert_eq!(output, b"\xEF\xBB\xBF");
+        assert_eq!(changes, vec![]);
+
+        let options: Options = options.strip_bom();
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBF", &options, &mut output);
+        assert_eq!(output, b"");
+        assert_eq!(
+            changes,
+            vec![Change::new(1, ChangeType::RemovedByteOrderMark)]
+        );
+
+        let options: Options = options.add_bom();
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBF", &options, &mut output);
+        assert_eq!(output, b"\xEF\xBB\xBF");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_bom_only_file_normalize_empty_files() {
+        // Without any options, the file is left unchanged.
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBF", &Options::new(), &mut output);
+        assert_eq!(output, b"\xEF\xBB\xBF");
+        assert_eq!(changes, vec![]);
+
+        // With the byte order mark kept, the file is empty, so it gets a single new line marker.
+        let options: Options =
+            Options::new().normalize_empty_files(TrivialFileReplacementMode::OneLine);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBF", &options, &mut output);
+        assert_eq!(output, b"\xEF\xBB\xBF\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(1, ChangeType::ReplacedEmptyFileWithOneLine)]
+        );
+
+        // Without the byte order mark, the file becomes empty.
+        let options: Options = Options::new().strip_bom();
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBF", &options, &mut output);
+        assert_eq!(output, b"");
+        assert_eq!(
+            changes,
+            vec![Change::new(1, ChangeType::RemovedByteOrderMark)]
+        );
+
+        // The empty file is then replaced by a single new line marker.
+        let options: Options = options.normalize_empty_files(TrivialFileReplacementMode::OneLine);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\xEF\xBB\xBF", &options, &mut output);
+        assert_eq!(output, b"\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedByteOrderMark),
+                Change::new(1, ChangeType::ReplacedEmptyFileWithOneLine),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_lines() {
+        let input_data: &[u8] = b"a \n  \nb\t\n\t\nc ";
+        for (lines, expected_output, expected_line_numbers) in [
+            (
+                TrailingWhitespaceLines::All,
+                b"a\n\nb\n\nc".as_slice(),
+                vec![1, 2, 3, 4, 5],
+            ),
+            (
+                TrailingWhitespaceLines::BlankOnly,
+                b"a \n\nb\t\n\nc ".as_slice(),
+                vec![2, 4],
+            ),
+            (
+                TrailingWhitespaceLines::ContentOnly,
+                b"a\n  \nb\n\t\nc".as_slice(),
+                vec![1, 3, 5],
+            ),
+        ] {
+            let options: Options = Options::new()
+                .remove_trailing_whitespace()
+                .trailing_whitespace_lines(lines.clone());
+            let mut output = Vec::new();
+            let changes = modify_content(input_data, &options, &mut output);
+            assert_eq!(output, expected_output, "{:?}", lines);
+            let line_numbers: Vec<usize> = changes.iter().map(Change::line_number).collect();
+            assert_eq!(line_numbers, expected_line_numbers, "{:?}", lines);
+            assert!(changes
+                .iter()
+                .all(|change| *change.change_type() == ChangeType::RemovedTrailingWhitespace));
+        }
+
+        // A blank last line without a new line marker.
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .trailing_whitespace_lines(TrailingWhitespaceLines::BlankOnly);
+        let mut output = Vec::new();
+        let changes = modify_content(b"a \n  ", &options, &mut output);
+        assert_eq!(output, b"a \n");
+        assert_eq!(changes.len(), 1);
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\n  ", &options, &mut output);
+        assert_eq!(output, b"a\n");
+        assert_eq!(changes.len(), 1);
+    }
+
+    #[test]
+    fn test_modify_content_markdown_hard_breaks() {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .markdown_hard_breaks();
+        for (input_data, expected_output, expected_columns) in [
+            (b"a \nb".as_slice(), b"a\nb".as_slice(), vec![2]),
+            (b"a  \nb", b"a  \nb", vec![]),
+            (b"a   \nb", b"a  \nb", vec![2]),
+            (b"a    \nb", b"a  \nb", vec![2]),
+            (b"a\t  \r\nb", b"a  \r\nb", vec![2]),
+            (b"a  \t\nb", b"a\nb", vec![2]),
+            // Blank lines are not hard line breaks.
+            (b"a\n  \n    \nb", b"a\n\n\nb", vec![1, 1]),
+            // The last line without a new line marker.
+            (b"a\nb ", b"a\nb", vec![2]),
+            (b"a\nb  ", b"a\nb  ", vec![]),
+            (b"a\nb    ", b"a\nb  ", vec![2]),
+        ] {
+            let mut output = Vec::new();
+            let changes = modify_content(input_data, &options, &mut output);
+            assert_eq!(output, expected_output, "{:?}", input_data);
+            let columns: Vec<usize> = changes
+                .iter()
+                .map(|change| change.column().unwrap())
+                .collect();
+            assert_eq!(columns, expected_columns, "{:?}", input_data);
+        }
+
+        // The spaces are counted in the input, so they are kept even if tabs
+        // in the content are replaced.
+        let options = options.replace_tabs_with_spaces(4);
+        let mut output = Vec::new();
+        modify_content(b"a\tb   \n", &options, &mut output);
+        assert_eq!(output, b"a    b  \n");
+
+        // Without the option, all trailing spaces are removed.
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let mut output = Vec::new();
+        modify_content(b"a  \n", &options, &mut output);
+        assert_eq!(output, b"a\n");
+    }
+
+    #[test]
+    fn test_process_file_content_markdown_hard_breaks_only_in_markdown_files() {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .markdown_hard_breaks();
+        for (file_name, expected_number_of_changes) in [
+            ("README.md", 0),
+            ("notes.MARKDOWN", 0),
+            ("docs/guide.md.gz", 0),
+            ("main.rs", 1),
+            ("md", 1),
+            ("README.md.txt", 1),
+        ] {
+            let result = process_file_content(
+                &PathBuf::from(file_name),
+                b"a  \nb\n",
+                &options,
+                &RunSettings::new(),
+                true,
+            )
+            .unwrap();
+            assert_eq!(
+                result.changes.len(),
+                expected_number_of_changes,
+                "{}",
+                file_name
+            );
+        }
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_1() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello world   ", &options, &mut output);
+        assert_eq!(output, b"hello world");
+        assert_eq!(
+            changes,
+            vec![Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(12)]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_2() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello\r\n\rworld   ", &options, &mut output);
+        assert_eq!(output, b"hello\r\n\rworld");
+        assert_eq!(
+            changes,
+            vec![Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(6)]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_3() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello \t  \r\n \t  \rworld   ", &options, &mut output);
+        assert_eq!(output, b"hello\r\n\rworld");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(6),
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(6)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_4() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello world   \n\n   \n", &options, &mut output);
+        assert_eq!(output, b"hello world\n\n\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(12),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_5() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello world   \x0C  \n\n \x0B \n", &options, &mut output);
+        assert_eq!(output, b"hello world\n\n\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(12),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_preserves_new_line_markers_1() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let mut output = Vec::new();
+        let changes = modify_content(b"a  \r\nb\n", &options, &mut output);
+        assert_eq!(output, b"a\r\nb\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(2)]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_preserves_new_line_markers_2() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let mut output = Vec::new();
+        let changes = modify_content(b"a \rb\t\r\nc  \nd \r", &options, &mut output);
+        assert_eq!(output, b"a\rb\r\nc\nd\r");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(4, ChangeType::RemovedTrailingWhitespace).with_column(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_preserves_new_line_markers_3() {
+        // Lines consisting of whitespace only keep their new line markers too.
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\r\n  \r\n\t\r  \nb  \r\n", &options, &mut output);
+        assert_eq!(output, b"a\r\n\r\n\r\nb\r\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(4, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(5, ChangeType::RemovedTrailingWhitespace).with_column(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_preserves_new_line_markers_4() {
+        // Auto-detected output new line marker is used only for normalization.
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .new_line_marker(OutputNewLineMarkerMode::Linux);
+        let mut output = Vec::new();
+        let changes = modify_content(b"a  \r\nb \r\nc\n", &options, &mut output);
+        assert_eq!(output, b"a\r\nb\r\nc\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_and_normalize_non_standard_whitespace_1() {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .normalize_non_standard_whitespace(NonStandardWhitespaceReplacementMode::Remove);
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello world   \x0C  \n\n \x0B \n", &options, &mut output);
+        assert_eq!(output, b"hello world\n\n\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedNonstandardWhitespace(0x0C)).with_column(15),
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(12),
+                Change::new(3, ChangeType::RemovedNonstandardWhitespace(0x0B)).with_column(2),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_and_normalize_non_standard_whitespace_2() {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .normalize_non_standard_whitespace(
+                NonStandardWhitespaceReplacementMode::ReplaceWithSpace,
+            );
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello world   \x0C  \n\n \x0B \n", &options, &mut output);
+        assert_eq!(output, b"hello world\n\n\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::ReplacedNonstandardWhitespaceBySpace(0x0C))
+                    .with_column(15),
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(12),
+                Change::new(3, ChangeType::ReplacedNonstandardWhitespaceBySpace(0x0B))
+                    .with_column(2),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_remove_trailing_whitespace_and_modify_content_remove_trailing_empty_lines(
+    ) {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines();
+        let mut output = Vec::new();
+        let changes = modify_content(b"hello world   \n\n   \n", &options, &mut output);
+        assert_eq!(output, b"hello world\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(12),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(2, ChangeType::RemovedTrailingEmptyLines),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_normalize_empty_files_empty() {
+        let options: Options =
+            Options::new().normalize_empty_files(TrivialFileReplacementMode::Empty);
+        let mut output = Vec::new();
+        let changes = modify_content(b"", &options, &mut output);
+        assert_eq!(output, b"");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_normalize_empty_files_ignore() {
+        let options: Options =
+            Options::new().normalize_empty_files(TrivialFileReplacementMode::Ignore);
+        let mut output = Vec::new();
+        let changes = modify_content(b"", &options, &mut output);
+        assert_eq!(output, b"");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_normalize_empty_files_one_line() {
+        let options: Options =
+            Options::new().normalize_empty_files(TrivialFileReplacementMode::OneLine);
+        let mut output = Vec::new();
+        let changes = modify_content(b"", &options, &mut output);
+        assert_eq!(output, b"\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(1, ChangeType::ReplacedEmptyFileWithOneLine)]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_normalize_whitespace_only_files_empty() {
+        let options: Options =
+            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::Empty);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\n\t \x0B \x0C \n  ", &options, &mut output);
+        assert_eq!(output, b"");
+        assert_eq!(
+            changes,
+            vec![Change::new(
+                1,
+                ChangeType::ReplacedWhiteSpaceOnlyFileWithEmptyFile
+            )]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_normalize_whitespace_only_files_ignore() {
+        let options: Options =
+            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::Ignore);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\n\t \x0B \x0C \n  ", &options, &mut output);
+        assert_eq!(output, b"\n\t \x0B \x0C \n  ");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_normalize_whitespace_only_files_one_line() {
+        let options: Options =
+            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\n\t \x0B \x0C \n  ", &options, &mut output);
+        assert_eq!(output, b"\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(
+                1,
+                ChangeType::ReplacedWhiteSpaceOnlyFileWithOneLine
+            )]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_normalize_whitespace_only_files_one_line_2() {
+        let options: Options =
+            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\n", &options, &mut output);
+        assert_eq!(output, b"\n");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_normalize_whitespace_only_files_one_line_3() {
+        let options: Options = Options::new()
+            .normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine)
+            .new_line_marker(OutputNewLineMarkerMode::Linux);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\r\n", &options, &mut output);
+        assert_eq!(output, b"\n");
+        assert_eq!(
+            changes,
+            vec![Change::new(
+                1,
+                ChangeType::ReplacedWhiteSpaceOnlyFileWithOneLine
+            )]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_normalize_whitespace_only_files_one_line_4() {
+        let options: Options =
+            Options::new().normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\r\n", &options, &mut output);
+        assert_eq!(output, b"\r\n");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_replace_tabs_with_spaces_ignore() {
+        let options: Options = Options::new().replace_tabs_with_spaces(-47);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\t", &options, &mut output);
+        assert_eq!(output, b"\t");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_replace_tabs_with_spaces_0() {
+        let options: Options = Options::new().replace_tabs_with_spaces(0);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\thello", &options, &mut output);
+        assert_eq!(output, b"hello");
+        assert_eq!(
+            changes,
+            vec![Change::new(1, ChangeType::RemovedTab).with_column(1)]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_replace_tabs_with_spaces_3() {
+        let options: Options = Options::new().replace_tabs_with_spaces(3);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\thello", &options, &mut output);
+        assert_eq!(output, b"   hello");
+        assert_eq!(
+            changes,
+            vec![Change::new(1, ChangeType::ReplacedTabWithSpaces(3)).with_column(1)]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_replace_tabs_with_spaces_reported_count() {
+        for tab_width in 1..=8 {
+            let options: Options = Options::new().replace_tabs_with_spaces(tab_width);
+            let mut output = Vec::new();
+            let changes = modify_content(b"a\tb\n\t\tc\n", &options, &mut output);
+            assert_eq!(changes.len(), 3);
+            for change in changes {
+                assert_eq!(
+                    change.change_type(),
+                    &ChangeType::ReplacedTabWithSpaces(tab_width as usize)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_modify_content_trailing_whitespace_chars_keep_tab() {
+        // A trailing tab is kept, e.g. in Makefiles, but trailing spaces are removed.
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .trailing_whitespace_chars(&[TrailingWhitespaceChar::Space]);
+        let mut output = Vec::new();
+        let changes = modify_content(
+            b"all:\t\nclean:  \n\trm -f *.o \t  \n \t \nlast\t ",
+            &options,
+            &mut output,
+        );
+        assert_eq!(output, b"all:\t\nclean:\n\trm -f *.o \t\n \t\nlast\t");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(7),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(13),
+                Change::new(4, ChangeType::RemovedTrailingWhitespace).with_column(3),
+                Change::new(5, ChangeType::RemovedTrailingWhitespace).with_column(6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_trailing_whitespace_chars_keep_spaces() {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .trailing_whitespace_chars(&[
+                TrailingWhitespaceChar::Tab,
+                TrailingWhitespaceChar::FormFeed,
+            ]);
+        let mut output = Vec::new();
+        let changes = modify_content(b"a \t\r\nb\x0C\x0B\t\n", &options, &mut output);
+        assert_eq!(output, b"a \r\nb\x0C\x0B\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(3),
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_columns() {
+        assert_eq!(count_columns(b""), 0);
+        assert_eq!(count_columns(b"abc"), 3);
+        assert_eq!(count_columns("čaj".as_bytes()), 3);
+        assert_eq!(count_columns("日本".as_bytes()), 2);
+    }
+
+    #[test]
+    fn test_modify_content_expand_tabs_to_stops() {
+        let options: Options = Options::new().expand_tabs_to_stops(4);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\tab\tc\n", &options, &mut output);
+        assert_eq!(output, b"    ab  c\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::ReplacedTabWithSpaces(4)).with_column(1),
+                Change::new(1, ChangeType::ReplacedTabWithSpaces(2)).with_column(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_expand_tabs_to_stops_columns() {
+        // A tab at columns 1, 2, 3, 4 and 5 (one-based) with the width 4.
+        let options: Options = Options::new().expand_tabs_to_stops(4);
+        for (input_data, expected_output) in [
+            (b"\tx".as_slice(), b"    x".as_slice()),
+            (b"a\tx", b"a   x"),
+            (b"ab\tx", b"ab  x"),
+            (b"abc\tx", b"abc x"),
+            (b"abcd\tx", b"abcd    x"),
+        ] {
+            let mut output = Vec::new();
+            modify_content(input_data, &options, &mut output);
+            assert_eq!(output, expected_output);
+        }
+    }
+
+    #[test]
+    fn test_modify_content_expand_tabs_to_stops_mixed() {
+        // Tabs and spaces mixed; the column is reset on every new line marker.
+        let options: Options = Options::new().expand_tabs_to_stops(8);
+        let mut output = Vec::new();
+        let changes = modify_content(b"  \t \tx\r\n\t\ty\rab \t\n", &options, &mut output);
+        assert_eq!(
+            output,
+            b"                x\r\n                y\rab      \n"
+        );
+        assert_eq!(changes.len(), 5);
+    }
+
+    #[test]
+    fn test_modify_content_expand_tabs_to_stops_multibyte() {
+        // Multi-byte UTF-8 characters occupy a single column.
+        let options: Options = Options::new().expand_tabs_to_stops(4);
+        let mut output = Vec::new();
+        modify_content("čaj\tx".as_bytes(), &options, &mut output);
+        assert_eq!(output, "čaj x".as_bytes());
+    }
+
+    #[test]
+    fn test_modify_content_expand_tabs_to_stops_origin() {
+        // Tab stops at columns 6, 10, 14, ... (zero-based) after a 6 column gutter.
+        let options: Options = Options::new().expand_tabs_to_stops(4).tab_stop_origin(6);
+        for (input_data, expected_output) in [
+            (b"\tx".as_slice(), b"      x".as_slice()),
+            (b"12345\tx", b"12345 x"),
+            (b"123456\tx", b"123456    x"),
+            (b"1234567\tx", b"1234567   x"),
+            (b"123456789\tx", b"123456789 x"),
+            (b"123456\ta\tb", b"123456    a   b"),
+        ] {
+            let mut output = Vec::new();
+            modify_content(input_data, &options, &mut output);
+            assert_eq!(
+                output,
+                expected_output,
+                "{}",
+                String::from_utf8_lossy(input_data)
+            );
+        }
+
+        // The column is reset on every line.
+        let mut output = Vec::new();
+        modify_content(b"  1 | \tx\n 10 | \ty\n", &options, &mut output);
+        assert_eq!(output, b"  1 |     x\n 10 |     y\n");
+
+        // The origin 0 is the same as no origin.
+        let options: Options = Options::new().expand_tabs_to_stops(4).tab_stop_origin(0);
+        let mut output = Vec::new();
+        modify_content(b"ab\tx", &options, &mut output);
+        assert_eq!(output, b"ab  x");
+    }
+
+    #[test]
+    fn test_modify_content_normalize_non_standard_whitespace_ignore() {
+        let options: Options = Options::new()
+            .normalize_non_standard_whitespace(NonStandardWhitespaceReplacementMode::Ignore);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\x0B\x0Chello\t ", &options, &mut output);
+        assert_eq!(output, b"\x0B\x0Chello\t ");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_modify_content_normalize_non_standard_whitespace_replace_with_space() {
+        let options: Options = Options::new().normalize_non_standard_whitespace(
+            NonStandardWhitespaceReplacementMode::ReplaceWithSpace,
+        );
+        let mut output = Vec::new();
+        let changes = modify_content(b"\x0B\x0Chello\t ", &options, &mut output);
+        assert_eq!(output, b"  hello\t ");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::ReplacedNonstandardWhitespaceBySpace(0x0B))
+                    .with_column(1),
+                Change::new(1, ChangeType::ReplacedNonstandardWhitespaceBySpace(0x0C))
+                    .with_column(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_normalize_non_standard_whitespace_remove() {
+        let options: Options = Options::new()
+            .normalize_non_standard_whitespace(NonStandardWhitespaceReplacementMode::Remove);
+        let mut output = Vec::new();
+        let changes = modify_content(b"\x0B\x0Chello\t ", &options, &mut output);
+        assert_eq!(output, b"hello\t ");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedNonstandardWhitespace(0x0B)).with_column(1),
+                Change::new(1, ChangeType::RemovedNonstandardWhitespace(0x0C)).with_column(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_normalize_unicode_whitespace_replace_with_space() {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .normalize_unicode_whitespace(UnicodeWhitespaceReplacementMode::ReplaceWithSpace);
+        let mut output = Vec::new();
+        let changes = modify_content(
+            "let x\u{00A0}= 1;\nend\u{3000}\n".as_bytes(),
+            &options,
+            &mut output,
+        );
+        assert_eq!(output, b"let x = 1;\nend\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::ReplacedUnicodeWhitespaceBySpace('\u{00A0}'))
+                    .with_column(6),
+                Change::new(2, ChangeType::ReplacedUnicodeWhitespaceBySpace('\u{3000}'))
+                    .with_column(4),
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modify_content_normalize_unicode_whitespace_remove() {
+        let options: Options =
+            Options::new().normalize_unicode_whitespace(UnicodeWhitespaceReplacementMode::Remove);
+        let mut output = Vec::new();
+        let changes = modify_content(
+            "zero\u{200B}width é\u{2028}".as_bytes(),
+            &options,
+            &mut output,
+        );
+        assert_eq!(output, "zerowidth é\u{2028}".as_bytes());
+        assert_eq!(
+            changes,
+            vec![Change::new(1, ChangeType::RemovedUnicodeWhitespace('\u{200B}')).with_column(5)]
+        );
+
+        // Without the option, Unicode whitespace is left as is.
+        let options: Options = Options::new();
+        let mut output = Vec::new();
+        let changes = modify_content("a\u{00A0}b\u{200B}\n".as_bytes(), &options, &mut output);
+        assert_eq!(output, "a\u{00A0}b\u{200B}\n".as_bytes());
+        assert_eq!(changes, vec![]);
+
+        // Truncated and invalid UTF-8 sequences are left as is.
+        let options: Options =
+            Options::new().normalize_unicode_whitespace(UnicodeWhitespaceReplacementMode::Remove);
+        let mut output = Vec::new();
+        let changes = modify_content(b"a\xC2\n\xE3\x80", &options, &mut output);
+        assert_eq!(output, b"a\xC2\n\xE3\x80");
+        assert_eq!(changes, vec![]);
+    }
+
+    #[test]
+    fn test_process_file() {
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .add_new_line_marker_at_end_of_file()
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .normalize_non_standard_whitespace(NonStandardWhitespaceReplacementMode::Remove)
+            .replace_tabs_with_spaces(4);
+
+        let args = [
+            "src/",
+            ".gitignore",
+            "Cargo.lock",
+            "Cargo.toml",
+            "DEVELOPING.md",
+            "LICENSE",
+            "README.md",
+        ];
+
+        let path_bufs = args.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let files = discover_files(&path_bufs, false, true, None, None, &[])
+            .unwrap()
+            .files;
+
+        for file in &files {
+            let result = process_file(file, &options, &RunSettings::new(), true).unwrap();
+            assert_eq!(
+                result.changes,
+                vec![],
+                "The file `{:?}` is not properly formatted.",
+                file
+            );
+        }
+    }
+
+    #[test]
+    fn test_has_ignore_file_directive() {
+        assert!(has_ignore_file_directive(
+            b"# whitespace-format: ignore-file\nhello  \n"
+        ));
+        assert!(has_ignore_file_directive(
+            b"// whitespace-format: ignore-file"
+        ));
+        assert!(!has_ignore_file_directive(
+            b"hello\n# whitespace-format: ignore-file\n"
+        ));
+        assert!(!has_ignore_file_directive(b"whitespace-format: ignore"));
+        assert!(!has_ignore_file_directive(b""));
+    }
+
+    #[test]
+    fn test_only_trailing_whitespace() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--check-only",
+            "--only=trailing-whitespace",
+            "--new-line-marker=linux",
+            "--normalize-new-line-markers",
+            "--remove-trailing-empty-lines",
+            "--add-new-line-marker-at-end-of-file",
+            "--replace-tabs-with-spaces=2",
+            "--normalize-whitespace-only-files=empty",
+            "file.txt",
+        ]);
+        let options = command_line_arguments.get_options();
+        let result = process_file_content(
+            &PathBuf::from("file.txt"),
+            b"a  \r\n\tb \n\n\nc",
+            &options,
+            &RunSettings::new(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            result.changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_only_multiple_rules() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--only=tabs,new-line-markers",
+            "--new-line-marker=linux",
+            "--remove-trailing-whitespace",
+            "file.txt",
+        ]);
+        let options = command_line_arguments.get_options();
+        let mut output = Vec::new();
+        modify_content(b"\ta \r\nb\x0C\n", &options, &mut output);
+        assert_eq!(output, b"    a \nb\x0C\n");
+
+        // The number of spaces per tab is kept.
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--only=tabs",
+            "--replace-tabs-with-spaces=2",
+            "file.txt",
+        ]);
+        let options = command_line_arguments.get_options();
+        let mut output = Vec::new();
+        modify_content(b"\ta", &options, &mut output);
+        assert_eq!(output, b"  a");
+    }
+
+    #[test]
+    fn test_only_changes_match_output() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--only=trailing-whitespace,tabs",
+            "--new-line-marker=linux",
+            "--normalize-new-line-markers",
+            "--remove-trailing-empty-lines",
+            "--normalize-non-standard-whitespace=remove",
+            "file.txt",
+        ]);
+        let options = command_line_arguments.get_options();
+        let mut output = Vec::new();
+        let changes = modify_content(b"\ta \r\nb\x0C\r\n\r\n", &options, &mut output);
+        assert_eq!(output, b"    a\r\nb\r\n\r\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::ReplacedTabWithSpaces(4)).with_column(1),
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(3),
+                Change::new(2, ChangeType::RemovedTrailingWhitespace).with_column(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_whitespace_only_difference() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        assert!(is_whitespace_only_difference(b"a \nb", b"a\nb", &options));
+        assert!(is_whitespace_only_difference(
+            b"a\t\nb  ",
+            b"a \nb",
+            &options
+        ));
+        assert!(!is_whitespace_only_difference(b"a\nb", b"a\nc", &options));
+        assert!(!is_whitespace_only_difference(b" a\nb", b"a\nb", &options));
+
+        // Without the option, trailing whitespace matters.
+        assert!(!is_whitespace_only_difference(
+            b"a \nb",
+            b"a\nb",
+            &Options::new()
+        ));
+        assert!(is_whitespace_only_difference(
+            b"a \nb",
+            b"a \nb",
+            &Options::new()
+        ));
+
+        // New line markers matter only if they are not normalized.
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers();
+        assert!(is_whitespace_only_difference(
+            b"a\r\nb\r\n",
+            b"a\nb\n",
+            &options
+        ));
+        assert!(!is_whitespace_only_difference(
+            b"a\r\nb\r\n",
+            b"a\nb\n",
+            &Options::new()
+        ));
+    }
+
+    #[test]
+    fn test_options_json_round_trip() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--dump-options-json",
+            "--new-line-marker=windows",
+            "--normalize-new-line-markers",
+            "--remove-trailing-whitespace",
+            "--normalize-whitespace-only-files=one-line",
+            "--normalize-non-standard-whitespace=replace-with-space",
+            "--replace-tabs-with-spaces=2",
+        ]);
+        let options = command_line_arguments.get_options();
+
+        let json = serde_json::to_string_pretty(&options).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["new_line_marker"], "windows");
+        assert_eq!(value["normalize_whitespace_only_files"], "one-line");
+        assert_eq!(
+            value["normalize_non_standard_whitespace"],
+            "replace-with-space"
+        );
+        assert_eq!(value["replace_tabs_with_spaces"], 2);
+        assert_eq!(value["remove_trailing_whitespace"], true);
+        assert_eq!(value["remove_trailing_empty_lines"], false);
+        // The settings that select the files are not formatting options.
+        assert!(value.get("process_binary").is_none());
+        assert!(value.get("content_matches").is_none());
+
+        let deserialized: Options = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, options);
+    }
+
+    #[test]
+    fn test_process_file_cannot_read_file() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let file_path = PathBuf::from("this_file_does_not_exist.txt");
+        assert_eq!(
+            process_file(&file_path, &options, &RunSettings::new(), true).unwrap_err(),
+            Error::CannotReadFile("this_file_does_not_exist.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn test_process_file_compute_hash() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        fs::write(&file_path, b"hello  \r\nworld\t\n\n\n").unwrap();
+
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines();
+        let settings = RunSettings::new().compute_hash();
+
+        // In check-only mode, the hash is computed from the content the file would have.
+        let check_only_hash = process_file(&file_path, &options, &settings, true)
+            .unwrap()
+            .hash;
+
+        let hash = process_file(&file_path, &options, &settings, false)
+            .unwrap()
+            .hash;
+        let formatted = fs::read(&file_path).unwrap();
+        assert_eq!(formatted, b"hello\nworld\n");
+        let expected_hash = Some(format!("{:x}", Sha256::digest(&formatted)));
+        assert_eq!(hash, expected_hash);
+        assert_eq!(check_only_hash, expected_hash);
+
+        // Unchanged file.
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes, vec![]);
+        assert_eq!(result.hash, expected_hash);
+        assert_eq!(
+            result.hash.unwrap(),
+            "4a1e67f2fe1d1cc7b31d0ca2ec441da4778203a036a77da10344c85e24ff0f92"
+        );
+    }
+
+    #[test]
+    fn test_process_file_sizes() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        let content = "hello world \t \n".repeat(100);
+        fs::write(&file_path, &content).unwrap();
+
+        let options: Options = Options::new().remove_trailing_whitespace();
+        for check_only in [true, false] {
+            fs::write(&file_path, &content).unwrap();
+            let result =
+                process_file(&file_path, &options, &RunSettings::new(), check_only).unwrap();
+            assert_eq!(result.input_size, 1500);
+            assert_eq!(result.output_size, 1200);
+        }
+        assert_eq!(fs::read(&file_path).unwrap().len(), 1200);
+
+        // Unchanged file.
+        let result = process_file(&file_path, &options, &RunSettings::new(), false).unwrap();
+        assert_eq!(result.input_size, 1200);
+        assert_eq!(result.output_size, 1200);
+    }
+
+    #[test]
+    fn test_process_file_processing_time() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        fs::write(&file_path, "hello \n").unwrap();
+
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let result = process_file(&file_path, &options, &RunSettings::new(), true).unwrap();
+        assert_eq!(result.processing_time, None);
+
+        let settings = RunSettings {
+            measure_processing_time: true,
+            ..RunSettings::new()
+        };
+        let result = process_file(&file_path, &options, &settings, true).unwrap();
+        assert!(result.processing_time.is_some());
+        let result =
+            process_file_content(&file_path, b"hello \n", &options, &settings, true).unwrap();
+        assert!(result.processing_time.is_some());
+    }
+
+    #[test]
+    fn test_process_file_show_diff() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        let content: &[u8] = b"hello  \r\nworld\n\n";
+        fs::write(&file_path, content).unwrap();
+
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines();
+        let settings = RunSettings::new().show_diff();
+
+        let result = process_file(&file_path, &options, &settings, true).unwrap();
+        assert_eq!(
+            result.diff.unwrap(),
+            format!(
+                "--- a/{}\n+++ b/{}\n@@ -1,3 +1,2 @@\n-hello··\\r\\n\n+hello\\n\n world\\n\n-\\n\n",
+                file_path.display(),
+                file_path.display()
+            )
+        );
+        assert_eq!(fs::read(&file_path).unwrap(), content);
+
+        // Without the option, no diff is computed.
+        let settings = RunSettings {
+            show_diff: false,
+            ..settings
+        };
+        let result = process_file(&file_path, &options, &settings, true).unwrap();
+        assert_eq!(result.changes.len(), 3);
+        assert_eq!(result.diff, None);
+    }
+
+    #[test]
+    fn test_process_file_diff_stat() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        let content: &[u8] = b"hello  \nworld\n\n\n";
+        fs::write(&file_path, content).unwrap();
+
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines();
+        let settings = RunSettings::new().compute_diff_stat();
+
+        let result = process_file(&file_path, &options, &settings, true).unwrap();
+        assert_eq!(
+            result.diff_stat,
+            Some(DiffStat {
+                insertions: 1,
+                deletions: 3
+            })
+        );
+        assert_eq!(fs::read(&file_path).unwrap(), content);
+    }
+
+    #[test]
+    fn test_count_new_line_markers() {
+        assert_eq!(
+            count_new_line_markers(b"a\r\nb\nc\rd\r\n\r"),
+            NewLineMarkerCounts {
+                linux: 1,
+                mac: 2,
+                windows: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_count_new_line_markers_mixed() {
+        let counts = |linux: usize, mac: usize, windows: usize| NewLineMarkerCounts {
+            linux,
+            mac,
+            windows,
+        };
+        assert_eq!(count_new_line_markers(b""), counts(0, 0, 0));
+        assert_eq!(count_new_line_markers(b"hello"), counts(0, 0, 0));
+        assert_eq!(count_new_line_markers(b"\n\r\n\r"), counts(1, 1, 1));
+        assert_eq!(count_new_line_markers(b"\r\r\n\n"), counts(1, 1, 1));
+        assert_eq!(count_new_line_markers(b"\n\r"), counts(1, 1, 0));
+        assert_eq!(count_new_line_markers(b"a\r\n\r\nb\n\r"), counts(1, 1, 2));
+
+        let mut total = count_new_line_markers(b"a\r\nb\n");
+        total += count_new_line_markers(b"c\rd\r\n");
+        assert_eq!(total, counts(1, 1, 2));
+        assert_eq!(
+            total.to_string(),
+            "2 Windows (\\r\\n), 1 Linux (\\n), 1 MacOS (\\r) line ending(s)"
+        );
+    }
+
+    #[test]
+    fn test_count_file_new_line_markers() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        fs::write(&file_path, b"hello\r\nworld\n").unwrap();
+        assert_eq!(
+            count_file_new_line_markers(&file_path),
+            Ok(NewLineMarkerCounts {
+                linux: 1,
+                mac: 0,
+                windows: 1
+            })
+        );
+        assert_eq!(
+            count_file_new_line_markers(&directory.path().join("missing.txt")),
+            Err(Error::CannotReadFile(
+                directory.path().join("missing.txt").display().to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_explain_line_endings_crlf() {
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers();
+        let settings = RunSettings::new().explain_line_endings();
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        fs::write(&file_path, b"hello\r\nworld\r\n").unwrap();
+
+        let result = process_file(&file_path, &options, &settings, true).unwrap();
+        assert_eq!(
+            explain_line_endings(b"hello\r\nworld\r\n", &result.changes, true, false),
+            "2 Windows (\\r\\n), 0 Linux (\\n), 0 MacOS (\\r) line ending(s). \
+             The dominant line ending is \\r\\n. \
+             2 line ending(s) would be normalized to \\n. \
+             Hint: Windows line endings on a non-Windows system are often caused by \
+             the git setting core.autocrlf. Consider running `git config core.autocrlf input`."
+        );
+        assert!(result.line_endings_explanation.is_some());
+
+        // No hint on Windows.
+        assert_eq!(
+            explain_line_endings(b"hello\r\nworld\r\n", &result.changes, false, true),
+            "2 Windows (\\r\\n), 0 Linux (\\n), 0 MacOS (\\r) line ending(s). \
+             The dominant line ending is \\r\\n. \
+             2 line ending(s) normalized to \\n."
+        );
+    }
+
+    #[test]
+    fn test_explain_line_endings_without_normalization() {
+        assert_eq!(
+            explain_line_endings(b"a\nb\rc\n", &[], true, false),
+            "0 Windows (\\r\\n), 2 Linux (\\n), 1 MacOS (\\r) line ending(s). \
+             The dominant line ending is \\n. \
+             The line endings are mixed, but they are not normalized without --normalize-new-line-markers."
+        );
+        assert_eq!(
+            explain_line_endings(b"hello", &[], true, false),
+            "0 Windows (\\r\\n), 0 Linux (\\n), 0 MacOS (\\r) line ending(s). \
+             No normalization is needed."
+        );
+    }
+
+    #[test]
+    fn test_count_lines() {
+        assert_eq!(count_lines(b""), 0);
+        assert_eq!(count_lines(b"hello"), 1);
+        assert_eq!(count_lines(b"hello\n"), 1);
+        assert_eq!(count_lines(b"\n\n"), 2);
+        assert_eq!(count_lines(b"a\r\nb\rc\nd"), 4);
+        assert_eq!(count_lines(b"\r\r\n\r"), 3);
+    }
+
+    #[test]
+    fn test_process_file_number_of_lines() {
+        let directory = tempfile::tempdir().unwrap();
+        let files: Vec<PathBuf> = [
+            ("one.txt", b"hello\n".as_slice()),
+            ("two.txt", b"hello  \r\nworld".as_slice()),
+            ("three.txt", b"a\n\n\n".as_slice()),
+            ("empty.txt", b"".as_slice()),
+            (
+                "ignored.txt",
+                b"whitespace-format: ignore-file\nx\n".as_slice(),
+            ),
+        ]
+        .iter()
+        .map(|(file_name, content)| {
+            let file_path = directory.path().join(file_name);
+            fs::write(&file_path, content).unwrap();
+            file_path
+        })
+        .collect();
+
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines();
+        let total = |settings: &RunSettings| -> usize {
+            files
+                .iter()
+                .map(|file_path| {
+                    process_file(file_path, &options, settings, true)
+                        .unwrap()
+                        .number_of_lines
+                })
+                .sum()
+        };
+        let settings = RunSettings::new().honor_ignore_file_directive();
+        assert_eq!(total(&settings.clone().count_lines()), 6);
+
+        // The lines are not counted unless the statistics need them.
+        assert_eq!(total(&settings), 0);
+    }
+
+    #[test]
+    fn test_process_file_backup() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let settings = RunSettings::new().backup_suffix(".bak");
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        let backup_path = directory.path().join("file.txt.bak");
+        fs::write(&file_path, b"hello  \nworld\n").unwrap();
+
+        // Check-only mode does not write a backup.
+        process_file(&file_path, &options, &settings, true).unwrap();
+        assert!(!backup_path.exists());
+
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(fs::read(&backup_path).unwrap(), b"hello  \nworld\n");
+        assert_eq!(fs::read(&file_path).unwrap(), b"hello\nworld\n");
+
+        // Unchanged files are not backed up, so the existing backup is not a problem.
+        process_file(&file_path, &options, &settings, false).unwrap();
+
+        // An existing backup file is never overwritten.
+        fs::write(&file_path, b"again \n").unwrap();
+        assert_eq!(
+            process_file(&file_path, &options, &settings, false).unwrap_err(),
+            Error::BackupFileExists(backup_path.display().to_string())
+        );
+        assert_eq!(fs::read(&backup_path).unwrap(), b"hello  \nworld\n");
+        assert_eq!(fs::read(&file_path).unwrap(), b"again \n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_file_preserves_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("script.sh");
+        fs::write(&file_path, b"#!/bin/sh  \necho hello\n").unwrap();
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o754)).unwrap();
+
+        let result = process_file(&file_path, &options, &RunSettings::new(), false).unwrap();
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(fs::read(&file_path).unwrap(), b"#!/bin/sh\necho hello\n");
+        assert_eq!(
+            fs::metadata(&file_path).unwrap().permissions().mode() & 0o777,
+            0o754
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_file_pipe_through() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+
+        // The identity command does not change the formatted content.
+        fs::write(&file_path, b"hello  \nworld\n").unwrap();
+        let options: Options = Options::new().remove_trailing_whitespace();
+        process_file(&file_path, &options, &RunSettings::new(), false).unwrap();
+        let expected_output = fs::read(&file_path).unwrap();
+        fs::write(&file_path, b"hello  \nworld\n").unwrap();
+        let settings = RunSettings::new().pipe_through("cat");
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(fs::read(&file_path).unwrap(), expected_output);
+
+        // The command transforms the formatted content.
+        fs::write(&file_path, b"hello  \nworld\n").unwrap();
+        let settings = settings.pipe_through("tr a-z A-Z");
+        process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(fs::read(&file_path).unwrap(), b"HELLO\nWORLD\n");
+
+        // Nothing is written in check-only mode.
+        fs::write(&file_path, b"hello  \n").unwrap();
+        process_file(&file_path, &options, &settings, true).unwrap();
+        assert_eq!(fs::read(&file_path).unwrap(), b"hello  \n");
+
+        // The command is not run for a file that is already formatted.
+        let clean_file_path = directory.path().join("clean.txt");
+        let marker_path = directory.path().join("marker");
+        fs::write(&clean_file_path, b"hello\n").unwrap();
+        let marker_settings = RunSettings::new()
+            .pipe_through(&format!("touch '{}'; tr a-z A-Z", marker_path.display()));
+        let result = process_file(&clean_file_path, &options, &marker_settings, false).unwrap();
+        assert!(result.changes.is_empty());
+        assert_eq!(fs::read(&clean_file_path).unwrap(), b"hello\n");
+        assert!(!marker_path.exists());
+
+        // The file is not written if the command fails.
+        let settings = settings.pipe_through("cat > /dev/null; exit 3");
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(fs::read(&file_path).unwrap(), b"hello  \n");
+    }
+
+    #[test]
+    fn test_process_file_output_suffix() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let settings = RunSettings::new().output_suffix(".clean");
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        fs::write(&file_path, b"hello  \nworld\t\n").unwrap();
+
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes.len(), 2);
+        assert_eq!(fs::read(&file_path).unwrap(), b"hello  \nworld\t\n");
+        assert_eq!(
+            fs::read(directory.path().join("file.txt.clean")).unwrap(),
+            b"hello\nworld\n"
+        );
+
+        // Nothing is written for a file that does not need formatting.
+        let clean_file_path = directory.path().join("clean.txt");
+        fs::write(&clean_file_path, b"hello\n").unwrap();
+        let result = process_file(&clean_file_path, &options, &settings, false).unwrap();
+        assert!(result.changes.is_empty());
+        assert!(!directory.path().join("clean.txt.clean").exists());
+    }
+
+    #[test]
+    fn test_path_in_output_dir() {
+        let output_dir = Path::new("out");
+        assert_eq!(
+            path_in_output_dir(Path::new("src/main.rs"), output_dir),
+            PathBuf::from("out/src/main.rs")
+        );
+        assert_eq!(
+            path_in_output_dir(Path::new("./src/./main.rs"), output_dir),
+            PathBuf::from("out/src/main.rs")
+        );
+        assert_eq!(
+            path_in_output_dir(Path::new("/home/user/file.txt"), output_dir),
+            PathBuf::from("out/home/user/file.txt")
+        );
+        assert_eq!(
+            path_in_output_dir(Path::new("src/../docs/file.txt"), output_dir),
+            PathBuf::from("out/docs/file.txt")
+        );
+        assert_eq!(
+            path_in_output_dir(Path::new("../../file.txt"), output_dir),
+            PathBuf::from("out/file.txt")
+        );
+    }
+
+    #[test]
+    fn test_process_file_output_dir() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let output_dir = root.join("out");
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let settings = RunSettings::new().output_dir(&output_dir).verify_writes();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        let dirty_file_path = root.join("src/nested/dirty.txt");
+        let clean_file_path = root.join("src/clean.txt");
+        let binary_file_path = root.join("src/binary.bin");
+        fs::write(&dirty_file_path, b"hello  \nworld\t\n").unwrap();
+        fs::write(&clean_file_path, b"hello\n").unwrap();
+        fs::write(&binary_file_path, b"\x00 \n").unwrap();
+
+        let result = process_file(&dirty_file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes.len(), 2);
+        let result = process_file(&clean_file_path, &options, &settings, false).unwrap();
+        assert!(result.changes.is_empty());
+        let result = process_file(&binary_file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.skipped, Some(SkipReason::BinaryFile));
+
+        // The output directory mirrors all the files, and the originals are unchanged.
+        let mirrored_path = |file_path: &Path| path_in_output_dir(file_path, &output_dir);
+        assert_eq!(
+            fs::read(mirrored_path(&dirty_file_path)).unwrap(),
+            b"hello\nworld\n"
+        );
+        assert_eq!(
+            fs::read(mirrored_path(&clean_file_path)).unwrap(),
+            b"hello\n"
+        );
+        assert_eq!(
+            fs::read(mirrored_path(&binary_file_path)).unwrap(),
+            b"\x00 \n"
+        );
+        assert_eq!(fs::read(&dirty_file_path).unwrap(), b"hello  \nworld\t\n");
+
+        // Nothing is written in check-only mode.
+        let other_output_dir = root.join("other");
+        let settings = settings.output_dir(&other_output_dir);
+        process_file(&dirty_file_path, &options, &settings, true).unwrap();
+        assert!(!other_output_dir.exists());
+    }
+
+    #[test]
+    fn test_process_file_preserve_mtime() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        let modified =
+            std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+        let set_modified = || {
+            fs::write(&file_path, b"hello  \n").unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&file_path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+
+        set_modified();
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let settings = RunSettings::new().preserve_mtime();
+        process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(fs::read(&file_path).unwrap(), b"hello\n");
+        assert_eq!(
+            fs::metadata(&file_path).unwrap().modified().unwrap(),
+            modified
+        );
+
+        // Without the option, the modification time is updated.
+        set_modified();
+        process_file(&file_path, &options, &RunSettings::new(), false).unwrap();
+        assert_ne!(
+            fs::metadata(&file_path).unwrap().modified().unwrap(),
+            modified
+        );
+    }
+
+    #[test]
+    fn test_process_file_content_matches() {
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let settings = RunSettings::new().content_matches("TODO|FIXME");
+        let directory = tempfile::tempdir().unwrap();
+        let mut results: Vec<(&str, Option<SkipReason>)> = Vec::new();
+        for (file_name, content) in [
+            ("todo.txt", "x = 1  \n# TODO: remove\n"),
+            ("fixme.txt", "# FIXME \n"),
+            ("other.txt", "x = 2  \n# todo\n"),
+        ] {
+            let file_path = directory.path().join(file_name);
+            fs::write(&file_path, content).unwrap();
+            let result = process_file(&file_path, &options, &settings, false).unwrap();
+            results.push((file_name, result.skipped));
+        }
+        assert_eq!(
+            results,
+            vec![
+                ("todo.txt", None),
+                ("fixme.txt", None),
+                ("other.txt", Some(SkipReason::ContentDoesNotMatch)),
+            ]
+        );
+        assert_eq!(
+            fs::read(directory.path().join("todo.txt")).unwrap(),
+            b"x = 1\n# TODO: remove\n"
+        );
+        assert_eq!(
+            fs::read(directory.path().join("other.txt")).unwrap(),
+            b"x = 2  \n# todo\n"
+        );
+    }
+
+    #[test]
+    fn test_is_binary() {
+        assert!(!is_binary(b""));
+        assert!(!is_binary(b"hello\r\nworld\n"));
+        assert!(is_binary(b"\x89PNG\r\n\x1A\n\x00\x00\x00\rIHDR"));
+
+        // Only the beginning of the file is searched.
+        let mut data = vec![b' '; BINARY_DETECTION_LENGTH];
+        data.push(0);
+        assert!(!is_binary(&data));
+        data.insert(0, 0);
+        assert!(is_binary(&data));
+    }
+
+    #[test]
+    fn test_process_file_binary() {
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines();
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("image.png");
+        let content: &[u8] = b"\x89PNG\r\n\x1A\n\x00\x00\x00\rIHDR  \r\n\n\n";
+        fs::write(&file_path, content).unwrap();
+
+        let result = process_file(&file_path, &options, &RunSettings::new(), false).unwrap();
+        assert_eq!(
+            result,
+            FormatResult {
+                changes: vec![],
+                skipped: Some(SkipReason::BinaryFile),
+                diff: None,
+                number_of_lines: 0,
+                hash: None,
+                diff_stat: None,
+                line_endings_explanation: None,
+                input_size: content.len(),
+                output_size: content.len(),
+                processing_time: None,
+            }
+        );
+        assert_eq!(fs::read(&file_path).unwrap(), content);
+
+        // With the option, binary files are formatted like any other file.
+        let settings = RunSettings::new().process_binary();
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.skipped, None);
+        assert_eq!(
+            fs::read(&file_path).unwrap(),
+            b"\x89PNG\n\x1A\n\x00\x00\x00\nIHDR\n"
+        );
+    }
+
+    #[test]
+    fn test_process_file_utf16() {
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .normalize_whitespace_only_files(TrivialFileReplacementMode::Empty);
+
+        let directory = tempfile::tempdir().unwrap();
+        for (file_name, content) in [
+            // "a \r\n" encoded as UTF-16 LE.
+            (
+                "little_endian.txt",
+                b"\xFF\xFEa\x00 \x00\r\x00\n\x00".as_slice(),
+            ),
+            // "  \n\n" encoded as UTF-16 BE.
+            (
+                "big_endian.txt",
+                b"\xFE\xFF\x00 \x00 \x00\n\x00\n".as_slice(),
+            ),
+        ] {
+            let file_path = directory.path().join(file_name);
+            fs::write(&file_path, content).unwrap();
+            let result = process_file(&file_path, &options, &RunSettings::new(), false).unwrap();
+            assert_eq!(
+                result,
+                FormatResult {
+                    changes: vec![],
+                    skipped: Some(SkipReason::Utf16Encoding),
+                    diff: None,
+                    number_of_lines: 0,
+                    hash: None,
+                    diff_stat: None,
+                    line_endings_explanation: None,
+                    input_size: content.len(),
+                    output_size: content.len(),
+                    processing_time: None,
+                }
+            );
+            assert_eq!(fs::read(&file_path).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_process_file_ignore_file_directive() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("generated.txt");
+        let content: &[u8] = b"# whitespace-format: ignore-file  \r\nhello\t \n\n\n";
+        fs::write(&file_path, content).unwrap();
+
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .replace_tabs_with_spaces(4);
+        let settings = RunSettings::new().honor_ignore_file_directive();
+
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(
+            result,
+            FormatResult {
+                changes: vec![],
+                skipped: Some(SkipReason::IgnoreFileDirective),
+                diff: None,
+                number_of_lines: 0,
+                hash: None,
+                diff_stat: None,
+                line_endings_explanation: None,
+                input_size: content.len(),
+                output_size: content.len(),
+                processing_time: None,
+            }
+        );
+        assert_eq!(fs::read(&file_path).unwrap(), content);
+
+        // Without the option, the directive has no effect.
+        let result = process_file(&file_path, &options, &RunSettings::new(), false).unwrap();
+        assert_eq!(result.skipped, None);
+        assert_eq!(result.changes.len(), 5);
+        assert_eq!(
+            fs::read(&file_path).unwrap(),
+            b"# whitespace-format: ignore-file\nhello\n"
+        );
+    }
+
+    /// Writer that keeps the output and counts the bytes written to it.
+    #[derive(Default)]
+    struct RecordingWriter {
+        output: Vec<u8>,
+        number_of_bytes_written: usize,
+    }
+
+    impl Writer for RecordingWriter {
+        fn write(&mut self, byte: u8) {
+            self.number_of_bytes_written += 1;
+            self.output.write(byte);
+        }
+
+        fn write_bytes(&mut self, bytes: &[u8]) {
+            self.number_of_bytes_written += bytes.len();
+            self.output.write_bytes(bytes);
+        }
+
+        fn rewind(&mut self, previous_position: usize) {
+            self.output.rewind(previous_position);
+        }
+
+        fn position(&self) -> usize {
+            self.output.position()
+        }
+    }
+
+    #[test]
+    fn test_modify_content_hashing_writer() {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines();
+        let input_data = b"hello  \r\nworld\t\n\n\n";
+        let mut expected_output: Vec<u8> = Vec::new();
+        let expected_changes = modify_content(input_data, &options, &mut expected_output);
+
+        let mut writer = HashingWriter::new();
+        let changes = modify_content(input_data, &options, &mut writer);
+        assert_eq!(changes, expected_changes);
+        assert_eq!(writer.finish(), sha256(&expected_output));
+    }
+
+    #[test]
+    fn test_process_file_single_pass_identical_output() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .replace_tabs_with_spaces(8);
+
+        for input_data in [
+            b"".as_slice(),
+            b"hello\n".as_slice(),
+            b"hello  \r\nworld\t\n\n\n".as_slice(),
+            // The formatted content is longer than the original content.
+            b"\t\t\ta\n\t\t\tb\r\n".as_slice(),
+            b"  \n\t\n\r\n".as_slice(),
+        ] {
+            let mut expected_output: Vec<u8> = Vec::new();
+            let expected_changes = modify_content(input_data, &options, &mut expected_output);
+
+            fs::write(&file_path, input_data).unwrap();
+            let check_only_result =
+                process_file(&file_path, &options, &RunSettings::new(), true).unwrap();
+            let result = process_file(&file_path, &options, &RunSettings::new(), false).unwrap();
+
+            assert_eq!(check_only_result.changes, expected_changes);
+            assert_eq!(result.changes, expected_changes);
+            assert_eq!(fs::read(&file_path).unwrap(), expected_output);
+        }
+    }
+
+    #[test]
+    fn test_process_file_streaming_write() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        let input_data: &[u8] = b"hello  \r\n\tworld\t\r\n\r\n\r\n";
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .replace_tabs_with_spaces(2);
+        let mut expected_output: Vec<u8> = Vec::new();
+        let expected_changes = modify_content(input_data, &options, &mut expected_output);
+
+        // Every file is at least as large as the threshold.
+        let settings = RunSettings::new().streaming_write_threshold(0);
+        fs::write(&file_path, input_data).unwrap();
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes, expected_changes);
+        assert_eq!(fs::read(&file_path).unwrap(), expected_output);
+        assert_eq!(fs::read(&file_path).unwrap(), b"hello\n  world\n");
+
+        // A file that does not need to change is not written.
+        let modified = fs::metadata(&file_path).unwrap().modified().unwrap();
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes, vec![]);
+        assert_eq!(
+            fs::metadata(&file_path).unwrap().modified().unwrap(),
+            modified
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_file_streaming_write_links() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("real.txt");
+        let symlink_path = directory.path().join("symlink.txt");
+        let hard_link_path = directory.path().join("hard_link.txt");
+        fs::write(&file_path, "hello  \n").unwrap();
+        std::os::unix::fs::symlink(&file_path, &symlink_path).unwrap();
+        fs::hard_link(&file_path, &hard_link_path).unwrap();
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let settings = RunSettings::new().streaming_write_threshold(0);
+
+        // The target of the symbolic link is formatted, and the link stays a link.
+        let result = process_file(&symlink_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes.len(), 1);
+        assert!(fs::symlink_metadata(&symlink_path)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read(&file_path).unwrap(), b"hello\n");
+        assert_eq!(fs::read(&hard_link_path).unwrap(), b"hello\n");
+
+        // The hard link stays linked to the same file.
+        fs::write(&hard_link_path, "world  \n").unwrap();
+        let result = process_file(&hard_link_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(fs::read(&file_path).unwrap(), b"world\n");
+        assert_eq!(fs::read(&symlink_path).unwrap(), b"world\n");
+    }
+
+    #[test]
+    fn test_process_file_memory_map() {
+        let directory = tempfile::tempdir().unwrap();
+        let options: Options = Options::new()
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .replace_tabs_with_spaces(4);
+        let memory_mapped_settings = RunSettings {
+            memory_map: true,
+            ..RunSettings::new()
+        };
+
+        for (file_name, content) in [
+            ("empty.txt", b"".as_slice()),
+            ("clean.txt", b"hello\n".as_slice()),
+            ("dirty.txt", b"hello  \r\n\tworld\n\n\n".as_slice()),
+        ] {
+            let file_path = directory.path().join(file_name);
+            fs::write(&file_path, content).unwrap();
+            assert!(matches!(
+                read_file(&file_path, true).unwrap(),
+                FileData::Mapped(_)
+            ));
+            assert_eq!(&*read_file(&file_path, true).unwrap(), content);
+            assert_eq!(
+                process_file(&file_path, &options, &memory_mapped_settings, true),
+                process_file(&file_path, &options, &RunSettings::new(), true)
+            );
+        }
+    }
+
+    #[test]
+    fn test_process_file_content_assert_idempotent() {
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Windows)
+            .normalize_new_line_markers()
+            .add_new_line_marker_at_end_of_file()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .normalize_whitespace_only_files(TrivialFileReplacementMode::Empty)
+            .replace_tabs_with_spaces(2);
+        let settings = RunSettings::new().assert_idempotent();
+
+        // Trailing whitespace, mixed new line markers and empty lines interact.
+        let input_data: &[u8] = b"\r\n \t\r\rhello \t\r\r\n\n  \n\x0C\r";
+        let result = process_file_content(
+            &PathBuf::from("file.txt"),
+            input_data,
+            &options,
+            &settings,
+            true,
+        )
+        .unwrap();
+        assert!(!result.changes.is_empty());
+
+        // The second pass would add the custom new line marker once more.
+        let options = options.new_line_marker(OutputNewLineMarkerMode::Custom(vec![0x1E]));
+        assert_eq!(
+            process_file_content(
+                &PathBuf::from("file.txt"),
+                b"hello",
+                &options,
+                &settings,
+                true
+            ),
+            Err(Error::NotIdempotent("file.txt".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_process_file_gzip() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt.gz");
+        let compressed_data = gzip_compress(b"hello \nworld\t\n").unwrap();
+        let options: Options = Options::new().remove_trailing_whitespace();
+        let settings = RunSettings::new().handle_gzip().verify_writes();
+
+        // Without --handle-gzip, the compressed file looks binary.
+        fs::write(&file_path, &compressed_data).unwrap();
+        let result = process_file(&file_path, &options, &RunSettings::new(), false).unwrap();
+        assert_eq!(result.skipped, Some(SkipReason::BinaryFile));
+
+        // Check-only mode checks the decompressed content and does not write the file.
+        let result = process_file(&file_path, &options, &settings, true).unwrap();
+        assert_eq!(result.skipped, None);
+        assert_eq!(result.changes.len(), 2);
+        assert_eq!(result.input_size, 14);
+        assert_eq!(fs::read(&file_path).unwrap(), compressed_data);
+
+        // The file is written compressed.
+        let settings = settings.backup_suffix(".bak");
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert_eq!(result.changes.len(), 2);
+        let written_data = fs::read(&file_path).unwrap();
+        assert!(is_gzip(&written_data));
+        assert_eq!(gzip_decompress(&written_data).unwrap(), b"hello\nworld\n");
+        assert_eq!(
+            fs::read(path_with_suffix(&file_path, ".bak")).unwrap(),
+            compressed_data
+        );
+
+        // A formatted file is not written again.
+        let result = process_file(&file_path, &options, &settings, false).unwrap();
+        assert!(result.changes.is_empty());
+        assert_eq!(fs::read(&file_path).unwrap(), written_data);
+
+        // The explanation of the line endings describes what has been done.
+        fs::write(&file_path, gzip_compress(b"hello\r\n").unwrap()).unwrap();
+        let options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers();
+        let explaining_settings = RunSettings::new().handle_gzip().explain_line_endings();
+        let result = process_file(&file_path, &options, &explaining_settings, false).unwrap();
+        let explanation = result.line_endings_explanation.unwrap();
+        assert!(
+            explanation.contains(" 1 line ending(s) normalized to \\n."),
+            "{}",
+            explanation
+        );
+        assert_eq!(
+            gzip_decompress(&fs::read(&file_path).unwrap()).unwrap(),
+            b"hello\n"
+        );
+
+        // Invalid compressed data cannot be read.
+        fs::write(&file_path, b"\x1F\x8Bhello").unwrap();
+        assert_eq!(
+            process_file(&file_path, &options, &settings, true),
+            Err(Error::CannotReadFile(file_path.display().to_string()))
+        );
+    }
+
+    #[test]
+    fn test_process_file_verify_writes() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("file.txt");
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers()
+            .add_new_line_marker_at_end_of_file()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .replace_tabs_with_spaces(4);
+        let settings = RunSettings::new().verify_writes();
+
+        // The written file reads back formatted, also when it is streamed.
+        let streaming_settings = settings.clone().streaming_write_threshold(0);
+        for settings in [&settings, &streaming_settings] {
+            fs::write(&file_path, b"\thello \r\nworld\n\n\n").unwrap();
+            let result = process_file(&file_path, &options, settings, false).unwrap();
+            assert_eq!(result.changes.len(), 4);
+            assert_eq!(fs::read(&file_path).unwrap(), b"    hello\nworld\n");
+        }
+
+        // The second pass would add the custom new line marker once more,
+        // so the file read back from the disk is not formatted.
+        fs::write(&file_path, b"hello").unwrap();
+        let options = options.new_line_marker(OutputNewLineMarkerMode::Custom(vec![0x1E]));
+        assert_eq!(
+            process_file(&file_path, &options, &settings, false),
+            Err(Error::WriteNotVerified(file_path.display().to_string()))
+        );
+
+        // A file that is modified on the disk after it was written is not formatted.
+        fs::write(&file_path, b"hello \n").unwrap();
+        assert_eq!(
+            verify_written_file(
+                &file_path,
+                &Options::new().remove_trailing_whitespace(),
+                &settings
+            ),
+            Err(Error::WriteNotVerified(file_path.display().to_string()))
+        );
+    }
+
+    #[test]
+    fn test_format_content_single_pass() {
+        let options: Options = Options::new()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .replace_tabs_with_spaces(2);
+        let input_data: &[u8] = b"a  \n\tb\n\n\n";
+
+        // Bytes written by exactly one formatting pass.
+        let mut one_pass = RecordingWriter::default();
+        let expected_changes = modify_content(input_data, &options, &mut one_pass);
+        assert!(one_pass.number_of_bytes_written > 0);
+
+        // When the output is needed, the content is formatted only once.
+        let mut output_writer = RecordingWriter::default();
+        let (changes, output_size) = format_content(input_data, &options, true, &mut output_writer);
+        assert_eq!(changes, expected_changes);
+        assert_eq!(output_size, 6);
+        assert_eq!(output_writer.output, b"a\n  b\n");
+        assert_eq!(
+            output_writer.number_of_bytes_written,
+            one_pass.number_of_bytes_written
+        );
+
+        // When the output is not needed, nothing is written to the output writer.
+        let mut output_writer = RecordingWriter::default();
+        let (changes, output_size) =
+            format_content(input_data, &options, false, &mut output_writer);
+        assert_eq!(changes, expected_changes);
+        assert_eq!(output_size, 6);
+        assert_eq!(output_writer.number_of_bytes_written, 0);
+    }
+
+    #[test]
+    fn test_format_reader() {
+        let options: Options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .normalize_new_line_markers()
+            .remove_trailing_whitespace()
+            .remove_trailing_empty_lines()
+            .replace_tabs_with_spaces(4);
+        let input_data: &[u8] = b"fn main() {  \r\n\tprintln!(\"hello\");\r\n}\r\n\r\n  \r\n";
+        let mut output: Vec<u8> = Vec::new();
+        let changes = format_reader(io::Cursor::new(input_data), &mut output, &options).unwrap();
+        assert_eq!(output, b"fn main() {\n    println!(\"hello\");\n}\n");
+
+        // The result is the same as with an in-memory writer.
+        let mut expected_output: Vec<u8> = Vec::new();
+        assert_eq!(
+            changes,
+            modify_content(input_data, &options, &mut expected_output)
+        );
+        assert_eq!(output, expected_output);
+        assert_eq!(changes.len(), 9);
+        assert_eq!(
+            changes[0],
+            Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(12)
+        );
+        assert_eq!(
+            changes[8],
+            Change::new(4, ChangeType::RemovedTrailingEmptyLines)
+        );
+    }
+
+    #[test]
+    fn test_format_reader_in_blocks() {
+        let inputs: [&[u8]; 12] = [
+            b"",
+            b"  \n\t\n",
+            b"hello",
+            b"hello  \r\n\r\n  \r\n",
+            b"a\n\n  \nb  \n\n\n",
+            b"\xEF\xBB\xBFa \r\n\xEF\xBB\xBFb\r\n\r",
+            b"a\rb \r\r\nc\t\n\n",
+            b"a\xE2\x80\xA8b \xE2\x80\xA9\nc\n",
+            b"\n\n a\n\tb\n c  \n\n \n",
+            b"x\n\x0Cy \n\xC2\xA0\nz",
+            "при  \n\u{00A0}\n世界\t\n\u{2028}\n\n".as_bytes(),
+            "\u{3000}\n\u{FEFF}мир\r\n\u{200B}\r\n".as_bytes(),
+        ];
+        let options = [
+            Options::new()
+                .new_line_marker(OutputNewLineMarkerMode::Linux)
+                .normalize_new_line_markers()
+                .remove_trailing_whitespace()
+                .remove_trailing_empty_lines()
+                .replace_tabs_with_spaces(4)
+                .strip_bom()
+                .normalize_unicode_line_separators(),
+            Options::new()
+                .new_line_marker(OutputNewLineMarkerMode::Windows)
+                .remove_new_line_marker_from_end_of_file()
+                .normalize_whitespace_only_files(TrivialFileReplacementMode::Empty)
+                .add_bom(),
+            Options::new()
+                .new_line_marker(OutputNewLineMarkerMode::Mac)
+                .add_new_line_marker_at_end_of_file()
+                .normalize_empty_files(TrivialFileReplacementMode::OneLine)
+                .normalize_whitespace_only_files(TrivialFileReplacementMode::OneLine)
+                .max_trailing_blank_lines(1)
+                .blank_lines_include_whitespace(),
+            Options::new()
+                .new_line_marker(OutputNewLineMarkerMode::Linux)
+                .ensure_trailing_newlines(2)
+                .remove_trailing_whitespace(),
+            Options::new()
+                .remove_trailing_whitespace()
+                .remove_trailing_empty_lines(),
+        ];
+
+        // The result does not depend on how the input is split into blocks.
+        for input_data in inputs {
+            for options in &options {
+                let mut expected_output: Vec<u8> = Vec::new();
+                let expected_changes = modify_content(input_data, options, &mut expected_output);
+                for block_size in 1..=8 {
+                    let mut output: Vec<u8> = Vec::new();
+                    let changes = format_reader_in_blocks(
+                        io::Cursor::new(input_data),
+                        &mut output,
+                        options,
+                        block_size,
+                    )
+                    .unwrap();
+                    assert_eq!(output, expected_output, "{:?}", input_data);
+                    assert_eq!(changes, expected_changes, "{:?}", input_data);
+                }
+            }
+        }
+    }
+
+    /// Reader that fails after reading all of its data, instead of reporting the end.
+    struct FailingReader<'a>(&'a [u8]);
+
+    impl io::Read for FailingReader<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::other("read failed"));
+            }
+            let length = self.0.len().min(buffer.len());
+            buffer[..length].copy_from_slice(&self.0[..length]);
+            self.0 = &self.0[length..];
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn test_format_reader_in_blocks_streams_non_ascii_input() {
+        let input_data = "привет  \n世界\t\n".repeat(100);
+        let options = Options::new()
+            .new_line_marker(OutputNewLineMarkerMode::Linux)
+            .remove_trailing_whitespace();
+
+        // The lines read before the failure have already been formatted and written.
+        let mut output: Vec<u8> = Vec::new();
+        let result = format_reader_in_blocks(
+            FailingReader(input_data.as_bytes()),
+            &mut output,
+            &options,
+            64,
+        );
+        assert!(result.is_err());
+        assert!(output.starts_with("привет\n世界\n".repeat(90).as_bytes()));
+    }
+
+    #[test]
+    fn test_find_end_of_formattable_part() {
+        assert_eq!(find_end_of_formattable_part(b""), None);
+        assert_eq!(find_end_of_formattable_part(b"a\n"), None);
+        assert_eq!(find_end_of_formattable_part(b"  \n\nb"), None);
+        assert_eq!(find_end_of_formattable_part(b"a\n\n  b\n\n"), Some(3));
+        assert_eq!(find_end_of_formattable_part(b"a\r\nb"), Some(3));
+        assert_eq!(find_end_of_formattable_part(b"a\rb"), Some(2));
+        assert_eq!(find_end_of_formattable_part(b"a\n\xEF\xBB\xBFb"), None);
+        // Non-ASCII characters are visible, unless they are whitespace.
+        assert_eq!(
+            find_end_of_formattable_part("мир\n世界\n".as_bytes()),
+            Some(7)
+        );
+        assert_eq!(
+            find_end_of_formattable_part("мир\n\u{00A0}\n".as_bytes()),
+            None
+        );
+        assert_eq!(
+            find_end_of_formattable_part("мир\n\u{2028}\n".as_bytes()),
+            None
+        );
+        assert_eq!(find_end_of_formattable_part(b"a\n\xE4\xB8"), None);
+    }
 }
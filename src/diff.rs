@@ -0,0 +1,408 @@
+// Library imports
+use std::path::Path;
+
+// Internal imports
+use crate::core::LINE_SEPARATOR;
+use crate::core::PARAGRAPH_SEPARATOR;
+
+/// Number of unchanged lines shown before and after each changed block of lines.
+const CONTEXT_LINES: usize = 3;
+
+/// Maximum width of the bar of `+` and `-` characters in the diff-stat summary.
+const STAT_BAR_WIDTH: usize = 50;
+
+/// Number of lines inserted and deleted by the formatting of a file.
+#[derive(PartialEq, Debug, Default, Clone, Copy)]
+pub struct DiffStat {
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Splits a buffer into lines. Each line includes its new line marker
+/// (`\n`, `\r` or `\r\n`), except possibly the last line. Unicode line separators
+/// U+2028 and U+2029 end lines too, so that the lines stay aligned when
+/// the separators are replaced by new line markers.
+fn split_lines(data: &[u8]) -> Vec<&[u8]> {
+    let mut lines: Vec<&[u8]> = Vec::new();
+    let mut start: usize = 0;
+    let mut i: usize = 0;
+    while i < data.len() {
+        if data[i] == b'\n' || data[i] == b'\r' {
+            if data[i] == b'\r' && i + 1 < data.len() && data[i + 1] == b'\n' {
+                i += 1;
+            }
+            lines.push(&data[start..=i]);
+            start = i + 1;
+        } else if data[i..].starts_with(LINE_SEPARATOR)
+            || data[i..].starts_with(PARAGRAPH_SEPARATOR)
+        {
+            i += 2;
+            lines.push(&data[start..=i]);
+            start = i + 1;
+        }
+        i += 1;
+    }
+    if start < data.len() {
+        lines.push(&data[start..]);
+    }
+    lines
+}
+
+/// Renders a line so that whitespace changes are visible:
+/// trailing spaces are shown as `·`, tabs as `→`, and
+/// new line markers and other control characters as escape sequences.
+fn render_line(line: &[u8]) -> String {
+    for (separator, escape_sequence) in [
+        (LINE_SEPARATOR, "\\u2028"),
+        (PARAGRAPH_SEPARATOR, "\\u2029"),
+    ] {
+        if let Some(line_without_separator) = line.strip_suffix(separator) {
+            return render_line(line_without_separator) + escape_sequence;
+        }
+    }
+
+    let content_length = line
+        .iter()
+        .rposition(|char| *char != b'\n' && *char != b'\r')
+        .map_or(0, |position| position + 1);
+    let trailing_whitespace_start = line[..content_length]
+        .iter()
+        .rposition(|char| !char.is_ascii_whitespace() && *char != 0x0B)
+        .map_or(0, |position| position + 1);
+
+    let mut rendered = String::new();
+    let text = String::from_utf8_lossy(&line[..trailing_whitespace_start]);
+    rendered.push_str(&text.replace('\t', "→"));
+    for char in &line[trailing_whitespace_start..] {
+        match char {
+            b' ' => rendered.push('·'),
+            b'\t' => rendered.push('→'),
+            b'\n' => rendered.push_str("\\n"),
+            b'\r' => rendered.push_str("\\r"),
+            0x0B => rendered.push_str("\\v"),
+            0x0C => rendered.push_str("\\f"),
+            _ => rendered.push(char::from(*char)),
+        }
+    }
+    rendered
+}
+
+/// Computes a unified diff between the original and the formatted content of a file.
+///
+/// The formatting changes lines in place, or removes or adds lines at the end of the file.
+/// Hence, the i-th line of the original content is compared with the i-th line of
+/// the formatted content. Returns an empty string if the contents are identical.
+pub fn unified_diff(file_path: &Path, original: &[u8], formatted: &[u8]) -> String {
+    let old_lines = split_lines(original);
+    let new_lines = split_lines(formatted);
+    let number_of_lines = old_lines.len().max(new_lines.len());
+    let is_changed = |i: usize| old_lines.get(i) != new_lines.get(i);
+
+    let changed_lines: Vec<usize> = (0..number_of_lines).filter(|i| is_changed(*i)).collect();
+    if changed_lines.is_empty() {
+        return String::new();
+    }
+
+    let mut diff = format!(
+        "--- a/{}\n+++ b/{}\n",
+        file_path.display(),
+        file_path.display()
+    );
+
+    // Group changed lines that are close to each other into hunks.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for line in changed_lines {
+        let start = line.saturating_sub(CONTEXT_LINES);
+        let end = (line + 1 + CONTEXT_LINES).min(number_of_lines);
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    for (start, end) in hunks {
+        let old_count = end.min(old_lines.len()).saturating_sub(start);
+        let new_count = end.min(new_lines.len()).saturating_sub(start);
+        let old_start = if old_count > 0 { start + 1 } else { start };
+        let new_start = if new_count > 0 { start + 1 } else { start };
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_count, new_start, new_count
+        ));
+
+        let mut i = start;
+        while i < end {
+            if !is_changed(i) {
+                diff.push_str(&format!(" {}\n", render_line(old_lines[i])));
+                i += 1;
+                continue;
+            }
+            // Print a block of consecutive changed lines: first the old ones, then the new ones.
+            let block_start = i;
+            while i < end && is_changed(i) {
+                i += 1;
+            }
+            for line in old_lines.iter().take(i).skip(block_start) {
+                diff.push_str(&format!("-{}\n", render_line(line)));
+            }
+            for line in new_lines.iter().take(i).skip(block_start) {
+                diff.push_str(&format!("+{}\n", render_line(line)));
+            }
+        }
+    }
+
+    diff
+}
+
+/// Counts the lines inserted and deleted by the formatting, consistently with `unified_diff`.
+/// A line that is changed in place counts as one deletion and one insertion.
+pub fn diff_stat(original: &[u8], formatted: &[u8]) -> DiffStat {
+    let old_lines = split_lines(original);
+    let new_lines = split_lines(formatted);
+    let number_of_lines = old_lines.len().max(new_lines.len());
+    let mut stat = DiffStat::default();
+    for i in 0..number_of_lines {
+        if old_lines.get(i) != new_lines.get(i) {
+            stat.deletions += usize::from(i < old_lines.len());
+            stat.insertions += usize::from(i < new_lines.len());
+        }
+    }
+    stat
+}
+
+/// Returns "1 <singular>" or "N <plural>".
+fn pluralize(count: usize, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, singular)
+    } else {
+        format!("{} {}", count, plural)
+    }
+}
+
+/// Renders a summary similar to `git diff --stat`. Each file gets a line
+/// with the number of changed lines and a bar of `+` and `-` characters,
+/// followed by a line with the totals. The bars are scaled down
+/// if the largest number of changed lines does not fit into the maximum width.
+pub fn render_diff_stat(file_stats: &[(String, DiffStat)]) -> String {
+    let name_width = file_stats
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max_changes = file_stats
+        .iter()
+        .map(|(_, stat)| stat.insertions + stat.deletions)
+        .max()
+        .unwrap_or(0);
+    let count_width = max_changes.to_string().len();
+    let scale = |count: usize| -> usize {
+        if max_changes <= STAT_BAR_WIDTH || count == 0 {
+            count
+        } else {
+            (count * STAT_BAR_WIDTH / max_changes).max(1)
+        }
+    };
+
+    let mut output = String::new();
+    let mut total = DiffStat::default();
+    for (name, stat) in file_stats {
+        output.push_str(&format!(
+            " {:<name_width$} | {:>count_width$} {}{}\n",
+            name,
+            stat.insertions + stat.deletions,
+            "+".repeat(scale(stat.insertions)),
+            "-".repeat(scale(stat.deletions)),
+        ));
+        total.insertions += stat.insertions;
+        total.deletions += stat.deletions;
+    }
+
+    output.push_str(&format!(
+        " {} changed",
+        pluralize(file_stats.len(), "file", "files")
+    ));
+    if total.insertions > 0 {
+        output.push_str(&format!(
+            ", {}",
+            pluralize(total.insertions, "insertion(+)", "insertions(+)")
+        ));
+    }
+    if total.deletions > 0 {
+        output.push_str(&format!(
+            ", {}",
+            pluralize(total.deletions, "deletion(-)", "deletions(-)")
+        ));
+    }
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_split_lines() {
+        assert_eq!(split_lines(b""), Vec::<&[u8]>::new());
+        assert_eq!(split_lines(b"a"), vec![b"a" as &[u8]]);
+        assert_eq!(
+            split_lines(b"a\nb\r\nc\rd"),
+            vec![b"a\n" as &[u8], b"b\r\n", b"c\r", b"d"]
+        );
+        assert_eq!(
+            split_lines(b"\r\r\n\n"),
+            vec![b"\r" as &[u8], b"\r\n", b"\n"]
+        );
+        assert_eq!(
+            split_lines("a\u{2028}b\u{2029}c".as_bytes()),
+            vec!["a\u{2028}".as_bytes(), "b\u{2029}".as_bytes(), b"c"]
+        );
+    }
+
+    #[test]
+    fn test_render_line() {
+        assert_eq!(render_line(b"hello  \r\n"), "hello··\\r\\n");
+        assert_eq!(render_line(b"\tx = 1\t\n"), "→x = 1→\\n");
+        assert_eq!(render_line(b"a b"), "a b");
+        assert_eq!(render_line(b"  \r"), "··\\r");
+        assert_eq!(render_line(b"x\x0C\n"), "x\\f\\n");
+        assert_eq!(render_line("x \u{2028}".as_bytes()), "x·\\u2028");
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let original: &[u8] =
+            b"line 1\nline 2  \nline 3\nline 4\nline 5\nline 6\nline 7\nline 8\nline 9\nline 10\r\nline 11\n\n\n";
+        let formatted: &[u8] =
+            b"line 1\nline 2\nline 3\nline 4\nline 5\nline 6\nline 7\nline 8\nline 9\nline 10\nline 11\n";
+        assert_eq!(
+            unified_diff(&PathBuf::from("foo.txt"), original, formatted),
+            "--- a/foo.txt\n\
+             +++ b/foo.txt\n\
+             @@ -1,5 +1,5 @@\n \
+             line 1\\n\n\
+             -line 2··\\n\n\
+             +line 2\\n\n \
+             line 3\\n\n \
+             line 4\\n\n \
+             line 5\\n\n\
+             @@ -7,7 +7,5 @@\n \
+             line 7\\n\n \
+             line 8\\n\n \
+             line 9\\n\n\
+             -line 10\\r\\n\n\
+             +line 10\\n\n \
+             line 11\\n\n\
+             -\\n\n\
+             -\\n\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_merges_close_hunks() {
+        assert_eq!(
+            unified_diff(
+                &PathBuf::from("foo.txt"),
+                b"a \nb\nc\nd\ne\t\n",
+                b"a\nb\nc\nd\ne\n"
+            ),
+            "--- a/foo.txt\n\
+             +++ b/foo.txt\n\
+             @@ -1,5 +1,5 @@\n\
+             -a·\\n\n\
+             +a\\n\n \
+             b\\n\n \
+             c\\n\n \
+             d\\n\n\
+             -e→\\n\n\
+             +e\\n\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_missing_new_line_marker() {
+        assert_eq!(
+            unified_diff(&PathBuf::from("foo.txt"), b"hello", b"hello\n"),
+            "--- a/foo.txt\n+++ b/foo.txt\n@@ -1,1 +1,1 @@\n-hello\n+hello\\n\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_no_changes() {
+        assert_eq!(
+            unified_diff(&PathBuf::from("foo.txt"), b"hello\n", b"hello\n"),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_diff_stat() {
+        assert_eq!(diff_stat(b"a\nb\n", b"a\nb\n"), DiffStat::default());
+        assert_eq!(
+            diff_stat(b"a \nb\nc\t\n\n\n", b"a\nb\nc\n"),
+            DiffStat {
+                insertions: 2,
+                deletions: 4
+            }
+        );
+        assert_eq!(
+            diff_stat(b"hello", b"hello\n"),
+            DiffStat {
+                insertions: 1,
+                deletions: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_diff_stat() {
+        assert_eq!(
+            render_diff_stat(&[
+                (
+                    String::from("src/main.rs"),
+                    DiffStat {
+                        insertions: 2,
+                        deletions: 3
+                    }
+                ),
+                (
+                    String::from("README.md"),
+                    DiffStat {
+                        insertions: 10,
+                        deletions: 10
+                    }
+                ),
+            ]),
+            " src/main.rs |  5 ++---\n\
+             \x20README.md   | 20 ++++++++++----------\n\
+             \x202 files changed, 12 insertions(+), 13 deletions(-)\n"
+        );
+    }
+
+    #[test]
+    fn test_render_diff_stat_scaled() {
+        assert_eq!(
+            render_diff_stat(&[
+                (
+                    String::from("a.txt"),
+                    DiffStat {
+                        insertions: 0,
+                        deletions: 100
+                    }
+                ),
+                (
+                    String::from("b.txt"),
+                    DiffStat {
+                        insertions: 1,
+                        deletions: 1
+                    }
+                ),
+            ]),
+            format!(
+                " a.txt | 100 {}\n b.txt |   2 +-\n 2 files changed, 1 insertion(+), 101 deletions(-)\n",
+                "-".repeat(50)
+            )
+        );
+    }
+}
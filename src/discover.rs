@@ -1,70 +1,211 @@
 // Library imports
+use globset::GlobBuilder;
+use globset::GlobSet;
+use globset::GlobSetBuilder;
 use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::path::Component;
+use std::path::Path;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
 // Internal imports
-use crate::error::die;
+use crate::config::CONFIG_FILE_NAME;
 use crate::error::Error;
+use crate::gitignore::GitignoreMatcher;
+
+/// Files found by `discover_files`.
+#[derive(Debug, Default, PartialEq)]
+pub struct DiscoveredFiles {
+    /// Files to be processed, sorted and without duplicates.
+    pub files: Vec<PathBuf>,
+
+    /// Symbolic links that were skipped because symbolic links are not followed.
+    /// Sorted and without duplicates.
+    pub skipped_symlinks: Vec<PathBuf>,
+
+    /// Paths that are neither regular files nor directories, e.g. named pipes,
+    /// sockets or device files. Sorted and without duplicates.
+    pub skipped_special_files: Vec<PathBuf>,
+}
 
 /// Lists all files in a collection of paths (directories or files).
-pub fn discover_files(paths: &[PathBuf], follow_symlinks: bool) -> Vec<PathBuf> {
+///
+/// If `follow_symlinks` is false, symbolic links are skipped and
+/// returned separately in `skipped_symlinks`.
+///
+/// If `respect_gitignore` is true, files and directories found inside the directories
+/// are skipped if they are ignored by `.gitignore` files. Paths given explicitly are
+/// never skipped because of `.gitignore` files.
+///
+/// If `max_depth` is set, directories are searched only up to that depth.
+/// The explicitly given paths have depth zero, the entries of the given directories
+/// have depth one, and so on. With zero, only explicitly given files are listed.
+///
+/// If `exclude` is set, directories whose path followed by `/` matches the regular
+/// expression are not searched at all, e.g. `^node_modules/`. The caller still has to
+/// exclude the matching files with `exclude_files`.
+///
+/// Directories found inside the directories whose name is one of `exclude_dirs`,
+/// e.g. `target`, are not searched at all either. Only the last component
+/// of the path is compared. Directories given explicitly are always searched.
+///
+/// Configuration files `.whitespace-format.toml` found inside the directories are not listed.
+///
+/// Each directory is searched at most once, even if it is reachable through several
+/// symbolic links. Hence, the search terminates even if symbolic links form a cycle.
+pub fn discover_files(
+    paths: &[PathBuf],
+    follow_symlinks: bool,
+    respect_gitignore: bool,
+    max_depth: Option<usize>,
+    exclude: Option<&Regex>,
+    exclude_dirs: &[String],
+) -> Result<DiscoveredFiles, Error> {
     let mut paths: Vec<PathBuf> = Vec::from(paths);
     let mut files: Vec<PathBuf> = Vec::new();
+    let mut skipped_symlinks: Vec<PathBuf> = Vec::new();
+    let mut skipped_special_files: Vec<PathBuf> = Vec::new();
+    let mut gitignore_matcher = GitignoreMatcher::new();
+    let mut explicit_paths = true;
+
+    // Depth of the paths currently being examined.
+    let mut depth: usize = 0;
+
+    // Canonical paths of the directories that have been searched or are about to be.
+    let mut visited_directories: HashSet<PathBuf> = HashSet::new();
 
     loop {
         let mut directories: Vec<PathBuf> = Vec::new();
 
         for path in paths.iter() {
             if !path.exists() {
-                die(Error::FileNotFound(path.display().to_string()));
+                return Err(Error::FileNotFound(path.display().to_string()));
             } else if path.is_symlink() && !follow_symlinks {
+                skipped_symlinks.push(path.clone());
+            } else if respect_gitignore
+                && !explicit_paths
+                && gitignore_matcher.is_ignored(path, path.is_dir())
+            {
                 continue;
             } else if path.is_file() {
+                if !explicit_paths
+                    && path
+                        .file_name()
+                        .is_some_and(|name| name == CONFIG_FILE_NAME)
+                {
+                    continue;
+                }
                 files.push(path.clone());
             } else if path.is_dir() {
-                directories.push(path.clone());
+                if exclude.is_some_and(|regex| is_excluded_directory(path, regex))
+                    || (!explicit_paths && has_excluded_name(path, exclude_dirs))
+                {
+                    continue;
+                }
+                let canonical_path = path.canonicalize().unwrap_or_else(|_| path.clone());
+                if visited_directories.insert(canonical_path) {
+                    directories.push(path.clone());
+                }
+            } else {
+                skipped_special_files.push(path.clone());
             }
         }
 
-        if directories.is_empty() {
+        if directories.is_empty() || max_depth.is_some_and(|max_depth| depth >= max_depth) {
             break;
         }
 
         paths.clear();
+        explicit_paths = false;
+        depth += 1;
 
         for directory in directories.iter() {
-            if let Ok(inner_paths) = directory.read_dir() {
-                for inner_path in inner_paths {
-                    if let Ok(inner_path) = inner_path {
-                        paths.push(inner_path.path());
-                    } else {
-                        die(Error::FailedToReadDirectoryEntry(
-                            directory.display().to_string(),
-                        ));
-                    }
-                }
-            } else {
-                die(Error::FailedToReadDirectory(
-                    directory.display().to_string(),
-                ));
+            let inner_paths = directory
+                .read_dir()
+                .map_err(|_| Error::FailedToReadDirectory(directory.display().to_string()))?;
+            for inner_path in inner_paths {
+                let inner_path = inner_path.map_err(|_| {
+                    Error::FailedToReadDirectoryEntry(directory.display().to_string())
+                })?;
+                paths.push(inner_path.path());
             }
         }
     }
 
     files.sort_unstable();
     files.dedup();
-    files
+    skipped_symlinks.sort_unstable();
+    skipped_symlinks.dedup();
+    skipped_special_files.sort_unstable();
+    skipped_special_files.dedup();
+    Ok(DiscoveredFiles {
+        files,
+        skipped_symlinks,
+        skipped_special_files,
+    })
 }
 
-/// Compiles regular expression.
-pub fn compile_regular_expression(regular_expression: &str) -> Regex {
-    if let Ok(regex) = Regex::new(regular_expression) {
-        regex
+/// Parses a list of files, one path per line. Empty lines and lines starting with `#`
+/// are skipped. Each path must be an existing file; directories are not searched.
+pub fn parse_file_list<R: BufRead>(reader: R, source: &str) -> Result<Vec<PathBuf>, Error> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for line in reader.lines() {
+        let line = line.map_err(|_| Error::CannotReadFile(source.to_string()))?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let path = PathBuf::from(line.strip_suffix('\r').unwrap_or(&line));
+        if !path.is_file() {
+            return Err(Error::FileNotFound(path.display().to_string()));
+        }
+        files.push(path);
+    }
+    Ok(files)
+}
+
+/// Reads a list of files from a file, or from the standard input if the path is `-`.
+/// See `parse_file_list` for the format of the list.
+pub fn read_file_list(list_path: &Path) -> Result<Vec<PathBuf>, Error> {
+    if list_path == Path::new("-") {
+        parse_file_list(io::stdin().lock(), "standard input")
     } else {
-        die(Error::InvalidRegularExpression(
-            regular_expression.to_string(),
-        ));
+        let list_file = fs::File::open(list_path)
+            .map_err(|_| Error::CannotReadFile(list_path.display().to_string()))?;
+        parse_file_list(
+            io::BufReader::new(list_file),
+            &list_path.display().to_string(),
+        )
+    }
+}
+
+/// Compiles regular expression.
+pub fn compile_regular_expression(regular_expression: &str) -> Result<Regex, Error> {
+    Regex::new(regular_expression)
+        .map_err(|_| Error::InvalidRegularExpression(regular_expression.to_string()))
+}
+
+/// Determines if a directory is excluded by a regular expression,
+/// i.e., if the path of the directory followed by `/` matches it.
+fn is_excluded_directory(path: &Path, regex: &Regex) -> bool {
+    let mut directory = path.to_string_lossy().into_owned();
+    if !directory.ends_with('/') {
+        directory.push('/');
     }
+    regex.is_match(&directory)
+}
+
+/// Determines if the name of a directory, i.e., the last component of its path,
+/// is one of the excluded names.
+fn has_excluded_name(path: &Path, excluded_names: &[String]) -> bool {
+    path.file_name().is_some_and(|name| {
+        excluded_names
+            .iter()
+            .any(|excluded_name| name == excluded_name.as_str())
+    })
 }
 
 /// Excludes file names that match a regular expression.
@@ -78,6 +219,88 @@ pub fn exclude_files(paths: &[PathBuf], regex: &Regex) -> Vec<PathBuf> {
     filtered_files
 }
 
+/// Compiles glob patterns into a single matcher.
+/// In the patterns, `*` does not match the path separator `/`, but `**` does.
+pub fn compile_globs(globs: &[String]) -> Result<GlobSet, Error> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        let compiled_glob = GlobBuilder::new(glob)
+            .literal_separator(true)
+            .build()
+            .map_err(|_| Error::InvalidGlob(glob.clone()))?;
+        builder.add(compiled_glob);
+    }
+    builder
+        .build()
+        .map_err(|_| Error::InvalidGlob(globs.join(" ")))
+}
+
+/// Removes the `.` components from a path, so that `./src/main.rs` is matched as `src/main.rs`.
+fn without_current_directory(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// Filters file names by glob patterns. A file is kept if it matches at least one
+/// of the `include` patterns and it does not match any of the `exclude` patterns.
+/// If there are no `include` patterns, every file that is not excluded is kept.
+pub fn filter_by_globs(paths: &[PathBuf], include: &GlobSet, exclude: &GlobSet) -> Vec<PathBuf> {
+    let mut filtered_files: Vec<PathBuf> = Vec::new();
+    for path in paths.iter() {
+        let path_to_match = without_current_directory(path);
+        if (include.is_empty() || include.is_match(&path_to_match))
+            && !exclude.is_match(&path_to_match)
+        {
+            filtered_files.push(path.clone());
+        }
+    }
+    filtered_files
+}
+
+/// Filters files by their extensions, which are compared case-insensitively.
+/// A leading dot in the allowed extensions is ignored, i.e., `.rs` is the same as `rs`.
+/// Files without an extension, including dotfiles such as `.gitignore`, are kept
+/// only if `include_files_without_extension` is set.
+/// If there are no allowed extensions, every file is kept.
+pub fn filter_by_extensions(
+    paths: &[PathBuf],
+    extensions: &[String],
+    include_files_without_extension: bool,
+) -> Vec<PathBuf> {
+    if extensions.is_empty() {
+        return paths.to_vec();
+    }
+    let extensions: Vec<String> = extensions
+        .iter()
+        .map(|extension| extension.trim_start_matches('.').to_lowercase())
+        .collect();
+    let mut filtered_files: Vec<PathBuf> = Vec::new();
+    for path in paths.iter() {
+        let is_allowed = match path.extension() {
+            Some(extension) => extensions.contains(&extension.to_string_lossy().to_lowercase()),
+            None => include_files_without_extension,
+        };
+        if is_allowed {
+            filtered_files.push(path.clone());
+        }
+    }
+    filtered_files
+}
+
+/// Keeps only the files modified after the given time. Files whose modification time
+/// cannot be determined are kept, so that the error is reported when they are processed.
+pub fn filter_modified_after(paths: &[PathBuf], time: SystemTime) -> Vec<PathBuf> {
+    let mut filtered_files: Vec<PathBuf> = Vec::new();
+    for path in paths.iter() {
+        let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+        if modified.map_or(true, |modified| modified > time) {
+            filtered_files.push(path.clone());
+        }
+    }
+    filtered_files
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,24 +308,327 @@ mod tests {
 
     #[test]
     fn test_compile_regular_expression() {
-        compile_regular_expression("");
-        compile_regular_expression(".jpg");
-        compile_regular_expression(UNMATCHABLE_REGEX);
+        assert!(compile_regular_expression("").is_ok());
+        assert!(compile_regular_expression(".jpg").is_ok());
+        assert!(compile_regular_expression(UNMATCHABLE_REGEX).is_ok());
+        assert_eq!(
+            compile_regular_expression("(unclosed").unwrap_err(),
+            Error::InvalidRegularExpression("(unclosed".to_string())
+        );
+    }
+
+    #[test]
+    fn test_compile_globs_invalid() {
+        assert_eq!(
+            compile_globs(&["*.rs".to_string(), "[unclosed".to_string()]).unwrap_err(),
+            Error::InvalidGlob("[unclosed".to_string())
+        );
+    }
+
+    #[test]
+    fn test_discover_files_file_not_found() {
+        assert_eq!(
+            discover_files(
+                &[PathBuf::from("this_file_does_not_exist.txt")],
+                false,
+                true,
+                None,
+                None,
+                &[],
+            )
+            .unwrap_err(),
+            Error::FileNotFound("this_file_does_not_exist.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn test_discover_files_max_depth() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("a/b/c")).unwrap();
+        for file in ["top.txt", "a/one.txt", "a/b/two.txt", "a/b/c/three.txt"] {
+            fs::write(root.join(file), "hello\n").unwrap();
+        }
+        let paths = [root.to_path_buf(), root.join("top.txt")];
+
+        assert_eq!(
+            discover_files(&paths, false, true, Some(0), None, &[])
+                .unwrap()
+                .files,
+            vec![root.join("top.txt")]
+        );
+        assert_eq!(
+            discover_files(&paths, false, true, Some(1), None, &[])
+                .unwrap()
+                .files,
+            vec![root.join("top.txt")]
+        );
+        assert_eq!(
+            discover_files(&paths, false, true, Some(2), None, &[])
+                .unwrap()
+                .files,
+            vec![root.join("a/one.txt"), root.join("top.txt")]
+        );
+        assert_eq!(
+            discover_files(&paths, false, true, Some(3), None, &[])
+                .unwrap()
+                .files,
+            vec![
+                root.join("a/b/two.txt"),
+                root.join("a/one.txt"),
+                root.join("top.txt")
+            ]
+        );
+        assert_eq!(
+            discover_files(&paths, false, true, None, None, &[])
+                .unwrap()
+                .files,
+            vec![
+                root.join("a/b/c/three.txt"),
+                root.join("a/b/two.txt"),
+                root.join("a/one.txt"),
+                root.join("top.txt")
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_files_symlink_cycle() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("top.txt"), "hello\n").unwrap();
+        fs::write(root.join("a/b/deep.txt"), "hello\n").unwrap();
+        // A symbolic link to an ancestor directory and a symbolic link to a file.
+        std::os::unix::fs::symlink(root, root.join("a/b/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("top.txt"), root.join("a/top_link.txt")).unwrap();
+
+        assert_eq!(
+            discover_files(&[root.to_path_buf()], true, true, None, None, &[])
+                .unwrap()
+                .files,
+            vec![
+                root.join("a/b/deep.txt"),
+                root.join("a/top_link.txt"),
+                root.join("top.txt"),
+            ]
+        );
+
+        // The same directory given twice is searched only once. The root directory
+        // is reached only through the symbolic link.
+        assert_eq!(
+            discover_files(
+                &[root.join("a"), root.join("a/b/loop/a")],
+                true,
+                true,
+                None,
+                None,
+                &[],
+            )
+            .unwrap()
+            .files,
+            vec![
+                root.join("a/b/deep.txt"),
+                root.join("a/b/loop/top.txt"),
+                root.join("a/top_link.txt"),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_files_skipped_symlinks() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("a")).unwrap();
+        fs::write(root.join("a/real.txt"), "hello\n").unwrap();
+        std::os::unix::fs::symlink(root.join("a/real.txt"), root.join("link.txt")).unwrap();
+        std::os::unix::fs::symlink(root.join("a"), root.join("linked_directory")).unwrap();
+
+        let discovered_files =
+            discover_files(&[root.to_path_buf()], false, true, None, None, &[]).unwrap();
+        assert_eq!(discovered_files.files, vec![root.join("a/real.txt")]);
+        assert_eq!(
+            discovered_files.skipped_symlinks,
+            vec![root.join("link.txt"), root.join("linked_directory")]
+        );
+
+        // Nothing is skipped if symbolic links are followed.
+        let discovered_files =
+            discover_files(&[root.to_path_buf()], true, true, None, None, &[]).unwrap();
+        assert!(discovered_files.skipped_symlinks.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_files_skipped_special_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::write(root.join("real.txt"), "hello\n").unwrap();
+        let _listener = std::os::unix::net::UnixListener::bind(root.join("socket")).unwrap();
+
+        let discovered_files =
+            discover_files(&[root.to_path_buf()], false, true, None, None, &[]).unwrap();
+        assert_eq!(discovered_files.files, vec![root.join("real.txt")]);
+        assert_eq!(
+            discovered_files.skipped_special_files,
+            vec![root.join("socket")]
+        );
+    }
+
+    #[test]
+    fn test_read_file_list() {
+        let directory = tempfile::tempdir().unwrap();
+        let first = directory.path().join("first.txt");
+        let second = directory.path().join("second file.txt");
+        fs::write(&first, "hello\n").unwrap();
+        fs::write(&second, "hello\n").unwrap();
+        let list_path = directory.path().join("list.txt");
+        fs::write(
+            &list_path,
+            format!(
+                "# Files to format.\n{}\n\n   \n{}\r\n# {}\n",
+                first.display(),
+                second.display(),
+                directory.path().join("commented_out.txt").display()
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(read_file_list(&list_path).unwrap(), vec![first, second]);
+
+        fs::write(&list_path, "does_not_exist.txt\n").unwrap();
+        assert_eq!(
+            read_file_list(&list_path).unwrap_err(),
+            Error::FileNotFound("does_not_exist.txt".to_string())
+        );
+        assert_eq!(
+            read_file_list(&directory.path().join("missing_list.txt")).unwrap_err(),
+            Error::CannotReadFile(
+                directory
+                    .path()
+                    .join("missing_list.txt")
+                    .display()
+                    .to_string()
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_files_excluded_directory_is_not_searched() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("node_modules/package")).unwrap();
+        fs::write(root.join("src/main.js"), "hello\n").unwrap();
+        fs::write(root.join("node_modules/package/index.js"), "hello\n").unwrap();
+        // Searching the excluded directory with symbolic links followed would fail
+        // on the dangling symbolic link.
+        std::os::unix::fs::symlink(root.join("missing"), root.join("node_modules/broken")).unwrap();
+
+        let regex = compile_regular_expression("/node_modules/").unwrap();
+        let discovered_files =
+            discover_files(&[root.to_path_buf()], true, true, None, Some(&regex), &[]).unwrap();
+        assert_eq!(discovered_files.files, vec![root.join("src/main.js")]);
+
+        assert!(discover_files(&[root.to_path_buf()], true, true, None, None, &[]).is_err());
+    }
+
+    #[test]
+    fn test_discover_files_skips_config_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("file.txt"), "hello\n").unwrap();
+        fs::write(root.join(CONFIG_FILE_NAME), "").unwrap();
+        fs::write(root.join("sub").join(CONFIG_FILE_NAME), "").unwrap();
+
+        let discovered_files =
+            discover_files(&[root.to_path_buf()], false, true, None, None, &[]).unwrap();
+        assert_eq!(discovered_files.files, vec![root.join("file.txt")]);
+
+        // A configuration file given explicitly is listed.
+        let discovered_files =
+            discover_files(&[root.join(CONFIG_FILE_NAME)], false, true, None, None, &[]).unwrap();
+        assert_eq!(discovered_files.files, vec![root.join(CONFIG_FILE_NAME)]);
+    }
+
+    #[test]
+    fn test_discover_files_exclude_dirs() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir_all(root.join("src/target")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join("src/main.rs"), "hello\n").unwrap();
+        fs::write(root.join("src/target.rs"), "hello\n").unwrap();
+        fs::write(root.join("src/target/file.rs"), "hello\n").unwrap();
+        fs::write(root.join("target/debug/build.log"), "hello\n").unwrap();
+        // Searching the excluded directory with symbolic links followed would fail
+        // on the dangling symbolic link.
+        std::os::unix::fs::symlink(root.join("missing"), root.join("target/broken")).unwrap();
+
+        let exclude_dirs = ["target".to_string(), "node_modules".to_string()];
+        let discovered_files =
+            discover_files(&[root.to_path_buf()], true, true, None, None, &exclude_dirs).unwrap();
+        assert_eq!(
+            discovered_files.files,
+            vec![root.join("src/main.rs"), root.join("src/target.rs")]
+        );
+        assert!(discover_files(&[root.to_path_buf()], true, true, None, None, &[]).is_err());
+
+        // Only the name of the directory is compared, not the whole path.
+        let exclude_dirs = ["src/target".to_string()];
+        let discovered_files =
+            discover_files(&[root.join("src")], true, true, None, None, &exclude_dirs).unwrap();
+        assert_eq!(discovered_files.files.len(), 3);
+
+        // A directory given explicitly is searched.
+        let exclude_dirs = ["debug".to_string()];
+        let discovered_files = discover_files(
+            &[root.join("target/debug")],
+            true,
+            true,
+            None,
+            None,
+            &exclude_dirs,
+        )
+        .unwrap();
+        assert_eq!(
+            discovered_files.files,
+            vec![root.join("target/debug/build.log")]
+        );
+    }
+
+    #[test]
+    fn test_is_excluded_directory() {
+        let regex = compile_regular_expression("^node_modules/").unwrap();
+        assert!(is_excluded_directory(Path::new("node_modules"), &regex));
+        assert!(is_excluded_directory(Path::new("node_modules/"), &regex));
+        assert!(!is_excluded_directory(Path::new("src"), &regex));
+        assert!(!is_excluded_directory(
+            Path::new("src/node_modules"),
+            &regex
+        ));
+
+        let regex = compile_regular_expression(UNMATCHABLE_REGEX).unwrap();
+        assert!(!is_excluded_directory(Path::new("src"), &regex));
     }
 
     #[test]
     fn test_exclude_files() {
-        let regex = compile_regular_expression("\\.(png|jpeg|jpg)$");
+        let regex = compile_regular_expression("\\.(png|jpeg|jpg)$").unwrap();
 
         assert_eq!(
             exclude_files(
-                &vec![
+                &[
                     PathBuf::from("photo.jpeg"),
                     PathBuf::from("web_page.html"),
                     PathBuf::from("diagram.png"),
                     PathBuf::from("photo2.jpg"),
                     PathBuf::from("README.txt"),
-                    PathBuf::from("Makefile"),
+                    PathBuf::from("Makefile")
                 ],
                 &regex
             ),
@@ -116,17 +642,17 @@ mod tests {
 
     #[test]
     fn test_exclude_files_default() {
-        let regex = compile_regular_expression(UNMATCHABLE_REGEX);
+        let regex = compile_regular_expression(UNMATCHABLE_REGEX).unwrap();
 
         assert_eq!(
             exclude_files(
-                &vec![
+                &[
                     PathBuf::from("photo.jpeg"),
                     PathBuf::from("web_page.html"),
                     PathBuf::from("diagram.png"),
                     PathBuf::from("photo2.jpg"),
                     PathBuf::from("README.txt"),
-                    PathBuf::from("Makefile"),
+                    PathBuf::from("Makefile")
                 ],
                 &regex
             ),
@@ -141,20 +667,241 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_filter_by_globs() {
+        let files = [
+            PathBuf::from("Cargo.toml"),
+            PathBuf::from("src/main.rs"),
+            PathBuf::from("src/notes.txt"),
+            PathBuf::from("src/nested/deep.rs"),
+            PathBuf::from("src/nested/other.txt"),
+            PathBuf::from("./src/dot.txt"),
+            PathBuf::from("images/logo.png"),
+            PathBuf::from("images/photo.jpg"),
+        ];
+        let none = compile_globs(&[]).unwrap();
+
+        assert_eq!(filter_by_globs(&files, &none, &none), files.to_vec());
+
+        assert_eq!(
+            filter_by_globs(
+                &files,
+                &compile_globs(&["**/*.rs".to_string()]).unwrap(),
+                &none
+            ),
+            vec![
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("src/nested/deep.rs"),
+            ]
+        );
+
+        assert_eq!(
+            filter_by_globs(
+                &files,
+                &compile_globs(&["src/*.txt".to_string()]).unwrap(),
+                &none
+            ),
+            vec![
+                PathBuf::from("src/notes.txt"),
+                PathBuf::from("./src/dot.txt"),
+            ]
+        );
+
+        assert_eq!(
+            filter_by_globs(
+                &files,
+                &none,
+                &compile_globs(&["**/*.{png,jpg}".to_string(), "src/nested/**".to_string()])
+                    .unwrap()
+            ),
+            vec![
+                PathBuf::from("Cargo.toml"),
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("src/notes.txt"),
+                PathBuf::from("./src/dot.txt"),
+            ]
+        );
+
+        assert_eq!(
+            filter_by_globs(
+                &files,
+                &compile_globs(&["src/**".to_string()]).unwrap(),
+                &compile_globs(&["**/*.txt".to_string()]).unwrap()
+            ),
+            vec![
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("src/nested/deep.rs"),
+            ]
+        );
+    }
+
     #[test]
     fn test_discover_files() {
-        let files = discover_files(&vec![PathBuf::from("src/")], false);
+        let files = discover_files(&[PathBuf::from("src/")], false, true, None, None, &[])
+            .unwrap()
+            .files;
         assert_eq!(
             files,
             vec![
                 PathBuf::from("src/change.rs"),
                 PathBuf::from("src/cli.rs"),
+                PathBuf::from("src/config.rs"),
                 PathBuf::from("src/core.rs"),
+                PathBuf::from("src/diff.rs"),
                 PathBuf::from("src/discover.rs"),
                 PathBuf::from("src/error.rs"),
+                PathBuf::from("src/exit.rs"),
+                PathBuf::from("src/gitignore.rs"),
+                PathBuf::from("src/lib.rs"),
                 PathBuf::from("src/main.rs"),
+                PathBuf::from("src/parallel.rs"),
+                PathBuf::from("src/read_ahead.rs"),
+                PathBuf::from("src/report.rs"),
                 PathBuf::from("src/writer.rs"),
             ]
         );
     }
+
+    #[test]
+    fn test_discover_files_respect_gitignore() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n*.log\n!keep.log\n").unwrap();
+        fs::write(root.join("sub/.gitignore"), "local.txt\n").unwrap();
+        for file in [
+            "a.txt",
+            "b.log",
+            "keep.log",
+            "target/x.txt",
+            "sub/c.txt",
+            "sub/local.txt",
+            "sub/target",
+        ] {
+            fs::write(root.join(file), "hello\n").unwrap();
+        }
+
+        assert_eq!(
+            discover_files(&[root.to_path_buf()], false, true, None, None, &[])
+                .unwrap()
+                .files,
+            vec![
+                root.join(".gitignore"),
+                root.join("a.txt"),
+                root.join("keep.log"),
+                root.join("sub/.gitignore"),
+                root.join("sub/c.txt"),
+                root.join("sub/target"),
+            ]
+        );
+
+        assert_eq!(
+            discover_files(&[root.to_path_buf()], false, false, None, None, &[])
+                .unwrap()
+                .files,
+            vec![
+                root.join(".gitignore"),
+                root.join("a.txt"),
+                root.join("b.log"),
+                root.join("keep.log"),
+                root.join("sub/.gitignore"),
+                root.join("sub/c.txt"),
+                root.join("sub/local.txt"),
+                root.join("sub/target"),
+                root.join("target/x.txt"),
+            ]
+        );
+
+        // Explicitly listed paths are never ignored.
+        assert_eq!(
+            discover_files(&[root.join("b.log")], false, true, None, None, &[])
+                .unwrap()
+                .files,
+            vec![root.join("b.log")]
+        );
+    }
+
+    #[test]
+    fn test_filter_modified_after() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let now = SystemTime::now();
+        let hour = std::time::Duration::from_secs(60 * 60);
+        let mut files: Vec<PathBuf> = Vec::new();
+        for (name, age) in [
+            ("old.txt", 48 * hour),
+            ("recent.txt", hour),
+            ("new.txt", hour / 60),
+        ] {
+            let path = root.join(name);
+            fs::write(&path, "hello\n").unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(now - age))
+                .unwrap();
+            files.push(path);
+        }
+
+        assert_eq!(
+            filter_modified_after(&files, now - 2 * hour),
+            vec![root.join("recent.txt"), root.join("new.txt")]
+        );
+        assert_eq!(
+            filter_modified_after(&files, now - hour / 2),
+            vec![root.join("new.txt")]
+        );
+        assert_eq!(filter_modified_after(&files, now), Vec::<PathBuf>::new());
+        assert_eq!(filter_modified_after(&files, now - 100 * hour), files);
+
+        // Files whose modification time is unknown are kept.
+        let missing = [root.join("missing.txt")];
+        assert_eq!(filter_modified_after(&missing, now), missing.to_vec());
+    }
+
+    #[test]
+    fn test_filter_by_extensions() {
+        let files = [
+            PathBuf::from("src/main.rs"),
+            PathBuf::from("README.md"),
+            PathBuf::from("docs/NOTES.TXT"),
+            PathBuf::from("docs/Image.PNG"),
+            PathBuf::from("Makefile"),
+            PathBuf::from(".gitignore"),
+            PathBuf::from("src/.hidden.rs"),
+            PathBuf::from("archive.tar.gz"),
+        ];
+
+        assert_eq!(filter_by_extensions(&files, &[], false), files.to_vec());
+
+        assert_eq!(
+            filter_by_extensions(
+                &files,
+                &["rs".to_string(), ".TXT".to_string(), "Md".to_string()],
+                false
+            ),
+            vec![
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("README.md"),
+                PathBuf::from("docs/NOTES.TXT"),
+                PathBuf::from("src/.hidden.rs"),
+            ]
+        );
+
+        // Dotfiles such as `.gitignore` have no extension.
+        assert_eq!(
+            filter_by_extensions(&files, &["gz".to_string()], true),
+            vec![
+                PathBuf::from("Makefile"),
+                PathBuf::from(".gitignore"),
+                PathBuf::from("archive.tar.gz"),
+            ]
+        );
+        assert_eq!(
+            filter_by_extensions(&files, &["gitignore".to_string()], false),
+            Vec::<PathBuf>::new()
+        );
+    }
 }
@@ -3,7 +3,11 @@ use colored::Colorize;
 use std::fmt;
 use std::process;
 
+// Internal imports
+use crate::exit::ExitCode;
+
 /// An error.
+#[derive(Debug, PartialEq)]
 pub enum Error {
     /// File cannot be found.
     FileNotFound(String),
@@ -17,11 +21,26 @@ pub enum Error {
     /// Regular expression (for filtering files) is invalid.
     InvalidRegularExpression(String),
 
+    /// Glob pattern (for filtering files) is invalid.
+    InvalidGlob(String),
+
     /// Cannot read file.
     CannotReadFile(String),
 
     /// Cannot write file.
     CannotWriteFile(String),
+
+    /// Backup file already exists.
+    BackupFileExists(String),
+
+    /// Formatting a file again would change it again.
+    NotIdempotent(String),
+
+    /// Configuration file is invalid. The path of the file and the reason.
+    InvalidConfigFile(String, String),
+
+    /// A written file read back from the disk is not formatted.
+    WriteNotVerified(String),
 }
 
 impl fmt::Display for Error {
@@ -52,23 +71,63 @@ impl fmt::Display for Error {
                     regular_expression.bold()
                 )
             }
+            Error::InvalidGlob(glob) => {
+                write!(formatter, "Invalid glob pattern {}.", glob.bold())
+            }
             Error::CannotReadFile(file_path) => {
                 write!(formatter, "Cannot read {}", file_path.bold())
             }
             Error::CannotWriteFile(file_path) => {
                 write!(formatter, "Cannot write {}", file_path.bold())
             }
+            Error::BackupFileExists(file_path) => {
+                write!(
+                    formatter,
+                    "Backup file {} already exists.",
+                    file_path.bold()
+                )
+            }
+            Error::NotIdempotent(file_path) => {
+                write!(
+                    formatter,
+                    "Formatting {} is not idempotent. Formatting it again would change it again.",
+                    file_path.bold()
+                )
+            }
+            Error::InvalidConfigFile(config_path, reason) => {
+                write!(
+                    formatter,
+                    "Invalid configuration file {}: {}.",
+                    config_path.bold(),
+                    reason
+                )
+            }
+            Error::WriteNotVerified(file_path) => {
+                write!(
+                    formatter,
+                    "File {} read back from the disk after writing it is not formatted.",
+                    file_path.bold()
+                )
+            }
         }
     }
 }
 
+impl std::error::Error for Error {}
+
 /// Prints an error message. The message is printed to standard error output.
 pub fn print_error(message: &str) {
     eprintln!("{} {}", "error:".bold().red(), message);
 }
 
-/// Prints error message and exits the program.
+/// Prints a warning message. The message is printed to standard error output.
+pub fn print_warning(message: &str) {
+    eprintln!("{} {}", "warning:".bold().yellow(), message);
+}
+
+/// Prints error message and exits the program with the exit code of the error.
+/// This function should be called only from `main`.
 pub fn die(error: Error) -> ! {
     print_error(&error.to_string());
-    process::exit(1);
+    process::exit(ExitCode::from(&error).code());
 }
@@ -0,0 +1,177 @@
+// Internal imports
+use crate::error::Error;
+
+/// Exit code of the program.
+///
+/// The exit code 2 is not listed here. It is used by the command line parser
+/// when the command line arguments are invalid.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ExitCode {
+    /// All files are formatted correctly, or they have been formatted.
+    Success = 0,
+
+    /// Some files would be formatted in check-only mode,
+    /// or some files have been formatted with `--fail-on-change`.
+    FormattingRequired = 1,
+
+    /// A file or a directory given on the command line does not exist.
+    FileNotFound = 3,
+
+    /// A directory cannot be read.
+    FailedToReadDirectory = 4,
+
+    /// An entry in a directory cannot be read.
+    FailedToReadDirectoryEntry = 5,
+
+    /// The regular expression given by `--exclude` is invalid.
+    InvalidRegularExpression = 6,
+
+    /// A glob pattern given by `--include` or `--exclude-glob` is invalid.
+    InvalidGlob = 7,
+
+    /// A file cannot be read.
+    CannotReadFile = 8,
+
+    /// A file cannot be written.
+    CannotWriteFile = 9,
+
+    /// A backup file given by `--backup` already exists.
+    BackupFileExists = 10,
+
+    /// Formatting a file is not idempotent. Used only with `--assert-idempotent`.
+    NotIdempotent = 11,
+
+    /// A configuration file is invalid.
+    InvalidConfigFile = 12,
+
+    /// A written file read back from the disk is not formatted.
+    /// Used only with `--verify-writes`.
+    WriteNotVerified = 13,
+}
+
+impl ExitCode {
+    /// Numeric value of the exit code.
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// Exit code of a run that finished without errors.
+    /// Changed files make the run fail only if `fail_on_change` is set,
+    /// i.e., in check-only mode or with `--fail-on-change`.
+    pub fn from_number_of_changed_files(
+        number_of_changed_files: usize,
+        fail_on_change: bool,
+    ) -> Self {
+        if fail_on_change && number_of_changed_files > 0 {
+            ExitCode::FormattingRequired
+        } else {
+            ExitCode::Success
+        }
+    }
+}
+
+impl From<&Error> for ExitCode {
+    /// Exit code of a run that failed with an error.
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::FileNotFound(_) => ExitCode::FileNotFound,
+            Error::FailedToReadDirectory(_) => ExitCode::FailedToReadDirectory,
+            Error::FailedToReadDirectoryEntry(_) => ExitCode::FailedToReadDirectoryEntry,
+            Error::InvalidRegularExpression(_) => ExitCode::InvalidRegularExpression,
+            Error::InvalidGlob(_) => ExitCode::InvalidGlob,
+            Error::CannotReadFile(_) => ExitCode::CannotReadFile,
+            Error::CannotWriteFile(_) => ExitCode::CannotWriteFile,
+            Error::BackupFileExists(_) => ExitCode::BackupFileExists,
+            Error::NotIdempotent(_) => ExitCode::NotIdempotent,
+            Error::InvalidConfigFile(_, _) => ExitCode::InvalidConfigFile,
+            Error::WriteNotVerified(_) => ExitCode::WriteNotVerified,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::CommandLineArguments;
+    use crate::core::process_file;
+    use crate::discover::compile_globs;
+    use crate::discover::compile_regular_expression;
+    use crate::discover::discover_files;
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_from_number_of_changed_files() {
+        assert_eq!(ExitCode::from_number_of_changed_files(0, false).code(), 0);
+        assert_eq!(ExitCode::from_number_of_changed_files(5, false).code(), 0);
+        assert_eq!(ExitCode::from_number_of_changed_files(0, true).code(), 0);
+        assert_eq!(ExitCode::from_number_of_changed_files(5, true).code(), 1);
+    }
+
+    #[test]
+    fn test_from_error() {
+        let error = discover_files(
+            &[PathBuf::from("this_file_does_not_exist.txt")],
+            false,
+            true,
+            None,
+            None,
+            &[],
+        )
+        .unwrap_err();
+        assert_eq!(ExitCode::from(&error).code(), 3);
+
+        let error = compile_regular_expression("(unclosed").unwrap_err();
+        assert_eq!(ExitCode::from(&error).code(), 6);
+
+        let error = compile_globs(&["[unclosed".to_string()]).unwrap_err();
+        assert_eq!(ExitCode::from(&error).code(), 7);
+
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--remove-trailing-whitespace",
+            ".",
+        ]);
+        let error = process_file(
+            &PathBuf::from("this_file_does_not_exist.txt"),
+            &command_line_arguments.get_options(),
+            &command_line_arguments.get_run_settings(),
+            true,
+        )
+        .unwrap_err();
+        assert_eq!(ExitCode::from(&error).code(), 8);
+
+        assert_eq!(
+            ExitCode::from(&Error::FailedToReadDirectory("foo".to_string())).code(),
+            4
+        );
+        assert_eq!(
+            ExitCode::from(&Error::FailedToReadDirectoryEntry("foo".to_string())).code(),
+            5
+        );
+        assert_eq!(
+            ExitCode::from(&Error::CannotWriteFile("foo".to_string())).code(),
+            9
+        );
+        assert_eq!(
+            ExitCode::from(&Error::BackupFileExists("foo.bak".to_string())).code(),
+            10
+        );
+        assert_eq!(
+            ExitCode::from(&Error::NotIdempotent("foo".to_string())).code(),
+            11
+        );
+        assert_eq!(
+            ExitCode::from(&Error::InvalidConfigFile(
+                "foo.toml".to_string(),
+                "bar".to_string()
+            ))
+            .code(),
+            12
+        );
+        assert_eq!(
+            ExitCode::from(&Error::WriteNotVerified("foo".to_string())).code(),
+            13
+        );
+    }
+}
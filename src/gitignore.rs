@@ -0,0 +1,128 @@
+// Library imports
+use ignore::gitignore::Gitignore;
+use ignore::gitignore::GitignoreBuilder;
+use std::collections::HashMap;
+use std::path;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Decides whether paths are ignored by `.gitignore` files.
+///
+/// Similarly to git, the `.gitignore` files in the directory containing the path
+/// and in all its ancestor directories are taken into account. The search
+/// for `.gitignore` files stops at the root of the git repository, i.e., at the
+/// first directory that contains `.git`. The closest `.gitignore` file that has
+/// a matching pattern decides. Within a single `.gitignore` file, the last
+/// matching pattern decides, which means that negated patterns (e.g. `!keep.txt`)
+/// can re-include a path.
+///
+/// The parsed `.gitignore` files are cached per directory.
+#[derive(Default)]
+pub struct GitignoreMatcher {
+    cache: HashMap<PathBuf, Option<Gitignore>>,
+}
+
+impl GitignoreMatcher {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the `.gitignore` file in a directory, if there is one.
+    fn load(directory: &Path) -> Option<Gitignore> {
+        let gitignore_path = directory.join(".gitignore");
+        if !gitignore_path.is_file() {
+            return None;
+        }
+        let mut builder = GitignoreBuilder::new(directory);
+        // Invalid lines are skipped. The remaining patterns are still used.
+        builder.add(gitignore_path);
+        builder.build().ok()
+    }
+
+    /// Determines if a path is ignored. The `is_directory` parameter says if the path
+    /// is a directory, since patterns ending with `/` match only directories.
+    pub fn is_ignored(&mut self, path: &Path, is_directory: bool) -> bool {
+        let Ok(absolute_path) = path::absolute(path) else {
+            return false;
+        };
+
+        for directory in absolute_path.ancestors().skip(1) {
+            let gitignore = self
+                .cache
+                .entry(directory.to_path_buf())
+                .or_insert_with(|| GitignoreMatcher::load(directory));
+
+            if let Some(gitignore) = gitignore {
+                let matched = gitignore.matched(&absolute_path, is_directory);
+                if matched.is_ignore() {
+                    return true;
+                }
+                if matched.is_whitelist() {
+                    return false;
+                }
+            }
+
+            if directory.join(".git").exists() {
+                break;
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_is_ignored() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("sub/deeper")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n*.log\n!keep.log\n").unwrap();
+        fs::write(
+            root.join("sub/.gitignore"),
+            "local.txt\n/anchored.txt\n!*.log\n",
+        )
+        .unwrap();
+
+        let mut matcher = GitignoreMatcher::new();
+
+        // Patterns from the root .gitignore.
+        assert!(!matcher.is_ignored(&root.join("a.txt"), false));
+        assert!(matcher.is_ignored(&root.join("b.log"), false));
+        assert!(!matcher.is_ignored(&root.join("keep.log"), false));
+        assert!(matcher.is_ignored(&root.join("target"), true));
+        assert!(matcher.is_ignored(&root.join("sub/deeper/target"), true));
+
+        // Directory-only patterns do not match files.
+        assert!(!matcher.is_ignored(&root.join("sub/target"), false));
+
+        // Patterns from the nested .gitignore.
+        assert!(matcher.is_ignored(&root.join("sub/local.txt"), false));
+        assert!(matcher.is_ignored(&root.join("sub/deeper/local.txt"), false));
+        assert!(matcher.is_ignored(&root.join("sub/anchored.txt"), false));
+        assert!(!matcher.is_ignored(&root.join("sub/deeper/anchored.txt"), false));
+        assert!(!matcher.is_ignored(&root.join("anchored.txt"), false));
+        assert!(!matcher.is_ignored(&root.join("local.txt"), false));
+
+        // The closest .gitignore wins.
+        assert!(!matcher.is_ignored(&root.join("sub/c.log"), false));
+    }
+
+    #[test]
+    fn test_is_ignored_stops_at_repository_root() {
+        let directory = tempfile::tempdir().unwrap();
+        let outer = directory.path();
+        fs::write(outer.join(".gitignore"), "*.txt\n").unwrap();
+        fs::create_dir_all(outer.join("repository/.git")).unwrap();
+
+        let mut matcher = GitignoreMatcher::new();
+        assert!(matcher.is_ignored(&outer.join("a.txt"), false));
+        assert!(!matcher.is_ignored(&outer.join("repository/a.txt"), false));
+    }
+}
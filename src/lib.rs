@@ -0,0 +1,20 @@
+//! Whitespace formatter and linter for text files and source code files.
+//!
+//! The command line utility `whitespace-format` is built on top of this library.
+//! Embedders can format in-memory data or streams with the same options
+//! as the command line utility, see [`core::format_reader`].
+
+// Modules
+pub mod change;
+pub mod cli;
+pub mod config;
+pub mod core;
+pub mod diff;
+pub mod discover;
+pub mod error;
+pub mod exit;
+pub mod gitignore;
+pub mod parallel;
+pub mod read_ahead;
+pub mod report;
+pub mod writer;
@@ -1,21 +1,44 @@
-// Modules
-mod change;
-mod cli;
-mod core;
-mod discover;
-mod error;
-mod writer;
-
 // Internal imports
-use crate::change::Change;
-use crate::cli::ColoredOutputMode;
-use crate::cli::CommandLineArguments;
+use whitespace_format::change::Change;
+use whitespace_format::cli::ColoredOutputMode;
+use whitespace_format::cli::CommandLineArguments;
+use whitespace_format::cli::OutputFormat;
+use whitespace_format::config;
+use whitespace_format::core;
+use whitespace_format::core::FormatResult;
+use whitespace_format::core::NewLineMarkerCounts;
+use whitespace_format::core::Options;
+use whitespace_format::core::RunSettings;
+use whitespace_format::core::SkipReason;
+use whitespace_format::diff;
+use whitespace_format::diff::DiffStat;
+use whitespace_format::discover;
+use whitespace_format::error::die;
+use whitespace_format::error::print_warning;
+use whitespace_format::error::Error;
+use whitespace_format::exit::ExitCode;
+use whitespace_format::parallel;
+use whitespace_format::read_ahead::ReadAhead;
+use whitespace_format::report;
+use whitespace_format::report::FileRecord;
+use whitespace_format::report::Summary;
 
 // Library imports
 use clap::Parser;
 use colored::Colorize;
+use globset::GlobSet;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::io;
 use std::path::Path;
+use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
 
 /// Returns "1 file" or "N files" if N > 1.
 fn file_count(number_of_files: usize) -> String {
@@ -27,16 +50,21 @@ fn file_count(number_of_files: usize) -> String {
 }
 
 /// Reports the number of changes and unchanged files.
-fn print_change_report_and_exit(
+fn print_change_report(
     number_of_changed_files: usize,
     number_of_unchanged_files: usize,
+    number_of_skipped_files: usize,
+    change_counts: &BTreeMap<&'static str, usize>,
     check_only: bool,
-) -> ! {
-    if check_only && number_of_changed_files > 0 {
-        println!("{}", "Oh no! 💥 💔 💥".bold());
-    } else {
-        println!("{}", "All done! ✨ 🍰 ✨".bold());
-    }
+    ascii: bool,
+) {
+    let banner = match (check_only && number_of_changed_files > 0, ascii) {
+        (true, false) => "Oh no! 💥 💔 💥",
+        (true, true) => "Oh no!",
+        (false, false) => "All done! ✨ 🍰 ✨",
+        (false, true) => "All done!",
+    };
+    println!("{}", banner.bold());
 
     let check_only_word = if check_only { " would be " } else { " " };
 
@@ -55,29 +83,158 @@ fn print_change_report_and_exit(
 
     if number_of_unchanged_files > 0 {
         print!(
-            "{}{}{}",
+            "{}{}left unchanged",
             file_count(number_of_unchanged_files).blue(),
-            check_only_word,
-            "left unchanged"
+            check_only_word
         );
     }
-    if number_of_changed_files > 0 || number_of_unchanged_files > 0 {
-        if number_of_unchanged_files > 0 {
-            println!("{}", ".");
+
+    if (number_of_changed_files > 0 || number_of_unchanged_files > 0) && number_of_skipped_files > 0
+    {
+        print!(", ");
+    }
+
+    if number_of_skipped_files > 0 {
+        print!("{} skipped", file_count(number_of_skipped_files).yellow());
+    }
+
+    if number_of_changed_files > 0 || number_of_unchanged_files > 0 || number_of_skipped_files > 0 {
+        if number_of_unchanged_files > 0 || number_of_skipped_files > 0 {
+            println!(".");
         } else {
             println!("{}", ".".bold());
         }
     }
 
-    if check_only && number_of_changed_files > 0 {
-        process::exit(1);
+    if number_of_changed_files > 0 {
+        println!(
+            "{}",
+            report::change_tally(change_counts, number_of_changed_files)
+        );
+    }
+}
+
+/// One-line summary of the run for commit hooks.
+fn hook_summary(number_of_changed_files: usize, check_only: bool) -> String {
+    match (number_of_changed_files, check_only) {
+        (0, _) => "whitespace-format: clean".to_string(),
+        (1, true) => "whitespace-format: 1 file needs formatting (run without --check-only to fix)"
+            .to_string(),
+        (_, true) => format!(
+            "whitespace-format: {} need formatting (run without --check-only to fix)",
+            file_count(number_of_changed_files)
+        ),
+        (_, false) => format!(
+            "whitespace-format: reformatted {}",
+            file_count(number_of_changed_files)
+        ),
     }
+}
+
+/// Prints statistics about the run.
+fn print_stats(
+    number_of_lines: usize,
+    number_of_processed_files: usize,
+    option_counts: &BTreeMap<&'static str, usize>,
+    number_of_bytes_read: usize,
+    elapsed: Duration,
+) {
+    println!(
+        "Processed {} line(s) across {} file(s).",
+        number_of_lines, number_of_processed_files
+    );
+    for (option, number_of_files) in option_counts {
+        println!("{} affected {} file(s).", option, number_of_files);
+    }
+    println!("{}", throughput(number_of_bytes_read, elapsed));
+}
 
+/// Describes the number of bytes read in the given time and the throughput in MB/s.
+fn throughput(number_of_bytes: usize, elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64();
+    let megabytes_per_second = if seconds > 0.0 {
+        number_of_bytes as f64 / 1_000_000.0 / seconds
+    } else {
+        0.0
+    };
+    format!(
+        "Read {} byte(s) in {:.3} s ({:.2} MB/s).",
+        number_of_bytes, seconds, megabytes_per_second
+    )
+}
+
+/// Prints the time spent processing each file, the slowest files first,
+/// followed by the total.
+fn print_timings(timings: &mut [(&Path, Duration)]) {
+    timings.sort_by(|(_, a), (_, b)| b.cmp(a));
+    println!("Timings (slowest first):");
+    let mut total = Duration::ZERO;
+    for (file_path, processing_time) in timings.iter() {
+        println!(
+            "{:>10.3} ms  {}",
+            milliseconds(*processing_time),
+            file_path.display()
+        );
+        total += *processing_time;
+    }
+    println!(
+        "Total: {:.3} ms in {} file(s).",
+        milliseconds(total),
+        timings.len()
+    );
+}
+
+/// Converts a duration to fractional milliseconds.
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Prints the numbers of new line markers in each file and in total, and exits.
+fn print_new_line_marker_stats(file_paths: &[PathBuf]) -> ! {
+    let mut total = NewLineMarkerCounts::default();
+    for file_path in file_paths {
+        let counts =
+            core::count_file_new_line_markers(file_path).unwrap_or_else(|error| die(error));
+        println!("{}: {}.", file_path.display(), counts);
+        total += counts;
+    }
+    println!("Total: {} in {} file(s).", total, file_paths.len());
     process::exit(0);
 }
 
+/// Prints how many paths were skipped, grouped by the reason.
+fn print_skip_tallies(
+    skipped_symlinks: &[PathBuf],
+    skipped_special_files: &[PathBuf],
+    skip_reason_counts: &BTreeMap<String, usize>,
+) {
+    if !skipped_symlinks.is_empty() {
+        println!("Skipped {} symbolic link(s).", skipped_symlinks.len());
+    }
+    if !skipped_special_files.is_empty() {
+        println!(
+            "Skipped {} special file(s), e.g. named pipes or sockets.",
+            skipped_special_files.len()
+        );
+    }
+    for (skip_reason, number_of_files) in skip_reason_counts {
+        println!("Skipped {} file(s): {}.", number_of_files, skip_reason);
+    }
+}
+
+/// Exits the program. The exit code is non-zero if some files need to be formatted
+/// in check-only mode, or if some files have been formatted with `--fail-on-change`.
+fn exit(number_of_changed_files: usize, command_line_arguments: &CommandLineArguments) -> ! {
+    // With --verify, the files are checked only for a stable second pass.
+    let fail_on_change = (command_line_arguments.check_only && !command_line_arguments.verify)
+        || command_line_arguments.fail_on_change;
+    process::exit(
+        ExitCode::from_number_of_changed_files(number_of_changed_files, fail_on_change).code(),
+    );
+}
+
 /// Reports the formatting changes that was made or would be made to a file.
-fn print_changes(file_path: &Path, changes: Vec<Change>, check_only: bool) {
+fn print_changes(file_path: &Path, changes: Vec<Change>, check_only: bool, ascii: bool) {
     let check_only_word = if check_only {
         "Would reformat"
     } else {
@@ -89,8 +246,190 @@ fn print_changes(file_path: &Path, changes: Vec<Change>, check_only: bool) {
         " file ".red().bold(),
         file_path.display().to_string().bold()
     );
+    let bullet = if ascii { "->" } else { "↳" };
     for change in changes {
-        println!("  ↳ {}", change.to_string(check_only).blue());
+        println!("  {} {}", bullet, change.to_string(check_only).blue());
+    }
+}
+
+/// Formats the input, i.e., the standard input or the text given by `--text`,
+/// and writes the formatted content to the standard output.
+/// In check-only mode, the formatted content is not written.
+/// The report is written to the standard error output, so that it does not mix
+/// with the formatted content. The input is reported under the name `file_path`.
+/// Exits the program.
+fn process_input<R: io::Read>(
+    input: R,
+    file_path: &Path,
+    command_line_arguments: &CommandLineArguments,
+) -> ! {
+    let options = command_line_arguments.get_options().for_file(file_path);
+    let check_only = command_line_arguments.check_only;
+    let mut changes = if check_only {
+        core::format_reader(input, io::sink(), &options)
+    } else {
+        core::format_reader(input, io::stdout().lock(), &options)
+    }
+    .unwrap_or_else(|_| die(Error::CannotReadFile(file_path.display().to_string())));
+    if command_line_arguments.sort_changes_by_line {
+        report::sort_changes_by_line(&mut changes);
+    }
+
+    let number_of_changed_files = usize::from(!changes.is_empty());
+    let file_records: Vec<FileRecord> = if changes.is_empty() {
+        Vec::new()
+    } else {
+        vec![FileRecord::new(file_path, &changes, check_only)]
+    };
+    let summary = Summary {
+        check_only,
+        changed_files: number_of_changed_files,
+        unchanged_files: 1 - number_of_changed_files,
+        skipped_files: 0,
+    };
+    if !command_line_arguments.quiet {
+        match command_line_arguments.format {
+            OutputFormat::Human => {
+                for change in &changes {
+                    eprintln!("{}: {}", file_path.display(), change.to_string(check_only));
+                }
+            }
+            OutputFormat::Json => eprintln!("{}", report::to_json(&file_records, &summary)),
+            OutputFormat::Toml => eprint!("{}", report::to_toml(&file_records, &summary)),
+            OutputFormat::Csv => eprint!("{}", report::to_csv(&file_records)),
+        }
+    }
+    exit(number_of_changed_files, command_line_arguments);
+}
+
+/// Determines if a file passes the filters given by `--exclude`, `--include`,
+/// `--exclude-glob` and `--extensions`. The file is not read.
+fn is_file_selected(
+    file_path: &Path,
+    regex: &Regex,
+    include_globs: &GlobSet,
+    exclude_globs: &GlobSet,
+    command_line_arguments: &CommandLineArguments,
+) -> bool {
+    let file_paths = [file_path.to_path_buf()];
+    let file_paths = discover::exclude_files(&file_paths, regex);
+    let file_paths = discover::filter_by_globs(&file_paths, include_globs, exclude_globs);
+    let file_paths = discover::filter_by_extensions(
+        &file_paths,
+        &command_line_arguments.extensions,
+        command_line_arguments.include_files_without_extension,
+    );
+    !file_paths.is_empty()
+}
+
+/// Copies the standard input to the standard output unchanged, and exits.
+/// In check-only mode, nothing is written.
+fn pass_stdin_through(command_line_arguments: &CommandLineArguments) -> ! {
+    if !command_line_arguments.check_only {
+        io::copy(&mut io::stdin().lock(), &mut io::stdout().lock())
+            .unwrap_or_else(|_| die(Error::CannotReadFile("<stdin>".to_string())));
+    }
+    exit(0, command_line_arguments);
+}
+
+/// Prints a unified diff. Removed lines are red and added lines are green.
+fn print_diff(diff: &str) {
+    for line in diff.lines() {
+        if line.starts_with("---") || line.starts_with("+++") {
+            println!("{}", line.bold());
+        } else if line.starts_with("@@") {
+            println!("{}", line.cyan());
+        } else if line.starts_with('-') {
+            println!("{}", line.red());
+        } else if line.starts_with('+') {
+            println!("{}", line.green());
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Reports a file that was skipped.
+fn print_skipped(file_path: &Path, skip_reason: &SkipReason) {
+    println!(
+        "{}{}{}",
+        "Skipped file ".yellow(),
+        file_path.display().to_string().bold(),
+        format!(" ({}).", skip_reason).yellow()
+    );
+}
+
+/// Reports a symbolic link that was skipped when searching for files.
+fn print_skipped_symlink(file_path: &Path) {
+    println!(
+        "{}{}{}",
+        "Skipped symbolic link ".yellow(),
+        file_path.display().to_string().bold(),
+        " (symbolic links are not followed).".yellow()
+    );
+}
+
+/// Reads the formatting options from the configuration files that apply to some of the files
+/// instead of the configuration file of the paths, e.g. the ones in subdirectories.
+/// The options given on the command line override the ones from each file.
+fn read_nested_config_options(
+    file_config_paths: &[Option<PathBuf>],
+    config_path: &Option<PathBuf>,
+) -> BTreeMap<PathBuf, Options> {
+    let mut options_by_config: BTreeMap<PathBuf, Options> = BTreeMap::new();
+    for file_config_path in file_config_paths.iter().flatten() {
+        if Some(file_config_path) == config_path.as_ref()
+            || options_by_config.contains_key(file_config_path)
+        {
+            continue;
+        }
+        let command_line_arguments =
+            config::parse_with_config_file(file_config_path, env::args_os())
+                .unwrap_or_else(|error| die(error));
+        command_line_arguments.validate();
+        options_by_config.insert(
+            file_config_path.clone(),
+            command_line_arguments.get_options(),
+        );
+    }
+    options_by_config
+}
+
+/// Formats or checks the files, either one by one, or in parallel.
+/// The results are produced in the same order as the files.
+/// With `--max-violations`, the files are processed one by one, so that
+/// the files after the limit are never read.
+fn process_files<'a>(
+    files: &'a [PathBuf],
+    options: &'a [&'a Options],
+    settings: &'a RunSettings,
+    command_line_arguments: &CommandLineArguments,
+) -> Box<dyn Iterator<Item = Result<FormatResult, Error>> + 'a> {
+    let check_only = command_line_arguments.check_only;
+    let jobs = command_line_arguments.number_of_jobs();
+
+    if jobs > 1 && command_line_arguments.max_violations.is_none() {
+        Box::new(parallel::process_files(files, options, settings, check_only, jobs).into_iter())
+    } else if command_line_arguments.read_ahead > 0 {
+        // Prefetch the content of the files in the background.
+        Box::new(
+            ReadAhead::new(files, command_line_arguments.read_ahead)
+                .zip(options)
+                .map(move |((file_path, content), options)| match content {
+                    Ok(input_data) => core::process_file_content(
+                        &file_path,
+                        &input_data,
+                        options,
+                        settings,
+                        check_only,
+                    ),
+                    Err(_) => Err(Error::CannotReadFile(file_path.display().to_string())),
+                }),
+        )
+    } else {
+        Box::new(files.iter().zip(options).map(move |(file_path, options)| {
+            core::process_file(file_path, options, settings, check_only)
+        }))
     }
 }
 
@@ -136,49 +475,419 @@ fn set_colored_output_mode(colored_output_mode: &ColoredOutputMode) {
 ///    whitespace-format --help
 ///
 fn main() {
-    let command_line_arguments: CommandLineArguments = CommandLineArguments::parse();
+    let mut command_line_arguments: CommandLineArguments = CommandLineArguments::parse();
+
+    // Read the options from the configuration file that applies to the paths.
+    // The options given on the command line override the ones from the file.
+    let mut config_path: Option<PathBuf> = None;
+    if !command_line_arguments.no_config {
+        // The configuration file of the standard input is found by its file name, if given.
+        let config_search_paths = match &command_line_arguments.stdin_filename {
+            Some(stdin_filename) => vec![stdin_filename.clone()],
+            None => command_line_arguments.paths.clone(),
+        };
+        config_path = config::find_config_file_for_paths(&config_search_paths);
+        if let Some(config_path) = &config_path {
+            command_line_arguments = config::parse_with_config_file(config_path, env::args_os())
+                .unwrap_or_else(|error| die(error));
+        }
+    }
 
     command_line_arguments.validate();
 
+    // Listing the files that would be changed never changes them.
+    command_line_arguments.check_only |= command_line_arguments.list_only;
+
+    // Verifying formats the files in memory and checks that a second pass changes nothing.
+    if command_line_arguments.verify {
+        command_line_arguments.check_only = true;
+        command_line_arguments.assert_idempotent = true;
+    }
+
+    if command_line_arguments.dump_options_json {
+        let options = command_line_arguments.get_options();
+        println!("{}", serde_json::to_string_pretty(&options).unwrap());
+        process::exit(0);
+    }
+
     // Determine whether to use colors or not.
     set_colored_output_mode(&command_line_arguments.color);
 
+    // Format the text given on the command line.
+    if let Some(text) = &command_line_arguments.text {
+        process_input(
+            text.as_bytes(),
+            Path::new("<text>"),
+            &command_line_arguments,
+        );
+    }
+
     // Compile the regular expression specified by the --exclude command line parameter.
     // Fail early if the expression is invalid.
-    let regex = discover::compile_regular_expression(command_line_arguments.exclude.as_str());
+    let regex = discover::compile_regular_expression(command_line_arguments.exclude.as_str())
+        .unwrap_or_else(|error| die(error));
+
+    // Compile the glob patterns specified by --include and --exclude-glob command line parameters.
+    let include_globs =
+        discover::compile_globs(&command_line_arguments.include).unwrap_or_else(|error| die(error));
+    let exclude_globs = discover::compile_globs(&command_line_arguments.exclude_glob)
+        .unwrap_or_else(|error| die(error));
+
+    // Format the standard input if it is the only path.
+    if command_line_arguments.paths == [PathBuf::from("-")] {
+        match &command_line_arguments.stdin_filename {
+            Some(stdin_filename)
+                if !is_file_selected(
+                    stdin_filename,
+                    &regex,
+                    &include_globs,
+                    &exclude_globs,
+                    &command_line_arguments,
+                ) =>
+            {
+                pass_stdin_through(&command_line_arguments)
+            }
+            Some(stdin_filename) => {
+                process_input(io::stdin().lock(), stdin_filename, &command_line_arguments)
+            }
+            None => process_input(
+                io::stdin().lock(),
+                Path::new("<stdin>"),
+                &command_line_arguments,
+            ),
+        }
+    }
 
     // Discover all files given on the command line.
-    let all_files = discover::discover_files(
+    let discovered_files = discover::discover_files(
         &command_line_arguments.paths,
         command_line_arguments.follow_symlinks,
-    );
+        !command_line_arguments.no_respect_gitignore,
+        command_line_arguments.max_depth,
+        Some(&regex),
+        &command_line_arguments.exclude_dir,
+    )
+    .unwrap_or_else(|error| die(error));
+    let mut all_files = discovered_files.files;
+
+    // Add the files listed in the file given by the --files-from command line parameter.
+    if let Some(list_path) = &command_line_arguments.files_from {
+        all_files.extend(discover::read_file_list(list_path).unwrap_or_else(|error| die(error)));
+        all_files.sort_unstable();
+        all_files.dedup();
+    }
 
     // Exclude files that match the regular expression specified by the --excluded command line parameter.
     let filtered_files = discover::exclude_files(&all_files, &regex);
-    println!("Processing {} file(s)...", filtered_files.len());
 
-    // Process files one by one.
-    let mut number_of_changed_files: usize = 0;
-    for file_path in &filtered_files {
-        let changes = core::process_file(
-            file_path,
-            &command_line_arguments.get_options(),
-            command_line_arguments.check_only,
-        );
+    // Keep only files selected by the glob patterns.
+    let filtered_files = discover::filter_by_globs(&filtered_files, &include_globs, &exclude_globs);
+
+    // Keep only files with the extensions given by the --extensions command line parameter.
+    let filtered_files = discover::filter_by_extensions(
+        &filtered_files,
+        &command_line_arguments.extensions,
+        command_line_arguments.include_files_without_extension,
+    );
 
-        if !changes.is_empty() {
+    // Keep only files modified after the time given by the --since or --newer-than
+    // command line parameters.
+    let modified_after: Option<SystemTime> = if let Some(since) = command_line_arguments.since {
+        Some(
+            SystemTime::now()
+                .checked_sub(since)
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+        )
+    } else {
+        command_line_arguments
+            .newer_than
+            .as_ref()
+            .map(|reference_path| {
+                fs::metadata(reference_path)
+                    .and_then(|metadata| metadata.modified())
+                    .unwrap_or_else(|_| {
+                        die(Error::FileNotFound(reference_path.display().to_string()))
+                    })
+            })
+    };
+    let filtered_files = match modified_after {
+        Some(time) => discover::filter_modified_after(&filtered_files, time),
+        None => filtered_files,
+    };
+
+    // A configuration file closer to a file than the one of the paths sets its formatting options.
+    let file_config_paths: Vec<Option<PathBuf>> = if command_line_arguments.no_config {
+        vec![None; filtered_files.len()]
+    } else {
+        filtered_files
+            .iter()
+            .map(|file_path| config::find_config_file(file_path))
+            .collect()
+    };
+    let nested_config_options = read_nested_config_options(&file_config_paths, &config_path);
+    let options = command_line_arguments.get_options();
+    let file_options: Vec<&Options> = file_config_paths
+        .iter()
+        .map(|file_config_path| {
+            file_config_path
+                .as_ref()
+                .and_then(|file_config_path| nested_config_options.get(file_config_path))
+                .unwrap_or(&options)
+        })
+        .collect();
+
+    // Print the plan without reading the content of the files.
+    if command_line_arguments.dry_plan {
+        println!("{}", report::plan_to_json(&filtered_files, &file_options));
+        process::exit(0);
+    }
+
+    // Count the new line markers without formatting the files.
+    if command_line_arguments.new_line_marker_stats {
+        print_new_line_marker_stats(&filtered_files);
+    }
+
+    let list_change_types = command_line_arguments.list_change_types;
+    let hook_output = command_line_arguments.hook_output;
+    let list_only = command_line_arguments.list_only;
+    let quiet = command_line_arguments.quiet;
+    let is_report_suppressed = list_change_types || hook_output || list_only || quiet;
+    // The file records are needed for the machine-readable reports.
+    let collects_file_records = command_line_arguments.format != OutputFormat::Human
+        || command_line_arguments.output_report.is_some();
+    if command_line_arguments.verbose
+        && command_line_arguments.format == OutputFormat::Human
+        && !is_report_suppressed
+    {
+        if let Some(config_path) = &config_path {
+            println!("Using configuration file {}.", config_path.display());
+        }
+        for nested_config_path in nested_config_options.keys() {
+            println!(
+                "Using configuration file {} for the files in its directory.",
+                nested_config_path.display()
+            );
+        }
+        for skipped_symlink in &discovered_files.skipped_symlinks {
+            print_skipped_symlink(skipped_symlink);
+        }
+    }
+    if command_line_arguments.format == OutputFormat::Human && !is_report_suppressed {
+        println!("Processing {} file(s)...", filtered_files.len());
+    }
+
+    // Process the files.
+    let settings = command_line_arguments.get_run_settings();
+    let mut number_of_changed_files: usize = 0;
+    let mut number_of_skipped_files: usize = 0;
+    let mut skip_reason_counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut file_records: Vec<FileRecord> = Vec::new();
+    let mut change_types: BTreeSet<&'static str> = BTreeSet::new();
+    let mut change_counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut option_counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut hashes: String = String::new();
+    let mut number_of_lines: usize = 0;
+    let mut number_of_bytes_read: usize = 0;
+    let mut timings: Vec<(&Path, Duration)> = Vec::new();
+    let mut diff_stats: Vec<(String, DiffStat)> = Vec::new();
+    let mut number_of_examined_files: usize = 0;
+    let mut is_check_cut_short = false;
+    let start_time = Instant::now();
+    let mut results = process_files(
+        &filtered_files,
+        &file_options,
+        &settings,
+        &command_line_arguments,
+    );
+    for file_path in filtered_files.iter() {
+        // Stop before processing the next file if enough violations have been found.
+        if command_line_arguments
+            .max_violations
+            .is_some_and(|max_violations| number_of_changed_files >= max_violations as usize)
+        {
+            is_check_cut_short = true;
+            break;
+        }
+        let mut result = results
+            .next()
+            .expect("There is one result for each file.")
+            .unwrap_or_else(|error| die(error));
+        if command_line_arguments.sort_changes_by_line {
+            report::sort_changes_by_line(&mut result.changes);
+        }
+        number_of_examined_files += 1;
+        number_of_lines += result.number_of_lines;
+        number_of_bytes_read += result.input_size;
+        if let Some(processing_time) = result.processing_time {
+            timings.push((file_path, processing_time));
+        }
+        if let Some(hash) = &result.hash {
+            hashes.push_str(&format!("{}  {}\n", hash, file_path.display()));
+        }
+        if let Some(explanation) = &result.line_endings_explanation {
+            println!(
+                "{}: {}",
+                file_path.display().to_string().bold(),
+                explanation
+            );
+        }
+        if let Some(skip_reason) = result.skipped {
+            number_of_skipped_files += 1;
+            *skip_reason_counts
+                .entry(skip_reason.to_string())
+                .or_default() += 1;
+            if collects_file_records {
+                file_records.push(FileRecord::skipped(file_path, &skip_reason));
+            }
+            if is_report_suppressed {
+                continue;
+            }
+            if command_line_arguments.format == OutputFormat::Human {
+                print_skipped(file_path, &skip_reason);
+            }
+        } else if !result.changes.is_empty() {
             number_of_changed_files += 1;
-            print_changes(file_path, changes, command_line_arguments.check_only);
+            report::count_change_types(&mut change_counts, &result.changes);
+            report::count_files_by_option(&mut option_counts, &result.changes);
+            if let Some(diff_stat) = result.diff_stat {
+                diff_stats.push((file_path.display().to_string(), diff_stat));
+            }
+            if list_change_types {
+                report::add_change_types(&mut change_types, &result.changes);
+            }
+            if list_only {
+                let separator = if command_line_arguments.null {
+                    '\0'
+                } else {
+                    '\n'
+                };
+                print!("{}{}", file_path.display(), separator);
+            }
+            if collects_file_records {
+                file_records.push(
+                    FileRecord::new(
+                        file_path,
+                        &result.changes,
+                        command_line_arguments.check_only,
+                    )
+                    .with_diff(result.diff.clone()),
+                );
+            }
+            if is_report_suppressed {
+                continue;
+            }
+            if command_line_arguments.format == OutputFormat::Human {
+                print_changes(
+                    file_path,
+                    result.changes,
+                    command_line_arguments.check_only,
+                    command_line_arguments.ascii,
+                );
+                if let Some(diff) = result.diff {
+                    print_diff(&diff);
+                }
+            }
         }
     }
 
-    let number_of_unchanged_files = filtered_files.len() - number_of_changed_files;
+    let elapsed = start_time.elapsed();
+    let number_of_unchanged_files =
+        number_of_examined_files - number_of_changed_files - number_of_skipped_files;
+    let summary = Summary {
+        check_only: command_line_arguments.check_only,
+        changed_files: number_of_changed_files,
+        unchanged_files: number_of_unchanged_files,
+        skipped_files: number_of_skipped_files,
+    };
 
-    print_change_report_and_exit(
-        number_of_changed_files,
-        number_of_unchanged_files,
-        command_line_arguments.check_only,
-    );
+    if list_only || quiet {
+        // Only the paths of the files are printed, if any.
+    } else if hook_output {
+        println!(
+            "{}",
+            hook_summary(number_of_changed_files, command_line_arguments.check_only)
+        );
+    } else if list_change_types {
+        for change_type in change_types {
+            println!("{}", change_type);
+        }
+    } else {
+        match command_line_arguments.format {
+            OutputFormat::Human => print_change_report(
+                number_of_changed_files,
+                number_of_unchanged_files,
+                number_of_skipped_files,
+                &change_counts,
+                command_line_arguments.check_only,
+                command_line_arguments.ascii,
+            ),
+            OutputFormat::Json | OutputFormat::Toml | OutputFormat::Csv => {
+                if command_line_arguments.format == OutputFormat::Json {
+                    println!("{}", report::to_json(&file_records, &summary));
+                } else if command_line_arguments.format == OutputFormat::Toml {
+                    print!("{}", report::to_toml(&file_records, &summary));
+                } else {
+                    print!("{}", report::to_csv(&file_records));
+                }
+            }
+        }
+    }
+
+    if is_check_cut_short {
+        print_warning(&format!(
+            "Stopped after finding {} that need formatting; {} not checked.",
+            file_count(number_of_changed_files),
+            file_count(filtered_files.len() - number_of_examined_files),
+        ));
+    }
+
+    if command_line_arguments.stat && !diff_stats.is_empty() {
+        print!("{}", diff::render_diff_stat(&diff_stats));
+    }
+
+    if command_line_arguments.stats && command_line_arguments.format == OutputFormat::Human {
+        print_stats(
+            number_of_lines,
+            number_of_changed_files + number_of_unchanged_files,
+            &option_counts,
+            number_of_bytes_read,
+            elapsed,
+        );
+    }
+
+    if command_line_arguments.timings && command_line_arguments.format == OutputFormat::Human {
+        print_timings(&mut timings);
+    }
+
+    if (command_line_arguments.stats || command_line_arguments.verbose)
+        && command_line_arguments.format == OutputFormat::Human
+        && !is_report_suppressed
+    {
+        print_skip_tallies(
+            &discovered_files.skipped_symlinks,
+            &discovered_files.skipped_special_files,
+            &skip_reason_counts,
+        );
+    }
+
+    // Print or save the hashes of the formatted files.
+    match &command_line_arguments.print_hashes {
+        Some(Some(hashes_path)) => fs::write(hashes_path, &hashes)
+            .unwrap_or_else(|_| die(Error::CannotWriteFile(hashes_path.display().to_string()))),
+        Some(None) => print!("{}", hashes),
+        None => {}
+    }
+
+    // Save the JSON report given by the --output-report command line parameter.
+    if let Some(report_path) = &command_line_arguments.output_report {
+        fs::write(
+            report_path,
+            format!("{}\n", report::to_json(&file_records, &summary)),
+        )
+        .unwrap_or_else(|_| die(Error::CannotWriteFile(report_path.display().to_string())));
+    }
+
+    exit(number_of_changed_files, &command_line_arguments);
 }
 
 #[cfg(test)]
@@ -195,4 +904,38 @@ mod tests {
         assert_eq!(file_count(5), String::from("5 files"));
         assert_eq!(file_count(6), String::from("6 files"));
     }
+
+    #[test]
+    fn test_hook_summary() {
+        assert_eq!(hook_summary(0, true), "whitespace-format: clean");
+        assert_eq!(hook_summary(0, false), "whitespace-format: clean");
+        assert_eq!(
+            hook_summary(1, true),
+            "whitespace-format: 1 file needs formatting (run without --check-only to fix)"
+        );
+        assert_eq!(
+            hook_summary(3, true),
+            "whitespace-format: 3 files need formatting (run without --check-only to fix)"
+        );
+        assert_eq!(
+            hook_summary(1, false),
+            "whitespace-format: reformatted 1 file"
+        );
+        assert_eq!(
+            hook_summary(3, false),
+            "whitespace-format: reformatted 3 files"
+        );
+    }
+
+    #[test]
+    fn test_throughput() {
+        assert_eq!(
+            throughput(3_000_000, Duration::from_millis(1500)),
+            "Read 3000000 byte(s) in 1.500 s (2.00 MB/s)."
+        );
+        assert_eq!(
+            throughput(0, Duration::ZERO),
+            "Read 0 byte(s) in 0.000 s (0.00 MB/s)."
+        );
+    }
 }
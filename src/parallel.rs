@@ -0,0 +1,126 @@
+// Library imports
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+// Internal imports
+use crate::core::process_file;
+use crate::core::FormatResult;
+use crate::core::Options;
+use crate::core::RunSettings;
+use crate::error::Error;
+
+/// Formats or checks files using a pool of `jobs` threads.
+///
+/// Each thread repeatedly takes the next unprocessed file from the list.
+/// The returned list of results is in the same order as the list of files,
+/// regardless of the order in which the threads finished processing them.
+/// A failure to process a file does not stop processing of the other files.
+/// Each file is formatted with its own options, given in the same order as the files.
+pub fn process_files(
+    files: &[PathBuf],
+    options: &[&Options],
+    settings: &RunSettings,
+    check_only: bool,
+    jobs: usize,
+) -> Vec<Result<FormatResult, Error>> {
+    let next_file_index = AtomicUsize::new(0);
+
+    let mut indexed_results: Vec<(usize, Result<FormatResult, Error>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut indexed_results: Vec<(usize, Result<FormatResult, Error>)> = Vec::new();
+                    loop {
+                        let index = next_file_index.fetch_add(1, Ordering::Relaxed);
+                        if index >= files.len() {
+                            break;
+                        }
+                        indexed_results.push((
+                            index,
+                            process_file(&files[index], options[index], settings, check_only),
+                        ));
+                    }
+                    indexed_results
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("Worker thread panicked."))
+            .collect()
+    });
+
+    indexed_results.sort_unstable_by_key(|(index, _)| *index);
+    indexed_results
+        .into_iter()
+        .map(|(_, result)| result)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::CommandLineArguments;
+    use crate::discover::discover_files;
+    use clap::Parser;
+    use std::fs;
+
+    #[test]
+    fn test_process_files_same_results_for_any_number_of_jobs() {
+        let directory = tempfile::tempdir().unwrap();
+        for i in 0..100 {
+            let content = match i % 4 {
+                0 => "hello world\n".to_string(),
+                1 => format!("line {}  \r\n\tindented\r\n", i),
+                2 => format!("{}\n\n\n", i),
+                _ => format!("mac\rline {}\x0C", i),
+            };
+            fs::write(directory.path().join(format!("file_{:03}.txt", i)), content).unwrap();
+        }
+
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--new-line-marker=linux",
+            "--normalize-new-line-markers",
+            "--remove-trailing-whitespace",
+            "--remove-trailing-empty-lines",
+            "--replace-tabs-with-spaces=4",
+            "--normalize-non-standard-whitespace=remove",
+            directory.path().to_str().unwrap(),
+        ]);
+        let options = command_line_arguments.get_options();
+        let settings = command_line_arguments.get_run_settings();
+        let files = discover_files(&command_line_arguments.paths, false, true, None, None, &[])
+            .unwrap()
+            .files;
+        assert_eq!(files.len(), 100);
+        let file_options = vec![&options; files.len()];
+
+        let sequential: Vec<Result<FormatResult, Error>> = files
+            .iter()
+            .map(|file_path| process_file(file_path, &options, &settings, true))
+            .collect();
+        let one_job = process_files(&files, &file_options, &settings, true, 1);
+        let eight_jobs = process_files(&files, &file_options, &settings, true, 8);
+
+        assert_eq!(one_job, sequential);
+        assert_eq!(eight_jobs, sequential);
+        assert_eq!(
+            sequential
+                .iter()
+                .filter(|result| !result.as_ref().unwrap().changes.is_empty())
+                .count(),
+            75
+        );
+    }
+
+    #[test]
+    fn test_process_files_no_files() {
+        let command_line_arguments = CommandLineArguments::parse_from(["whitespace-format", "."]);
+        let settings = command_line_arguments.get_run_settings();
+        assert!(process_files(&[], &[], &settings, true, 4).is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+// Library imports
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+
+/// Content of a file, or the error that occurred while reading it.
+pub type FileContent = (PathBuf, io::Result<Vec<u8>>);
+
+/// Reads files in a background thread ahead of the consumer.
+///
+/// The files are produced in exactly the same order as they were given.
+/// At most `depth` files are buffered in memory at any time (plus the one
+/// that is being read). This decouples I/O from formatting without the
+/// complexity of processing the files in parallel.
+pub struct ReadAhead {
+    receiver: mpsc::Receiver<FileContent>,
+}
+
+impl ReadAhead {
+    /// Starts a background thread that prefetches the content of the files.
+    pub fn new(files: &[PathBuf], depth: usize) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(depth);
+        let files: Vec<PathBuf> = Vec::from(files);
+        thread::spawn(move || {
+            for file_path in files {
+                let content = fs::read(&file_path);
+                if sender.send((file_path, content)).is_err() {
+                    // The consumer went away. There is no point in reading more files.
+                    break;
+                }
+            }
+        });
+        ReadAhead { receiver }
+    }
+}
+
+impl Iterator for ReadAhead {
+    type Item = FileContent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::CommandLineArguments;
+    use crate::core::process_file;
+    use crate::core::process_file_content;
+    use crate::discover::discover_files;
+    use clap::Parser;
+
+    #[test]
+    fn test_read_ahead_preserves_order_and_content() {
+        let files = discover_files(&[PathBuf::from("src/")], false, true, None, None, &[])
+            .unwrap()
+            .files;
+        let prefetched: Vec<FileContent> = ReadAhead::new(&files, 2).collect();
+
+        assert_eq!(prefetched.len(), files.len());
+        for (file_path, (prefetched_path, content)) in files.iter().zip(prefetched) {
+            assert_eq!(file_path, &prefetched_path);
+            assert_eq!(content.unwrap(), fs::read(file_path).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_read_ahead_reports_read_errors() {
+        let files = vec![
+            PathBuf::from("src/main.rs"),
+            PathBuf::from("src/this_file_does_not_exist.rs"),
+        ];
+        let prefetched: Vec<FileContent> = ReadAhead::new(&files, 1).collect();
+
+        assert_eq!(prefetched.len(), 2);
+        assert!(prefetched[0].1.is_ok());
+        assert!(prefetched[1].1.is_err());
+    }
+
+    #[test]
+    fn test_read_ahead_same_changes() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--new-line-marker=windows",
+            "--normalize-new-line-markers",
+            "--replace-tabs-with-spaces=2",
+            "src/",
+        ]);
+        let options = command_line_arguments.get_options();
+        let settings = command_line_arguments.get_run_settings();
+        let files = discover_files(&command_line_arguments.paths, false, true, None, None, &[])
+            .unwrap()
+            .files;
+
+        for (file_path, content) in ReadAhead::new(&files, 3) {
+            assert_eq!(
+                process_file_content(&file_path, &content.unwrap(), &options, &settings, true),
+                process_file(&file_path, &options, &settings, true),
+            );
+        }
+    }
+}
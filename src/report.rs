@@ -0,0 +1,647 @@
+// Library imports
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+// Internal imports
+use crate::change::Change;
+use crate::core::Options;
+use crate::core::SkipReason;
+
+/// Machine-readable representation of a single change.
+#[derive(Serialize, Debug)]
+pub struct ChangeRecord {
+    /// Line number of the change.
+    line: usize,
+
+    /// Column of the change. Present only for changes within a line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+
+    /// Stable name of the type of the change.
+    #[serde(rename = "type")]
+    change_type: &'static str,
+
+    /// Human-readable description of the change.
+    message: String,
+}
+
+/// Machine-readable representation of the changes made (or that would be made) to a file.
+#[derive(Serialize, Debug)]
+pub struct FileRecord {
+    file: String,
+    changes: Vec<ChangeRecord>,
+
+    /// Reason why the file was skipped. Present only for skipped files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skipped: Option<String>,
+
+    /// Unified diff of the changes. Present only with `--show-diff`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,
+}
+
+impl FileRecord {
+    /// Constructor.
+    pub fn new(file_path: &Path, changes: &[Change], check_only: bool) -> Self {
+        FileRecord {
+            file: file_path.display().to_string(),
+            changes: changes
+                .iter()
+                .map(|change| ChangeRecord {
+                    line: change.line_number(),
+                    column: change.column(),
+                    change_type: change.change_type().name(),
+                    message: change.change_type().to_string(check_only),
+                })
+                .collect(),
+            skipped: None,
+            diff: None,
+        }
+    }
+
+    /// Attaches a unified diff of the changes to the record.
+    pub fn with_diff(mut self, diff: Option<String>) -> Self {
+        self.diff = diff;
+        self
+    }
+
+    /// Constructor for a file that was skipped.
+    pub fn skipped(file_path: &Path, skip_reason: &SkipReason) -> Self {
+        FileRecord {
+            file: file_path.display().to_string(),
+            changes: Vec::new(),
+            skipped: Some(skip_reason.to_string()),
+            diff: None,
+        }
+    }
+}
+
+/// Machine-readable summary of the whole run.
+#[derive(Serialize, Debug)]
+pub struct Summary {
+    pub check_only: bool,
+    pub changed_files: usize,
+    pub unchanged_files: usize,
+    pub skipped_files: usize,
+}
+
+/// A single element of the JSON report.
+/// The report is an array of file records followed by a single summary.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ReportEntry<'a> {
+    File(&'a FileRecord),
+    Summary { summary: &'a Summary },
+}
+
+/// Serializes the file records and the summary as a JSON array.
+/// The summary is the last element of the array.
+pub fn to_json(file_records: &[FileRecord], summary: &Summary) -> String {
+    let mut entries: Vec<ReportEntry> = file_records.iter().map(ReportEntry::File).collect();
+    entries.push(ReportEntry::Summary { summary });
+    serde_json::to_string_pretty(&entries).expect("Serialization of the report cannot fail.")
+}
+
+/// The TOML report. Unlike JSON, a TOML document cannot be an array,
+/// so the summary and the file records are stored under separate keys.
+#[derive(Serialize)]
+struct TomlReport<'a> {
+    summary: &'a Summary,
+    files: &'a [FileRecord],
+}
+
+/// Serializes the file records and the summary as a TOML document.
+/// The summary is stored in the `[summary]` table and the file records
+/// in the `[[files]]` array of tables.
+pub fn to_toml(file_records: &[FileRecord], summary: &Summary) -> String {
+    let report = TomlReport {
+        summary,
+        files: file_records,
+    };
+    toml::to_string(&report).expect("Serialization of the report cannot fail.")
+}
+
+/// Serializes the changes in the file records as CSV with the header
+/// `path,line,change_type,detail` and one row per change.
+/// Skipped files and the summary are not included.
+pub fn to_csv(file_records: &[FileRecord]) -> String {
+    let mut csv = String::from("path,line,change_type,detail\n");
+    for file_record in file_records {
+        for change in &file_record.changes {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                csv_field(&file_record.file),
+                change.line,
+                change.change_type,
+                csv_field(&change.message)
+            ));
+        }
+    }
+    csv
+}
+
+/// Quotes a CSV field if it contains a comma, a double quote or a line break.
+/// Double quotes inside a quoted field are doubled.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A single element of the processing plan: a file and the options that apply to it.
+#[derive(Serialize)]
+struct PlanEntry {
+    file: String,
+    options: Options,
+}
+
+/// Serializes the processing plan as a JSON array.
+/// Each element contains a file and the formatting options that apply to it.
+/// The options of each file are given in the same order as the files.
+pub fn plan_to_json(files: &[PathBuf], options: &[&Options]) -> String {
+    let entries: Vec<PlanEntry> = files
+        .iter()
+        .zip(options)
+        .map(|(file_path, options)| PlanEntry {
+            file: file_path.display().to_string(),
+            options: options.for_file(file_path),
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).expect("Serialization of the plan cannot fail.")
+}
+
+/// Sorts changes by line number. Changes on the same line keep their order.
+/// Changes at the end of the file, e.g. removing trailing empty lines,
+/// are made last, but they can refer to earlier lines.
+pub fn sort_changes_by_line(changes: &mut [Change]) {
+    changes.sort_by_key(Change::line_number);
+}
+
+/// Adds the names of the types of the changes to a set of names.
+/// The set is sorted, so the names are listed in a stable order.
+pub fn add_change_types(change_types: &mut BTreeSet<&'static str>, changes: &[Change]) {
+    for change in changes {
+        change_types.insert(change.change_type().name());
+    }
+}
+
+/// Counts the changes by the human-readable labels of their types.
+pub fn count_change_types(change_counts: &mut BTreeMap<&'static str, usize>, changes: &[Change]) {
+    for change in changes {
+        *change_counts
+            .entry(change.change_type().label())
+            .or_insert(0) += 1;
+    }
+}
+
+/// Counts the files by the command line options responsible for their changes.
+/// A file counts once toward each option that changed it.
+pub fn count_files_by_option(
+    option_counts: &mut BTreeMap<&'static str, usize>,
+    changes: &[Change],
+) {
+    let options: BTreeSet<&'static str> = changes
+        .iter()
+        .map(|change| change.change_type().option())
+        .collect();
+    for option in options {
+        *option_counts.entry(option).or_insert(0) += 1;
+    }
+}
+
+/// Summarizes the total number of changes and breaks it down by the types of the changes,
+/// e.g. `5 changes across 2 files: 3 trailing whitespace, 2 tab replacement(s).`
+/// The most frequent types of changes are listed first.
+pub fn change_tally(
+    change_counts: &BTreeMap<&'static str, usize>,
+    number_of_files: usize,
+) -> String {
+    let number_of_changes: usize = change_counts.values().sum();
+    let mut counts: Vec<(&&'static str, &usize)> = change_counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    format!(
+        "{} change{} across {} file{}: {}.",
+        number_of_changes,
+        if number_of_changes == 1 { "" } else { "s" },
+        number_of_files,
+        if number_of_files == 1 { "" } else { "s" },
+        counts
+            .iter()
+            .map(|(name, count)| format!("{} {}", count, name))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::change::ChangeType;
+    use crate::cli::CommandLineArguments;
+    use crate::core::process_file_content;
+    use crate::core::NewLineMarker;
+    use clap::Parser;
+    use serde_json::Value;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_to_json() {
+        let changes = vec![
+            Change::new(1, ChangeType::RemovedTrailingWhitespace),
+            Change::new(
+                2,
+                ChangeType::ReplacedNewLineMarker(NewLineMarker::Windows, NewLineMarker::Linux),
+            ),
+            Change::new(3, ChangeType::ReplacedTabWithSpaces(4)).with_column(5),
+            Change::new(4, ChangeType::NewLineMarkerAddedToEndOfFile),
+        ];
+        let file_records = vec![FileRecord::new(
+            &PathBuf::from("src/foo.txt"),
+            &changes,
+            true,
+        )];
+        let summary = Summary {
+            check_only: true,
+            changed_files: 1,
+            unchanged_files: 5,
+            skipped_files: 0,
+        };
+
+        let json: Value = serde_json::from_str(&to_json(&file_records, &summary)).unwrap();
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0]["file"], "src/foo.txt");
+        let changes = entries[0]["changes"].as_array().unwrap();
+        assert_eq!(changes.len(), 4);
+        assert_eq!(changes[0]["line"], 1);
+        assert_eq!(changes[0]["type"], "RemovedTrailingWhitespace");
+        assert_eq!(
+            changes[0]["message"],
+            "Trailing whitespace would be removed."
+        );
+        assert_eq!(changes[1]["line"], 2);
+        assert_eq!(changes[1]["type"], "ReplacedNewLineMarker");
+        assert_eq!(
+            changes[1]["message"],
+            "New line marker '\\r\\n' would be replaced by '\\n'."
+        );
+        assert_eq!(changes[2]["line"], 3);
+        assert_eq!(changes[2]["column"], 5);
+        assert_eq!(changes[2]["type"], "ReplacedTabWithSpaces");
+        assert!(changes[3].get("column").is_none());
+        assert_eq!(changes[3]["line"], 4);
+        assert_eq!(changes[3]["type"], "NewLineMarkerAddedToEndOfFile");
+
+        assert_eq!(entries[1]["summary"]["check_only"], true);
+        assert_eq!(entries[1]["summary"]["changed_files"], 1);
+        assert_eq!(entries[1]["summary"]["unchanged_files"], 5);
+        assert_eq!(entries[1]["summary"]["skipped_files"], 0);
+        assert!(entries[0].get("skipped").is_none());
+    }
+
+    #[test]
+    fn test_to_json_skipped_file() {
+        let file_records = vec![FileRecord::skipped(
+            &PathBuf::from("generated.txt"),
+            &SkipReason::IgnoreFileDirective,
+        )];
+        let summary = Summary {
+            check_only: false,
+            changed_files: 0,
+            unchanged_files: 0,
+            skipped_files: 1,
+        };
+
+        let json: Value = serde_json::from_str(&to_json(&file_records, &summary)).unwrap();
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["file"], "generated.txt");
+        assert_eq!(entries[0]["changes"].as_array().unwrap().len(), 0);
+        assert_eq!(
+            entries[0]["skipped"],
+            "whitespace-format: ignore-file directive"
+        );
+        assert_eq!(entries[1]["summary"]["skipped_files"], 1);
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--remove-trailing-whitespace",
+            "--replace-tabs-with-spaces=2",
+            "file.txt",
+        ]);
+        let options = command_line_arguments.get_options();
+        let settings = command_line_arguments.get_run_settings();
+        let file_records = vec![
+            FileRecord::new(
+                &PathBuf::from("notes, draft.txt"),
+                &process_file_content(
+                    &PathBuf::from("notes, draft.txt"),
+                    b"a \n\tb\n",
+                    &options,
+                    &settings,
+                    true,
+                )
+                .unwrap()
+                .changes,
+                true,
+            ),
+            FileRecord::skipped(
+                &PathBuf::from("generated.txt"),
+                &SkipReason::IgnoreFileDirective,
+            ),
+        ];
+        assert_eq!(
+            to_csv(&file_records),
+            "path,line,change_type,detail\n\
+             \"notes, draft.txt\",1,RemovedTrailingWhitespace,Trailing whitespace would be removed.\n\
+             \"notes, draft.txt\",2,ReplacedTabWithSpaces,Tab would be replaced with 2 space(s).\n"
+        );
+        assert_eq!(to_csv(&[]), "path,line,change_type,detail\n");
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("foo.txt"), "foo.txt");
+        assert_eq!(csv_field("a,b.txt"), "\"a,b.txt\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn test_to_toml() {
+        let changes = vec![
+            Change::new(1, ChangeType::RemovedTrailingWhitespace),
+            Change::new(2, ChangeType::NewLineMarkerAddedToEndOfFile),
+        ];
+        let file_records = vec![
+            FileRecord::new(&PathBuf::from("src/foo.txt"), &changes, false),
+            FileRecord::skipped(
+                &PathBuf::from("generated.txt"),
+                &SkipReason::IgnoreFileDirective,
+            ),
+        ];
+        let summary = Summary {
+            check_only: false,
+            changed_files: 1,
+            unchanged_files: 3,
+            skipped_files: 1,
+        };
+
+        let document: toml::Table = to_toml(&file_records, &summary).parse().unwrap();
+        assert_eq!(
+            document.keys().collect::<Vec<&String>>(),
+            vec!["files", "summary"]
+        );
+
+        let summary = document["summary"].as_table().unwrap();
+        assert_eq!(summary["check_only"].as_bool(), Some(false));
+        assert_eq!(summary["changed_files"].as_integer(), Some(1));
+        assert_eq!(summary["unchanged_files"].as_integer(), Some(3));
+        assert_eq!(summary["skipped_files"].as_integer(), Some(1));
+
+        let files = document["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["file"].as_str(), Some("src/foo.txt"));
+        let changes = files[0]["changes"].as_array().unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0]["line"].as_integer(), Some(1));
+        assert_eq!(
+            changes[0]["type"].as_str(),
+            Some("RemovedTrailingWhitespace")
+        );
+        assert_eq!(
+            changes[1]["message"].as_str(),
+            Some("New line marker added to the end of the file.")
+        );
+        assert_eq!(files[1]["file"].as_str(), Some("generated.txt"));
+        assert_eq!(
+            files[1]["skipped"].as_str(),
+            Some("whitespace-format: ignore-file directive")
+        );
+    }
+
+    #[test]
+    fn test_add_change_types() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--check-only",
+            "--list-change-types",
+            "--new-line-marker=linux",
+            "--normalize-new-line-markers",
+            "--remove-trailing-whitespace",
+            "first.txt",
+            "second.txt",
+        ]);
+        let options = command_line_arguments.get_options();
+        let settings = command_line_arguments.get_run_settings();
+
+        let mut change_types: BTreeSet<&'static str> = BTreeSet::new();
+        for (file_name, content) in [
+            ("first.txt", b"a  \r\nb\r\n".as_slice()),
+            ("second.txt", b"c \nd \n".as_slice()),
+            ("third.txt", b"formatted\n".as_slice()),
+        ] {
+            let result = process_file_content(
+                &PathBuf::from(file_name),
+                content,
+                &options,
+                &settings,
+                true,
+            )
+            .unwrap();
+            add_change_types(&mut change_types, &result.changes);
+        }
+
+        assert_eq!(
+            change_types.into_iter().collect::<Vec<&str>>(),
+            vec!["RemovedTrailingWhitespace", "ReplacedNewLineMarker"]
+        );
+    }
+
+    #[test]
+    fn test_change_tally() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--check-only",
+            "--remove-trailing-whitespace",
+            "--remove-trailing-empty-lines",
+            "--replace-tabs-with-spaces=4",
+            "first.txt",
+            "second.txt",
+        ]);
+        let options = command_line_arguments.get_options();
+        let settings = command_line_arguments.get_run_settings();
+
+        let mut change_counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+        for (file_name, content) in [
+            ("first.txt", b"a  \n\tb \n\n".as_slice()),
+            ("second.txt", b"\tc\nd \n".as_slice()),
+        ] {
+            let result = process_file_content(
+                &PathBuf::from(file_name),
+                content,
+                &options,
+                &settings,
+                true,
+            )
+            .unwrap();
+            count_change_types(&mut change_counts, &result.changes);
+        }
+
+        assert_eq!(
+            change_tally(&change_counts, 2),
+            "6 changes across 2 files: 3 trailing whitespace, \
+             2 tab replacement(s), 1 trailing empty line removal(s)."
+        );
+
+        let mut change_counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+        change_counts.insert(ChangeType::RemovedTab.label(), 1);
+        assert_eq!(
+            change_tally(&change_counts, 1),
+            "1 change across 1 file: 1 tab removal(s)."
+        );
+    }
+
+    #[test]
+    fn test_sort_changes_by_line() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--remove-trailing-whitespace",
+            "--remove-trailing-empty-lines",
+            "--blank-lines-include-whitespace",
+            "--replace-tabs-with-spaces=2",
+            "file.txt",
+        ]);
+        let options = command_line_arguments.get_options();
+        let settings = command_line_arguments.get_run_settings();
+        let mut changes = process_file_content(
+            &PathBuf::from("file.txt"),
+            b"a \n\tb\n  \n\t\n",
+            &options,
+            &settings,
+            true,
+        )
+        .unwrap()
+        .changes;
+
+        // The removal of the trailing empty lines is made last.
+        let line_numbers: Vec<usize> = changes.iter().map(Change::line_number).collect();
+        assert_eq!(line_numbers, vec![1, 2, 3, 4, 4, 3]);
+
+        sort_changes_by_line(&mut changes);
+        assert_eq!(
+            changes,
+            vec![
+                Change::new(1, ChangeType::RemovedTrailingWhitespace).with_column(2),
+                Change::new(2, ChangeType::ReplacedTabWithSpaces(2)).with_column(1),
+                Change::new(3, ChangeType::RemovedTrailingWhitespace).with_column(1),
+                Change::new(3, ChangeType::RemovedTrailingEmptyLines),
+                Change::new(4, ChangeType::ReplacedTabWithSpaces(2)).with_column(1),
+                Change::new(4, ChangeType::RemovedTrailingWhitespace).with_column(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_files_by_option() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--check-only",
+            "--remove-trailing-whitespace",
+            "--remove-trailing-empty-lines",
+            "--replace-tabs-with-spaces=4",
+            "first.txt",
+            "second.txt",
+            "third.txt",
+            "fourth.txt",
+        ]);
+        let options = command_line_arguments.get_options();
+        let settings = command_line_arguments.get_run_settings();
+
+        let mut option_counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+        for (file_name, content) in [
+            ("first.txt", b"a  \nb \n\n".as_slice()),
+            ("second.txt", b"\tc\n\td\n".as_slice()),
+            ("third.txt", b"\te \n".as_slice()),
+            ("fourth.txt", b"f\n".as_slice()),
+        ] {
+            let result = process_file_content(
+                &PathBuf::from(file_name),
+                content,
+                &options,
+                &settings,
+                true,
+            )
+            .unwrap();
+            count_files_by_option(&mut option_counts, &result.changes);
+        }
+
+        assert_eq!(
+            option_counts.into_iter().collect::<Vec<(&str, usize)>>(),
+            vec![
+                ("--remove-trailing-empty-lines", 1),
+                ("--remove-trailing-whitespace", 2),
+                ("--replace-tabs-with-spaces", 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_to_json() {
+        let command_line_arguments = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--remove-trailing-whitespace",
+            "--new-line-marker=windows",
+            "src/",
+        ]);
+        let options = command_line_arguments.get_options();
+        let sub_options = CommandLineArguments::parse_from([
+            "whitespace-format",
+            "--remove-trailing-empty-lines",
+            "--new-line-marker=windows",
+            "src/",
+        ])
+        .get_options();
+        let files = vec![PathBuf::from("a.txt"), PathBuf::from("sub/b.txt")];
+
+        let plan: Value =
+            serde_json::from_str(&plan_to_json(&files, &[&options, &sub_options])).unwrap();
+        let entries = plan.as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["file"], "a.txt");
+        assert_eq!(entries[1]["file"], "sub/b.txt");
+        assert_eq!(entries[0]["options"]["remove_trailing_whitespace"], true);
+        assert_eq!(entries[0]["options"]["remove_trailing_empty_lines"], false);
+        assert_eq!(entries[1]["options"]["remove_trailing_whitespace"], false);
+        assert_eq!(entries[1]["options"]["remove_trailing_empty_lines"], true);
+        for entry in entries {
+            assert_eq!(entry["options"]["new_line_marker"], "windows");
+        }
+
+        assert_eq!(plan_to_json(&[], &[]), "[]");
+    }
+
+    #[test]
+    fn test_to_json_no_changes() {
+        let summary = Summary {
+            check_only: false,
+            changed_files: 0,
+            unchanged_files: 0,
+            skipped_files: 0,
+        };
+
+        let json: Value = serde_json::from_str(&to_json(&[], &summary)).unwrap();
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["summary"]["changed_files"], 0);
+    }
+}
@@ -1,4 +1,7 @@
+use sha2::Digest;
+use sha2::Sha256;
 use std::cmp::max;
+use std::io;
 
 /// Writer is an abstraction of an output buffer
 /// that can rewind back to a previous position.
@@ -16,6 +19,11 @@ pub trait Writer {
 
     /// Gets the current position.
     fn position(&self) -> usize;
+
+    /// Declares that the output before the position is final,
+    /// i.e., the writer will never be rewound before the position.
+    /// Writers that keep the whole output in memory can ignore it.
+    fn commit(&mut self, _position: usize) {}
 }
 
 /// Implementation of the Writer trait for a standard vector.
@@ -39,7 +47,7 @@ impl Writer for Vec<u8> {
 
 /// Writer that only counts the number of bytes written.
 /// The bytes are written to /dev/null.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct CountingWriter {
     /// Number of bytes currently stored in the "buffer".
     position: usize,
@@ -85,6 +93,148 @@ impl Writer for CountingWriter {
     }
 }
 
+/// Writer that streams the output to an `io::Write`.
+///
+/// Only the part of the output that can still be rewound is kept in memory.
+/// The rest is written out as soon as it is committed.
+/// The first I/O error is remembered and reported by `finish`.
+#[derive(Debug)]
+pub struct StreamingWriter<W: io::Write> {
+    /// The underlying output.
+    inner: W,
+
+    /// Output that was not committed yet.
+    buffer: Vec<u8>,
+
+    /// Position of the first byte of the buffer.
+    start: usize,
+
+    /// The first error that occurred while writing to the underlying output.
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> StreamingWriter<W> {
+    /// Factory method.
+    pub fn new(inner: W) -> Self {
+        StreamingWriter {
+            inner,
+            buffer: Vec::new(),
+            start: 0,
+            error: None,
+        }
+    }
+
+    /// Getter. Number of bytes kept in memory.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Writes out the rest of the output and returns the underlying output.
+    pub fn finish(mut self) -> io::Result<W> {
+        let position = self.position();
+        self.commit(position);
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: io::Write> Writer for StreamingWriter<W> {
+    fn write(&mut self, byte: u8) {
+        self.buffer.push(byte);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    fn rewind(&mut self, previous_position: usize) {
+        assert!(
+            previous_position >= self.start,
+            "Cannot rewind before the committed position."
+        );
+        self.buffer.truncate(previous_position - self.start);
+    }
+
+    fn position(&self) -> usize {
+        self.start + self.buffer.len()
+    }
+
+    fn commit(&mut self, position: usize) {
+        if position <= self.start {
+            return;
+        }
+        let length = position - self.start;
+        if self.error.is_none() {
+            if let Err(error) = self.inner.write_all(&self.buffer[..length]) {
+                self.error = Some(error);
+            }
+        }
+        self.buffer.drain(..length);
+        self.start = position;
+    }
+}
+
+/// Writer that computes the SHA-256 hash of the output without keeping it in memory.
+///
+/// Only the part of the output that can still be rewound is kept in memory,
+/// as in `StreamingWriter`. The rest is hashed as soon as it is committed.
+#[derive(Debug)]
+pub struct HashingWriter {
+    /// The output streamed into the hash.
+    inner: StreamingWriter<Sha256>,
+}
+
+impl HashingWriter {
+    /// Factory method.
+    pub fn new() -> Self {
+        HashingWriter {
+            inner: StreamingWriter::new(Sha256::new()),
+        }
+    }
+
+    /// Getter. Number of bytes kept in memory.
+    pub fn buffered(&self) -> usize {
+        self.inner.buffered()
+    }
+
+    /// Hashes the rest of the output and returns the hash as a hexadecimal string.
+    pub fn finish(self) -> String {
+        let hasher = self.inner.finish().expect("Writing to a hash never fails.");
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+impl Default for HashingWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Writer for HashingWriter {
+    fn write(&mut self, byte: u8) {
+        self.inner.write(byte);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.inner.write_bytes(bytes);
+    }
+
+    fn rewind(&mut self, previous_position: usize) {
+        self.inner.rewind(previous_position);
+    }
+
+    fn position(&self) -> usize {
+        self.inner.position()
+    }
+
+    fn commit(&mut self, position: usize) {
+        self.inner.commit(position);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,4 +272,120 @@ mod tests {
         assert_eq!(writer.position(), 2);
         assert_eq!(writer, vec![42, 7]);
     }
+
+    #[test]
+    fn test_streaming_writer() {
+        let mut writer = StreamingWriter::new(Vec::new());
+        writer.write_bytes(b"hello  ");
+        writer.rewind(5);
+        writer.write(b'\n');
+        assert_eq!(writer.position(), 6);
+        writer.commit(6);
+        assert_eq!(writer.buffered(), 0);
+        writer.write_bytes(b"world\n\n");
+        writer.commit(11);
+        assert_eq!(writer.position(), 13);
+        assert_eq!(writer.buffered(), 2);
+        writer.rewind(12);
+        assert_eq!(writer.finish().unwrap(), b"hello\nworld\n");
+    }
+
+    #[test]
+    fn test_streaming_writer_to_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let file_path = directory.path().join("output.txt");
+        let file = std::fs::File::create(&file_path).unwrap();
+        let mut writer = StreamingWriter::new(io::BufWriter::new(file));
+        assert_eq!(writer.position(), 0);
+        writer.write(42);
+        assert_eq!(writer.position(), 1);
+        writer.write(7);
+        assert_eq!(writer.position(), 2);
+        writer.write(13);
+        writer.rewind(2);
+        assert_eq!(writer.position(), 2);
+        writer.commit(1);
+        assert_eq!(writer.buffered(), 1);
+        writer.write_bytes(&[5, 6]);
+        writer.rewind(3);
+        assert_eq!(writer.position(), 3);
+        writer.finish().unwrap();
+        assert_eq!(std::fs::read(&file_path).unwrap(), vec![42, 7, 5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot rewind before the committed position.")]
+    fn test_streaming_writer_rewind_before_commit() {
+        let mut writer = StreamingWriter::new(Vec::new());
+        writer.write_bytes(b"hello\n");
+        writer.commit(6);
+        writer.rewind(5);
+    }
+
+    /// Operation on a writer.
+    enum Operation {
+        Write(u8),
+        WriteBytes(&'static [u8]),
+        Rewind(usize),
+        Commit(usize),
+    }
+
+    /// Applies the operation to the writer.
+    fn apply<T: Writer>(writer: &mut T, operation: &Operation) {
+        match operation {
+            Operation::Write(byte) => writer.write(*byte),
+            Operation::WriteBytes(bytes) => writer.write_bytes(bytes),
+            Operation::Rewind(position) => writer.rewind(*position),
+            Operation::Commit(position) => writer.commit(*position),
+        }
+    }
+
+    #[test]
+    fn test_hashing_writer() {
+        let sequences: [&[Operation]; 5] = [
+            &[],
+            &[Operation::WriteBytes(b"hello\n")],
+            &[
+                Operation::WriteBytes(b"hello  "),
+                Operation::Rewind(5),
+                Operation::Write(b'\n'),
+                Operation::Commit(6),
+                Operation::WriteBytes(b"world\n\n\n"),
+                Operation::Commit(11),
+                Operation::Rewind(12),
+            ],
+            &[
+                Operation::Write(42),
+                Operation::Write(7),
+                Operation::Rewind(0),
+                Operation::Write(13),
+                Operation::Commit(1),
+                Operation::WriteBytes(&[5, 6, 7]),
+                Operation::Rewind(3),
+                Operation::Commit(2),
+                Operation::Write(8),
+            ],
+            &[
+                Operation::WriteBytes(b"abc"),
+                Operation::Commit(3),
+                Operation::Commit(1),
+                Operation::Rewind(3),
+                Operation::WriteBytes(b"def"),
+            ],
+        ];
+        for operations in sequences {
+            let mut expected_writer: Vec<u8> = Vec::new();
+            let mut writer = HashingWriter::new();
+            for operation in operations {
+                apply(&mut expected_writer, operation);
+                apply(&mut writer, operation);
+                assert_eq!(writer.position(), expected_writer.position());
+            }
+            assert!(writer.buffered() <= expected_writer.len());
+            assert_eq!(
+                writer.finish(),
+                format!("{:x}", Sha256::digest(&expected_writer))
+            );
+        }
+    }
 }
@@ -0,0 +1,39 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_ascii() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::write(root.join("clean.txt"), "hello\n").unwrap();
+    fs::write(root.join("dirty.txt"), "hello  \n").unwrap();
+
+    for path in [root.join("clean.txt"), root.join("dirty.txt")] {
+        let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+            .arg("--check-only")
+            .arg("--color=off")
+            .arg("--ascii")
+            .arg("--remove-trailing-whitespace")
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.stdout.is_ascii(), "{:?}", output.stdout);
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(
+            stdout.contains("All done!\n") || stdout.contains("Oh no!\n"),
+            "{}",
+            stdout
+        );
+    }
+
+    // Without the option, the banner contains emoji.
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg("--remove-trailing-whitespace")
+        .arg(root.join("dirty.txt"))
+        .output()
+        .unwrap();
+    assert!(!output.stdout.is_ascii());
+}
@@ -0,0 +1,47 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_change_tally() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("a.txt"), "a  \n\tb \n\n\n").unwrap();
+    fs::write(directory.path().join("b.txt"), "\tc\n").unwrap();
+    fs::write(directory.path().join("c.txt"), "clean\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg("--remove-trailing-whitespace")
+        .arg("--remove-trailing-empty-lines")
+        .arg("--replace-tabs-with-spaces=2")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.ends_with(
+            "2 files would be reformatted, 1 file would be left unchanged.\n\
+             5 changes across 2 files: 2 tab replacement(s), \
+             2 trailing whitespace, 1 trailing empty line removal(s).\n"
+        ),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_change_tally_clean() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("a.txt"), "clean\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("across"), "{}", stdout);
+}
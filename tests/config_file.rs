@@ -0,0 +1,132 @@
+// Library imports
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::process::Output;
+
+fn run(directory: &Path, arguments: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .current_dir(directory)
+        .arg("--color=off")
+        .args(arguments)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_config_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("src")).unwrap();
+    fs::write(root.join("src/file.txt"), "hello  \n").unwrap();
+    fs::write(
+        root.join(".whitespace-format.toml"),
+        "remove_trailing_whitespace = true\n\
+         normalize_new_line_markers = true\n\
+         new_line_marker = \"windows\"\n",
+    )
+    .unwrap();
+
+    // The configuration file in the parent directory is found.
+    let output = run(root, &["--check-only", "src/file.txt"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("Trailing whitespace"), "{}", stdout);
+    assert!(stdout.contains("'\\r\\n'"), "{}", stdout);
+
+    // The command line overrides the configuration file.
+    let output = run(
+        root,
+        &["--check-only", "--new-line-marker=linux", "src/file.txt"],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("Trailing whitespace"), "{}", stdout);
+    assert!(!stdout.contains("'\\r\\n'"), "{}", stdout);
+
+    // The configuration file is not read with --no-config.
+    let output = run(root, &["--check-only", "--no-config", "src/file.txt"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    // The files are formatted according to the configuration file.
+    let output = run(root, &["src/"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        fs::read_to_string(root.join("src/file.txt")).unwrap(),
+        "hello\r\n"
+    );
+}
+
+#[test]
+fn test_malformed_config_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::write(root.join("file.txt"), "hello\n").unwrap();
+
+    for content in [
+        "remove_trailing_whitespace = \n",
+        "no_such_option = true\n",
+        "new_line_marker = \"unix\"\n",
+        "pipe_through = \"touch PWNED; cat\"\n",
+        "output_dir = \"out\"\n",
+    ] {
+        fs::write(root.join(".whitespace-format.toml"), content).unwrap();
+        let output = run(root, &["--check-only", "file.txt"]);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert_eq!(output.status.code(), Some(12), "{}", stderr);
+        assert!(stderr.contains("Invalid configuration file"), "{}", stderr);
+        assert!(stderr.contains(".whitespace-format.toml"), "{}", stderr);
+    }
+    // The command from the configuration file has not been run.
+    assert!(!root.join("PWNED").exists());
+    assert!(!root.join("out").exists());
+}
+
+#[test]
+fn test_nested_config_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir(root.join("sub")).unwrap();
+    fs::write(
+        root.join(".whitespace-format.toml"),
+        "remove_trailing_whitespace = true\n",
+    )
+    .unwrap();
+    // The trailing whitespace shows that the configuration file itself is not formatted.
+    fs::write(
+        root.join("sub/.whitespace-format.toml"),
+        "replace_tabs_with_spaces = 2   \n",
+    )
+    .unwrap();
+    for file_path in ["a.txt", "b.txt", "sub/c.txt"] {
+        fs::write(root.join(file_path), "hello  \n\tworld\n").unwrap();
+    }
+
+    // Each file is formatted according to the closest configuration file.
+    let output = run(root, &["--verbose", "."]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert!(stdout.contains("sub/.whitespace-format.toml"), "{}", stdout);
+    assert!(stdout.contains("Processing 3 file(s)..."), "{}", stdout);
+    assert_eq!(
+        fs::read_to_string(root.join("a.txt")).unwrap(),
+        "hello\n\tworld\n"
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("sub/c.txt")).unwrap(),
+        "hello  \n  world\n"
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("sub/.whitespace-format.toml")).unwrap(),
+        "replace_tabs_with_spaces = 2   \n"
+    );
+
+    // The paths on the command line can have different configuration files.
+    fs::write(root.join("b.txt"), "hello  \n\tworld\n").unwrap();
+    fs::write(root.join("sub/c.txt"), "hello  \n\tworld\n").unwrap();
+    let output = run(root, &["--check-only", "b.txt", "sub/c.txt"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("Trailing whitespace"), "{}", stdout);
+    assert!(stdout.contains("Tab would be replaced"), "{}", stdout);
+}
@@ -0,0 +1,82 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_dry_plan() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::create_dir(directory.path().join("sub")).unwrap();
+    fs::write(directory.path().join("a.txt"), "dirty  \n").unwrap();
+    fs::write(directory.path().join("sub/b.txt"), "dirty  \n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--dry-plan")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    let plan: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let files: Vec<&str> = plan
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["file"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        files,
+        vec![
+            directory.path().join("a.txt").to_str().unwrap(),
+            directory.path().join("sub/b.txt").to_str().unwrap(),
+        ]
+    );
+    assert_eq!(plan[0]["options"]["remove_trailing_whitespace"], true);
+
+    // The files are not changed.
+    assert_eq!(
+        fs::read_to_string(directory.path().join("a.txt")).unwrap(),
+        "dirty  \n"
+    );
+}
+
+#[test]
+fn test_dry_plan_nested_config_file() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::create_dir(directory.path().join("sub")).unwrap();
+    fs::write(
+        directory.path().join(".whitespace-format.toml"),
+        "remove_trailing_whitespace = true\n",
+    )
+    .unwrap();
+    fs::write(
+        directory.path().join("sub/.whitespace-format.toml"),
+        "replace_tabs_with_spaces = 2\n",
+    )
+    .unwrap();
+    fs::write(directory.path().join("a.txt"), "dirty  \n").unwrap();
+    fs::write(directory.path().join("sub/b.txt"), "\tdirty  \n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--dry-plan")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    // Each file has the options of the closest configuration file.
+    let plan: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(plan.as_array().unwrap().len(), 2);
+    assert_eq!(
+        plan[0]["file"],
+        directory.path().join("a.txt").to_str().unwrap()
+    );
+    assert_eq!(plan[0]["options"]["remove_trailing_whitespace"], true);
+    assert_eq!(plan[0]["options"]["replace_tabs_with_spaces"], -1);
+    assert_eq!(
+        plan[1]["file"],
+        directory.path().join("sub/b.txt").to_str().unwrap()
+    );
+    assert_eq!(plan[1]["options"]["remove_trailing_whitespace"], false);
+    assert_eq!(plan[1]["options"]["replace_tabs_with_spaces"], 2);
+}
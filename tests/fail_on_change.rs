@@ -0,0 +1,29 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_fail_on_change() {
+    let directory = tempfile::tempdir().unwrap();
+    let file_path = directory.path().join("file.txt");
+    fs::write(&file_path, "hello   \n").unwrap();
+
+    let run = || {
+        Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+            .arg("--color=off")
+            .arg("--fail-on-change")
+            .arg("--remove-trailing-whitespace")
+            .arg(&file_path)
+            .output()
+            .unwrap()
+    };
+
+    // The file is formatted and the run fails.
+    let output = run();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "hello\n");
+
+    // Nothing to change the second time.
+    let output = run();
+    assert_eq!(output.status.code(), Some(0));
+}
@@ -0,0 +1,53 @@
+// Library imports
+use std::fs;
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+#[test]
+fn test_files_from() {
+    let directory = tempfile::tempdir().unwrap();
+    let listed = directory.path().join("listed.txt");
+    let unlisted = directory.path().join("unlisted.txt");
+    let argument = directory.path().join("argument.txt");
+    for file_path in [&listed, &unlisted, &argument] {
+        fs::write(file_path, "hello  \n").unwrap();
+    }
+    let list_path = directory.path().join("list");
+    fs::write(&list_path, format!("# Comment\n\n{}\n\n", listed.display())).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--list-only")
+        .arg("--remove-trailing-whitespace")
+        .arg(format!("--files-from={}", list_path.display()))
+        .arg(&argument)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("{}\n{}\n", argument.display(), listed.display())
+    );
+}
+
+#[test]
+fn test_files_from_stdin() {
+    let directory = tempfile::tempdir().unwrap();
+    let listed = directory.path().join("listed.txt");
+    fs::write(&listed, "hello  \n").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--remove-trailing-whitespace")
+        .arg("--files-from=-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(format!("{}\n# {}\n", listed.display(), listed.display()).as_bytes())
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+    assert_eq!(fs::read(&listed).unwrap(), b"hello\n");
+}
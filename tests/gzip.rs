@@ -0,0 +1,51 @@
+// Library imports
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::Read;
+use std::io::Write;
+use std::process::Command;
+
+#[test]
+fn test_handle_gzip() {
+    let directory = tempfile::tempdir().unwrap();
+    let file_path = directory.path().join("corpus.txt.gz");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"hello  \nworld\t\n").unwrap();
+    fs::write(&file_path, encoder.finish().unwrap()).unwrap();
+
+    // Check-only mode compares the decompressed content.
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--handle-gzip")
+        .arg("--remove-trailing-whitespace")
+        .arg(&file_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--handle-gzip")
+        .arg("--remove-trailing-whitespace")
+        .arg(&file_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    // The written file is still valid gzip, and its decompressed content is formatted.
+    let mut content = String::new();
+    GzDecoder::new(fs::File::open(&file_path).unwrap())
+        .read_to_string(&mut content)
+        .unwrap();
+    assert_eq!(content, "hello\nworld\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--handle-gzip")
+        .arg("--remove-trailing-whitespace")
+        .arg(&file_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+}
@@ -0,0 +1,71 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_list_only() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("a.txt"), "hello  \n").unwrap();
+    fs::write(directory.path().join("b.txt"), "hello\n").unwrap();
+    fs::write(directory.path().join("c d.txt"), "hello\t\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--list-only")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{}\n{}\n",
+            directory.path().join("a.txt").display(),
+            directory.path().join("c d.txt").display()
+        )
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    // Nothing is changed.
+    assert_eq!(
+        fs::read(directory.path().join("a.txt")).unwrap(),
+        b"hello  \n"
+    );
+}
+
+#[test]
+fn test_list_only_null() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("a.txt"), "hello  \n").unwrap();
+    fs::write(directory.path().join("b c.txt"), "hello \n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--list-only")
+        .arg("--null")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{}\0{}\0",
+            directory.path().join("a.txt").display(),
+            directory.path().join("b c.txt").display()
+        )
+    );
+}
+
+#[test]
+fn test_list_only_clean() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("a.txt"), "hello\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--list-only")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+    assert_eq!(output.status.code(), Some(0));
+}
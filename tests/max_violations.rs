@@ -0,0 +1,93 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_max_violations() {
+    let directory = tempfile::tempdir().unwrap();
+    for i in 0..5 {
+        fs::write(directory.path().join(format!("{}.txt", i)), "dirty  \n").unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--max-violations=2")
+        .arg("--color=off")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout.matches("Would reformat").count(), 2, "{}", stdout);
+    assert!(
+        stdout.contains("2 files would be reformatted."),
+        "{}",
+        stdout
+    );
+    assert!(
+        stderr.contains("Stopped after finding 2 files that need formatting; 3 files not checked."),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_max_violations_not_reached() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("a.txt"), "dirty  \n").unwrap();
+    fs::write(directory.path().join("b.txt"), "clean\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--max-violations=2")
+        .arg("--color=off")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout.contains("1 file would be reformatted, 1 file would be left unchanged."),
+        "{}",
+        stdout
+    );
+    assert!(output.stderr.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_max_violations_does_not_read_files_after_limit() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("a.txt"), "dirty  \n").unwrap();
+    fs::write(directory.path().join("b.txt"), "dirty  \n").unwrap();
+    let unreadable_file = directory.path().join("z.txt");
+    fs::write(&unreadable_file, "clean\n").unwrap();
+    fs::set_permissions(&unreadable_file, fs::Permissions::from_mode(0o000)).unwrap();
+    if fs::read(&unreadable_file).is_ok() {
+        // The file is readable anyway, e.g. when running as root.
+        return;
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--max-violations=2")
+        .arg("--jobs=4")
+        .arg("--color=off")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert_eq!(stdout.matches("Would reformat").count(), 2, "{}", stdout);
+    assert!(!stderr.contains("Cannot read"), "{}", stderr);
+}
@@ -0,0 +1,44 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_new_line_marker_stats() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("a.txt"), b"a\r\nb\r\nc\n").unwrap();
+    fs::write(directory.path().join("b.txt"), b"d\re\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--color=off")
+        .arg("--new-line-marker-stats")
+        .arg("--normalize-new-line-markers")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(
+        stdout.contains("a.txt: 2 Windows (\\r\\n), 1 Linux (\\n), 0 MacOS (\\r) line ending(s)."),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("b.txt: 0 Windows (\\r\\n), 1 Linux (\\n), 1 MacOS (\\r) line ending(s)."),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.ends_with(
+            "Total: 2 Windows (\\r\\n), 2 Linux (\\n), 1 MacOS (\\r) line ending(s) in 2 file(s).\n"
+        ),
+        "{}",
+        stdout
+    );
+
+    // The files are not formatted.
+    assert_eq!(
+        fs::read(directory.path().join("a.txt")).unwrap(),
+        b"a\r\nb\r\nc\n"
+    );
+}
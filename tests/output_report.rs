@@ -0,0 +1,72 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_output_report() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path().join("tree");
+    fs::create_dir_all(root.join("nested")).unwrap();
+    fs::write(root.join("clean.txt"), "clean\n").unwrap();
+    fs::write(root.join("nested/dirty.txt"), "dirty  \nline\t\n").unwrap();
+    fs::write(root.join("binary.bin"), b"\x00\x01 \n").unwrap();
+    let report_path = directory.path().join("report.json");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--quiet")
+        .arg("--remove-trailing-whitespace")
+        .arg(format!("--output-report={}", report_path.display()))
+        .arg(&root)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&report_path).unwrap()).unwrap();
+    let entries = report.as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+
+    // The skipped file and the changed file, in the order in which they are processed.
+    assert_eq!(
+        entries[0]["file"],
+        root.join("binary.bin").display().to_string()
+    );
+    assert!(entries[0]["skipped"].is_string());
+    assert_eq!(
+        entries[1]["file"],
+        root.join("nested/dirty.txt").display().to_string()
+    );
+    let changes = entries[1]["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 2);
+    for (change, line) in changes.iter().zip([1, 2]) {
+        assert_eq!(change["line"], line);
+        assert_eq!(change["type"], "RemovedTrailingWhitespace");
+        assert!(change["message"].is_string());
+    }
+
+    // The summary is the last element.
+    let summary = &entries[2]["summary"];
+    assert_eq!(summary["check_only"], true);
+    assert_eq!(summary["changed_files"], 1);
+    assert_eq!(summary["unchanged_files"], 1);
+    assert_eq!(summary["skipped_files"], 1);
+}
+
+#[test]
+fn test_output_report_cannot_write_file() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("clean.txt"), "clean\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg(format!(
+            "--output-report={}",
+            directory.path().join("missing/report.json").display()
+        ))
+        .arg(directory.path().join("clean.txt"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(9));
+}
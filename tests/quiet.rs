@@ -0,0 +1,58 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_quiet_clean() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("a.txt"), "clean\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--quiet")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_quiet_dirty() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(directory.path().join("a.txt"), "dirty  \n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--quiet")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_quiet_list_only() {
+    let directory = tempfile::tempdir().unwrap();
+    let file_path = directory.path().join("a.txt");
+    fs::write(&file_path, "dirty  \n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--list-only")
+        .arg("--quiet")
+        .arg("--remove-trailing-whitespace")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("{}\n", file_path.display())
+    );
+}
@@ -0,0 +1,52 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[cfg(unix)]
+#[test]
+fn test_skip_tallies() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::write(root.join("text.txt"), "hello\n").unwrap();
+    fs::write(
+        root.join("image.bin"),
+        b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR",
+    )
+    .unwrap();
+    std::os::unix::fs::symlink(root.join("text.txt"), root.join("link.txt")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg("--verbose")
+        .arg(root)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(
+        stdout.contains("Skipped 1 symbolic link(s).\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Skipped 1 file(s): binary file.\n"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("special file(s)"), "{}", stdout);
+
+    // Nothing is tallied without --verbose or --stats.
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg(root)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        !stdout.contains("Skipped 1 symbolic link(s)."),
+        "{}",
+        stdout
+    );
+}
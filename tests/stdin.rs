@@ -0,0 +1,118 @@
+// Library imports
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+/// Runs the program with the given arguments and feeds the input to its standard input.
+fn run_with_stdin(arguments: &[&str], input: &[u8]) -> std::process::Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_stdin_json_report() {
+    let output = run_with_stdin(
+        &[
+            "--remove-trailing-whitespace",
+            "--remove-trailing-empty-lines",
+            "--format=json",
+            "-",
+        ],
+        b"a  \n\tb\n\n\n",
+    );
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"a\n\tb\n");
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(report[0]["file"], "<stdin>");
+    assert_eq!(report[0]["changes"][0]["type"], "RemovedTrailingWhitespace");
+    assert_eq!(report[0]["changes"][1]["type"], "RemovedTrailingEmptyLines");
+    assert_eq!(report[1]["summary"]["changed_files"], 1);
+}
+
+#[test]
+fn test_stdin_check_only() {
+    let output = run_with_stdin(
+        &["--check-only", "--remove-trailing-whitespace", "-"],
+        b"a  \n",
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "<stdin>: line 1, col 2: Trailing whitespace would be removed.\n"
+    );
+}
+
+#[test]
+fn test_stdin_with_other_paths() {
+    let output = run_with_stdin(&["--remove-trailing-whitespace", "-", "src/"], b"a\n");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_stdin_filename() {
+    // The file name decides whether the content is formatted.
+    let output = run_with_stdin(
+        &[
+            "--no-config",
+            "--remove-trailing-whitespace",
+            "--extensions=rs",
+            "--stdin-filename=notes.md",
+            "-",
+        ],
+        b"a  \n",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"a  \n");
+    assert_eq!(output.stderr, b"");
+
+    let output = run_with_stdin(
+        &[
+            "--no-config",
+            "--color=off",
+            "--remove-trailing-whitespace",
+            "--extensions=rs",
+            "--stdin-filename=src/main.rs",
+            "-",
+        ],
+        b"a  \n",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"a\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "src/main.rs: line 1, col 2: Trailing whitespace removed.\n"
+    );
+}
+
+#[test]
+fn test_stdin_filename_config_file() {
+    let directory = tempfile::tempdir().unwrap();
+    std::fs::write(
+        directory.path().join(".whitespace-format.toml"),
+        "remove_trailing_whitespace = true\n",
+    )
+    .unwrap();
+
+    // The configuration file is found from the file name, which does not exist.
+    let stdin_filename = directory.path().join("src/unsaved.txt");
+    let output = run_with_stdin(
+        &[
+            &format!("--stdin-filename={}", stdin_filename.display()),
+            "-",
+        ],
+        b"a  \n",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"a\n");
+}
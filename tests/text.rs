@@ -0,0 +1,29 @@
+// Library imports
+use std::process::Command;
+
+#[test]
+fn test_text() {
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--color=off")
+        .arg("--text=a  ")
+        .arg("--remove-trailing-whitespace")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, b"a");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "<text>: line 1, col 2: Trailing whitespace removed.\n"
+    );
+
+    // In check-only mode, the formatted text is not written.
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--color=off")
+        .arg("--check-only")
+        .arg("--text=a  ")
+        .arg("--remove-trailing-whitespace")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"");
+}
@@ -0,0 +1,34 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_throughput() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::write(root.join("a.txt"), "hello  \n").unwrap();
+    fs::write(root.join("b.txt"), "world\n".repeat(100)).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg("--remove-trailing-whitespace")
+        .arg("--stats")
+        .arg(root)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Read 608 byte(s) in "), "{}", stdout);
+    assert!(stdout.contains(" MB/s).\n"), "{}", stdout);
+
+    // The throughput is printed only with --stats.
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg("--remove-trailing-whitespace")
+        .arg(root)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("MB/s"), "{}", stdout);
+}
@@ -0,0 +1,58 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_timings() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::write(root.join("a.txt"), "hello  \n").unwrap();
+    fs::write(root.join("b.txt"), "world\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg("--remove-trailing-whitespace")
+        .arg("--timings")
+        .arg(root)
+        .output()
+        .unwrap();
+    // The timings do not change the exit code.
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let lines: Vec<&str> = stdout
+        .lines()
+        .skip_while(|line| *line != "Timings (slowest first):")
+        .skip(1)
+        .collect();
+    assert_eq!(lines.len(), 3, "{}", stdout);
+    let mut file_times: Vec<f64> = Vec::new();
+    for line in &lines[..2] {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        assert_eq!(fields.len(), 3, "{}", line);
+        file_times.push(fields[0].parse().unwrap());
+        assert_eq!(fields[1], "ms");
+        assert!(fields[2].ends_with(".txt"), "{}", line);
+    }
+    // The slowest file is first.
+    assert!(file_times[0] >= file_times[1]);
+    let total = lines[2]
+        .strip_prefix("Total: ")
+        .and_then(|line| line.strip_suffix(" ms in 2 file(s)."))
+        .unwrap();
+    total.parse::<f64>().unwrap();
+
+    // Nothing is printed without the option.
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg("--remove-trailing-whitespace")
+        .arg(root)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Timings"));
+}
@@ -0,0 +1,58 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_verify() {
+    let directory = tempfile::tempdir().unwrap();
+    let root = directory.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    let files: [(&str, &[u8]); 4] = [
+        ("a.txt", b"\r\n \t\r\rhello \t\r\r\n\n  \n\x0C\r"),
+        ("b.txt", b"  \t  \n\n"),
+        ("src/c.txt", b"\tx = 1 \r\n\ty = 2\t\n\n\n"),
+        ("src/d.txt", b""),
+    ];
+    for (file_name, content) in files {
+        fs::write(root.join(file_name), content).unwrap();
+    }
+
+    // Options that interact at the end of the file and in whitespace-only files.
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--verify")
+        .arg("--new-line-marker=windows")
+        .arg("--normalize-new-line-markers")
+        .arg("--add-new-line-marker-at-end-of-file")
+        .arg("--remove-trailing-whitespace")
+        .arg("--remove-trailing-empty-lines")
+        .arg("--normalize-whitespace-only-files=one-line")
+        .arg("--normalize-empty-files=one-line")
+        .arg("--replace-tabs-with-spaces=2")
+        .arg("--normalize-non-standard-whitespace=replace-with-space")
+        .arg(root)
+        .output()
+        .unwrap();
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The files are not written.
+    for (file_name, content) in files {
+        assert_eq!(fs::read(root.join(file_name)).unwrap(), content);
+    }
+
+    // The second pass would add the custom new line marker once more.
+    fs::write(root.join("e.txt"), "hello").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--verify")
+        .arg("--custom-new-line-marker=\\x1e")
+        .arg("--add-new-line-marker-at-end-of-file")
+        .arg(root.join("e.txt"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(11));
+    assert_eq!(fs::read(root.join("e.txt")).unwrap(), b"hello");
+}
@@ -0,0 +1,35 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_warn_double_encoded_bom() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(
+        directory.path().join("mojibake.txt"),
+        b"\xC3\xAF\xC2\xBB\xC2\xBFhello\n",
+    )
+    .unwrap();
+    fs::write(directory.path().join("bom.txt"), b"\xEF\xBB\xBFhello\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg("--warn-double-encoded-bom")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(
+        stderr
+            .matches("File starts with a double-encoded byte order mark")
+            .count(),
+        1,
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("mojibake.txt"), "{}", stderr);
+    assert!(!stderr.contains("bom.txt:"), "{}", stderr);
+    assert_eq!(output.status.code(), Some(0));
+}
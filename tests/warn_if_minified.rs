@@ -0,0 +1,37 @@
+// Library imports
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_warn_if_minified() {
+    let directory = tempfile::tempdir().unwrap();
+    fs::write(
+        directory.path().join("app.min.js"),
+        format!("var a=1;{}", "b=2;".repeat(1000)),
+    )
+    .unwrap();
+    fs::write(
+        directory.path().join("app.js"),
+        "var a = 1;\nvar b = 2;\n".repeat(100),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_whitespace-format"))
+        .arg("--check-only")
+        .arg("--color=off")
+        .arg("--warn-if-minified")
+        .arg("--add-new-line-marker-at-end-of-file")
+        .arg(directory.path())
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(
+        stderr.matches("File looks minified").count(),
+        1,
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("app.min.js"), "{}", stderr);
+    assert!(!stderr.contains("app.js:"), "{}", stderr);
+}